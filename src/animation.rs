@@ -0,0 +1,226 @@
+use crate::FractalState;
+use std::time::Instant;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Easing {
+    Linear,
+    EaseIn,
+    EaseOut,
+    EaseInOut,
+    // CSS-style control points (x1, y1, x2, y2); the end points are fixed at (0,0) and (1,1).
+    CubicBezier(f64, f64, f64, f64),
+}
+
+impl Easing {
+    pub const PRESETS: [Easing; 5] = [
+        Easing::Linear,
+        Easing::EaseIn,
+        Easing::EaseOut,
+        Easing::EaseInOut,
+        Easing::CubicBezier(0.25, 0.1, 0.25, 1.0),
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Easing::Linear => "Linear",
+            Easing::EaseIn => "Ease In",
+            Easing::EaseOut => "Ease Out",
+            Easing::EaseInOut => "Ease In/Out",
+            Easing::CubicBezier(..) => "Cubic Bezier",
+        }
+    }
+
+    pub fn apply(&self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match *self {
+            Easing::Linear => t,
+            Easing::EaseIn => t * t * t,
+            Easing::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Easing::EaseInOut => {
+                if t < 0.5 {
+                    4.0 * t * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(3) / 2.0
+                }
+            }
+            Easing::CubicBezier(x1, y1, x2, y2) => {
+                let s = solve_bezier_x(x1, x2, t);
+                bezier(y1, y2, s)
+            }
+        }
+    }
+}
+
+fn bezier(p1: f64, p2: f64, s: f64) -> f64 {
+    let u = 1.0 - s;
+    3.0 * u * u * s * p1 + 3.0 * u * s * s * p2 + s * s * s
+}
+
+// x(s) is monotonic for control x in [0, 1], so bisection always converges.
+fn solve_bezier_x(x1: f64, x2: f64, x: f64) -> f64 {
+    let (mut lo, mut hi) = (0.0, 1.0);
+    for _ in 0..40 {
+        let mid = (lo + hi) / 2.0;
+        if bezier(x1, x2, mid) < x {
+            lo = mid;
+        } else {
+            hi = mid;
+        }
+    }
+    (lo + hi) / 2.0
+}
+
+#[derive(Clone)]
+pub struct Keyframe {
+    pub time: f64,
+    pub state: FractalState,
+    // Easings describe the segment leaving this keyframe.
+    pub camera_easing: Easing,
+    pub param_easing: Easing,
+}
+
+#[derive(Clone, Default)]
+pub struct Timeline {
+    pub keyframes: Vec<Keyframe>,
+}
+
+impl Timeline {
+    pub fn duration(&self) -> f64 {
+        self.keyframes.last().map_or(0.0, |k| k.time)
+    }
+
+    pub fn push(&mut self, time: f64, state: FractalState) {
+        self.keyframes.push(Keyframe {
+            time,
+            state,
+            camera_easing: Easing::Linear,
+            param_easing: Easing::Linear,
+        });
+        self.sort();
+    }
+
+    pub fn sort(&mut self) {
+        self.keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+    }
+
+    pub fn sample(&self, t: f64) -> Option<FractalState> {
+        let first = self.keyframes.first()?;
+        if t <= first.time {
+            return Some(first.state.clone());
+        }
+
+        for pair in self.keyframes.windows(2) {
+            let (a, b) = (&pair[0], &pair[1]);
+            if t <= b.time {
+                let span = b.time - a.time;
+                let local = if span > 0.0 { (t - a.time) / span } else { 1.0 };
+                return Some(interpolate(
+                    &a.state,
+                    &b.state,
+                    a.camera_easing.apply(local),
+                    a.param_easing.apply(local),
+                ));
+            }
+        }
+
+        self.keyframes.last().map(|k| k.state.clone())
+    }
+}
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+    a + (b - a) * t
+}
+
+// Zoom is interpolated geometrically so each frame magnifies by the same factor,
+// and the center follows the view scale rather than time so panning keeps a
+// constant on-screen speed while zooming.
+pub fn interpolate(a: &FractalState, b: &FractalState, camera_t: f64, t: f64) -> FractalState {
+    let mut out = if t < 0.5 { a.clone() } else { b.clone() };
+    out.zoom = (lerp(a.zoom.ln(), b.zoom.ln(), camera_t)).exp();
+    let pan_t = if (a.zoom / b.zoom - 1.0).abs() > 1e-6 {
+        (1.0 / a.zoom - 1.0 / out.zoom) / (1.0 / a.zoom - 1.0 / b.zoom)
+    } else {
+        camera_t
+    };
+    out.center_x = lerp(a.center_x, b.center_x, pan_t);
+    out.center_y = lerp(a.center_y, b.center_y, pan_t);
+    out.power = lerp(a.power, b.power, t);
+    out.power_im = lerp(a.power_im, b.power_im, t);
+    out.secondary_param = lerp(a.secondary_param, b.secondary_param, t);
+    out.julia_seed = a.julia_seed + (b.julia_seed - a.julia_seed) * t;
+    out.phoenix_p = a.phoenix_p + (b.phoenix_p - a.phoenix_p) * t;
+    out.convergence_shading = lerp(a.convergence_shading as f64, b.convergence_shading as f64, t) as f32;
+    out.max_iter = lerp(a.max_iter as f64, b.max_iter as f64, t).round() as u32;
+    out.hue_offset = lerp(a.hue_offset as f64, b.hue_offset as f64, t) as f32;
+    out.saturation = lerp(a.saturation as f64, b.saturation as f64, t) as f32;
+    out.value = lerp(a.value as f64, b.value as f64, t) as f32;
+    out
+}
+
+pub struct Playback {
+    timeline: Timeline,
+    started: Instant,
+}
+
+impl Playback {
+    pub fn new(timeline: Timeline) -> Self {
+        Self { timeline, started: Instant::now() }
+    }
+
+    pub fn current(&self) -> Option<FractalState> {
+        self.timeline.sample(self.started.elapsed().as_secs_f64())
+    }
+
+    pub fn finished(&self) -> bool {
+        self.started.elapsed().as_secs_f64() >= self.timeline.duration()
+    }
+}
+
+pub enum MorphFrame {
+    Single(Box<FractalState>),
+    // Two renders blended by the given weight of the second.
+    CrossFade(Box<FractalState>, Box<FractalState>, f64),
+}
+
+pub struct Morph {
+    pub from: FractalState,
+    pub to: FractalState,
+    pub duration: f64,
+    started: Instant,
+}
+
+impl Morph {
+    pub fn new(from: FractalState, to: FractalState, duration: f64) -> Self {
+        Self { from, to, duration, started: Instant::now() }
+    }
+
+    pub fn progress(&self) -> f64 {
+        (self.started.elapsed().as_secs_f64() / self.duration).min(1.0)
+    }
+
+    pub fn finished(&self) -> bool {
+        self.progress() >= 1.0
+    }
+
+    // Matching fractal types morph their parameters; different types share the
+    // camera path and cross-fade between the two renders.
+    pub fn frame_at(&self, t: f64) -> MorphFrame {
+        let t = Easing::EaseInOut.apply(t);
+        let blended = interpolate(&self.from, &self.to, t, t);
+        if self.from.fractal_type == self.to.fractal_type {
+            return MorphFrame::Single(Box::new(blended));
+        }
+
+        let mut a = blended.clone();
+        a.fractal_type = self.from.fractal_type;
+        a.power = self.from.power;
+        a.power_im = self.from.power_im;
+        a.secondary_param = self.from.secondary_param;
+        let mut b = blended;
+        b.fractal_type = self.to.fractal_type;
+        b.power = self.to.power;
+        b.power_im = self.to.power_im;
+        b.secondary_param = self.to.secondary_param;
+        MorphFrame::CrossFade(Box::new(a), Box::new(b), t)
+    }
+}
@@ -0,0 +1,234 @@
+use crate::orbit::Orbit;
+use crate::postprocess::{luma, ColorBuffer};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SamplePattern {
+    Grid,
+    RotatedGrid,
+    Jittered,
+    BlueNoise,
+}
+
+impl SamplePattern {
+    pub const ALL: [SamplePattern; 4] = [
+        SamplePattern::Grid,
+        SamplePattern::RotatedGrid,
+        SamplePattern::Jittered,
+        SamplePattern::BlueNoise,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            SamplePattern::Grid => "Grid",
+            SamplePattern::RotatedGrid => "Rotated grid",
+            SamplePattern::Jittered => "Jittered",
+            SamplePattern::BlueNoise => "Blue noise",
+        }
+    }
+
+    // Offset of sample `index` of `count` for pixel (x, y), in pixels, within [-0.5, 0.5).
+    pub fn offset(&self, index: u32, count: u32, x: u32, y: u32) -> (f64, f64) {
+        let n = (count as f64).sqrt().ceil().max(1.0) as u32;
+        let (a, b) = (index % n, index / n);
+        let cells = n as f64;
+        match self {
+            SamplePattern::Grid => ((a as f64 + 0.5) / cells - 0.5, (b as f64 + 0.5) / cells - 0.5),
+            SamplePattern::RotatedGrid => {
+                // A lattice tilted so that every sample gets its own row and column
+                // on the n*n sub-grid; n = 2 is the classic RGSS pattern.
+                let total = (n * n) as f64;
+                let sx = (a * n + b) as f64;
+                let sy = (b * n + (n - 1 - a)) as f64;
+                ((sx + 0.5) / total - 0.5, (sy + 0.5) / total - 0.5)
+            }
+            SamplePattern::Jittered => (
+                (a as f64 + hash(x, y, index, 0)) / cells - 0.5,
+                (b as f64 + hash(x, y, index, 1)) / cells - 0.5,
+            ),
+            SamplePattern::BlueNoise => {
+                // R2 low-discrepancy points, shifted per pixel by interleaved gradient
+                // noise so the remaining error is high-frequency grain rather than blotches.
+                const PLASTIC: f64 = 1.324_717_957_244_746;
+                let i = index as f64 + 0.5;
+                let shift_x = gradient_noise(x, y);
+                let shift_y = gradient_noise(y + 17, x + 31);
+                ((i / PLASTIC + shift_x).fract() - 0.5, (i / (PLASTIC * PLASTIC) + shift_y).fract() - 0.5)
+            }
+        }
+    }
+}
+
+fn hash(x: u32, y: u32, index: u32, axis: u32) -> f64 {
+    let mut h = x.wrapping_mul(0x8da6_b343)
+        ^ y.wrapping_mul(0xd816_3841)
+        ^ index.wrapping_mul(0xcb1a_b31f)
+        ^ axis.wrapping_mul(0x1656_67b1);
+    h ^= h >> 16;
+    h = h.wrapping_mul(0x7feb_352d);
+    h ^= h >> 15;
+    h = h.wrapping_mul(0x846c_a68b);
+    h ^= h >> 16;
+    h as f64 / 4_294_967_296.0
+}
+
+// Jimenez's interleaved gradient noise.
+fn gradient_noise(x: u32, y: u32) -> f64 {
+    (52.982_918_9 * (0.067_110_56 * x as f64 + 0.005_837_15 * y as f64).fract()).fract()
+}
+
+pub const SAMPLE_COUNTS: [u32; 6] = [1, 4, 9, 16, 25, 64];
+
+// Samples every pixel gets before the adaptive pass decides where to refine.
+pub const ADAPTIVE_INITIAL_SAMPLES: u32 = 4;
+
+// How the adaptive pass picks the pixels that get the full sample count.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Refinement {
+    // Luminance variance over the first few samples of each pixel.
+    Variance,
+    // Escape counts that differ between neighbours in a single-sample pass.
+    EscapeEdges,
+}
+
+impl Refinement {
+    pub const ALL: [Refinement; 2] = [Refinement::Variance, Refinement::EscapeEdges];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Refinement::Variance => "Luminance variance",
+            Refinement::EscapeEdges => "Escape count edges",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct AntialiasSettings {
+    pub pattern: SamplePattern,
+    pub samples: u32,
+    // Refine only the pixels the refinement criterion flags.
+    pub adaptive: bool,
+    pub refinement: Refinement,
+    pub variance_threshold: f32,
+    // Smallest difference in escape count between neighbours that counts as an edge.
+    pub iteration_threshold: u32,
+}
+
+impl Default for AntialiasSettings {
+    fn default() -> Self {
+        Self {
+            pattern: SamplePattern::Jittered,
+            samples: 1,
+            adaptive: false,
+            refinement: Refinement::Variance,
+            variance_threshold: 0.002,
+            iteration_threshold: 1,
+        }
+    }
+}
+
+// Running per-pixel sums of sample passes.
+pub struct Accumulator {
+    width: u32,
+    height: u32,
+    color: Vec<[f32; 3]>,
+    alpha: Vec<f32>,
+    luma: Vec<f32>,
+    luma_sq: Vec<f32>,
+    count: Vec<u32>,
+}
+
+impl Accumulator {
+    pub fn new(width: u32, height: u32) -> Self {
+        let len = (width * height) as usize;
+        Self {
+            width,
+            height,
+            color: vec![[0.0; 3]; len],
+            alpha: vec![0.0; len],
+            luma: vec![0.0; len],
+            luma_sq: vec![0.0; len],
+            count: vec![0; len],
+        }
+    }
+
+    // `selected` restricts the pass to the pixels that were actually sampled.
+    pub fn add(&mut self, pass: &ColorBuffer, selected: Option<&[bool]>) {
+        for (i, count) in self.count.iter_mut().enumerate() {
+            if selected.is_some_and(|s| !s[i]) {
+                continue;
+            }
+            let c = pass.pixels[i];
+            let l = luma(c);
+            for (sum, v) in self.color[i].iter_mut().zip(c) {
+                *sum += v;
+            }
+            self.alpha[i] += pass.alpha[i];
+            self.luma[i] += l;
+            self.luma_sq[i] += l * l;
+            *count += 1;
+        }
+    }
+
+    // Pixels whose own sample variance, or that of a 4-neighbour, exceeds the threshold.
+    // Looking at neighbours catches thin filaments that the first samples straddled.
+    pub fn needs_refinement(&self, threshold: f32) -> Vec<bool> {
+        let variance: Vec<f32> = (0..self.count.len())
+            .map(|i| {
+                let n = self.count[i].max(1) as f32;
+                let mean = self.luma[i] / n;
+                (self.luma_sq[i] / n - mean * mean).max(0.0)
+            })
+            .collect();
+
+        let (width, height) = (self.width as usize, self.height as usize);
+        (0..variance.len())
+            .map(|i| {
+                let (x, y) = (i % width, i / width);
+                let mut local = variance[i];
+                if x > 0 {
+                    local = local.max(variance[i - 1]);
+                }
+                if x + 1 < width {
+                    local = local.max(variance[i + 1]);
+                }
+                if y > 0 {
+                    local = local.max(variance[i - width]);
+                }
+                if y + 1 < height {
+                    local = local.max(variance[i + width]);
+                }
+                local > threshold
+            })
+            .collect()
+    }
+
+    pub fn resolve(&self) -> ColorBuffer {
+        let mut out = ColorBuffer::new(self.width, self.height);
+        for (i, (pixel, alpha)) in out.pixels.iter_mut().zip(out.alpha.iter_mut()).enumerate() {
+            let n = self.count[i].max(1) as f32;
+            *pixel = self.color[i].map(|c| c / n);
+            *alpha = self.alpha[i] / n;
+        }
+        out
+    }
+}
+
+// Pixels whose escape count differs from a 4-neighbour's by at least `threshold`, or
+// that escaped or found a root where the neighbour did not. One sample per pixel is
+// enough to find them, so flat regions are never supersampled.
+pub fn escape_edges(orbits: &[Orbit], width: u32, height: u32, threshold: u32) -> Vec<bool> {
+    let differs = |a: &Orbit, b: &Orbit| {
+        a.escaped != b.escaped || a.root != b.root || a.iterations.abs_diff(b.iterations) >= threshold
+    };
+    let (width, height) = (width as usize, height as usize);
+    (0..orbits.len())
+        .map(|i| {
+            let (x, y) = (i % width, i / width);
+            let orbit = &orbits[i];
+            (x > 0 && differs(orbit, &orbits[i - 1]))
+                || (x + 1 < width && differs(orbit, &orbits[i + 1]))
+                || (y > 0 && differs(orbit, &orbits[i - width]))
+                || (y + 1 < height && differs(orbit, &orbits[i + width]))
+        })
+        .collect()
+}
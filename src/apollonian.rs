@@ -0,0 +1,143 @@
+use num_complex::Complex64;
+use rayon::prelude::*;
+
+#[derive(Clone, Copy)]
+struct Circle {
+    center: Complex64,
+    // Negative for the outer circle, which encloses the others.
+    radius: f64,
+}
+
+impl Circle {
+    fn curvature(&self) -> f64 {
+        1.0 / self.radius
+    }
+}
+
+// Gasket grown from three mutually tangent circles with the given curvatures and
+// the outer circle tangent to all three, scaled to the unit disk.
+#[derive(Clone)]
+pub struct Apollonian {
+    pub curvatures: [f64; 3],
+    pub iterations: u32,
+}
+
+impl Default for Apollonian {
+    fn default() -> Self {
+        Self { curvatures: [1.0, 1.0, 1.0], iterations: 100 }
+    }
+}
+
+impl Apollonian {
+    // Curvature of the enclosing circle by Descartes' theorem; negative as it
+    // encloses the others.
+    fn outer_curvature(&self) -> f64 {
+        let [k1, k2, k3] = self.curvatures.map(|k| k.max(1e-3));
+        k1 + k2 + k3 - 2.0 * (k1 * k2 + k2 * k3 + k3 * k1).sqrt()
+    }
+
+    // When one circle is much smaller than the other two, the fourth tangent
+    // circle degenerates into a line or sits inside the three.
+    pub fn is_bounded(&self) -> bool {
+        self.outer_curvature() < -1e-9
+    }
+
+    // The four Descartes circles, and for each the dual circle through the three
+    // tangency points of the others. Inverting in the duals generates the gasket.
+    fn circles(&self) -> Option<([Circle; 4], [Circle; 4])> {
+        if !self.is_bounded() {
+            return None;
+        }
+        let [k1, k2, k3] = self.curvatures.map(|k| k.max(1e-3));
+        let (r1, r2, r3) = (1.0 / k1, 1.0 / k2, 1.0 / k3);
+        // Circle 1 at the origin, circle 2 on the real axis, circle 3 above them.
+        let (d12, d13, d23) = (r1 + r2, r1 + r3, r2 + r3);
+        let x = (d13 * d13 - d23 * d23 + d12 * d12) / (2.0 * d12);
+        let z = [
+            Complex64::new(0.0, 0.0),
+            Complex64::new(d12, 0.0),
+            Complex64::new(x, (d13 * d13 - x * x).max(0.0).sqrt()),
+        ];
+        let k = [k1, k2, k3];
+
+        // The complex Descartes theorem places the enclosing circle's center.
+        let k4 = self.outer_curvature();
+        let sum = z[0] * k1 + z[1] * k2 + z[2] * k3;
+        let root = (z[0] * z[1] * k1 * k2 + z[1] * z[2] * k2 * k3 + z[0] * z[2] * k1 * k3).sqrt() * 2.0;
+        // Of the two signs, the outer circle is the one internally tangent to circle 1.
+        let fits = |center: Complex64| ((center - z[0]).norm() - (1.0 / k4.abs() - r1)).abs();
+        let (plus, minus) = ((sum + root) / k4, (sum - root) / k4);
+        let c4 = if fits(plus) < fits(minus) { plus } else { minus };
+
+        let scale = k4.abs();
+        let mut circles = [Circle { center: Complex64::new(0.0, 0.0), radius: -1.0 }; 4];
+        for ((circle, z), k) in circles.iter_mut().zip(z).zip(k) {
+            *circle = Circle { center: (z - c4) * scale, radius: scale / k };
+        }
+
+        // Tangency point of circles a and b, weighted by signed curvature.
+        let touch = |a: &Circle, b: &Circle| {
+            (a.center * a.curvature() + b.center * b.curvature()) / (a.curvature() + b.curvature())
+        };
+        let mut duals = circles;
+        for (i, dual) in duals.iter_mut().enumerate() {
+            let others: Vec<&Circle> = circles.iter().enumerate().filter(|&(j, _)| j != i).map(|(_, c)| c).collect();
+            *dual = circumcircle(
+                touch(others[0], others[1]),
+                touch(others[1], others[2]),
+                touch(others[0], others[2]),
+            );
+        }
+        Some((circles, duals))
+    }
+
+    // Distance estimate to the gasket and the number of inversions it took.
+    fn estimate(&self, mut p: Complex64, circles: &[Circle; 4], duals: &[Circle; 4]) -> (f64, u32) {
+        let mut scale = 1.0;
+        let mut n = 0;
+        while n < self.iterations {
+            let Some(dual) = duals.iter().find(|d| (p - d.center).norm_sqr() < d.radius * d.radius) else {
+                break;
+            };
+            let offset = p - dual.center;
+            let factor = dual.radius * dual.radius / offset.norm_sqr();
+            p = dual.center + offset * factor;
+            scale *= factor;
+            n += 1;
+        }
+        // Every Descartes circle lies in the gasket, so the nearest one bounds the distance.
+        let distance = circles
+            .iter()
+            .map(|c| ((p - c.center).norm() - c.radius.abs()).abs())
+            .fold(f64::MAX, f64::min);
+        (distance / scale, n)
+    }
+}
+
+fn circumcircle(a: Complex64, b: Complex64, c: Complex64) -> Circle {
+    let d = 2.0 * (a.re * (b.im - c.im) + b.re * (c.im - a.im) + c.re * (a.im - b.im));
+    let (a2, b2, c2) = (a.norm_sqr(), b.norm_sqr(), c.norm_sqr());
+    let center = Complex64::new(
+        (a2 * (b.im - c.im) + b2 * (c.im - a.im) + c2 * (a.im - b.im)) / d,
+        (a2 * (c.re - b.re) + b2 * (a.re - c.re) + c2 * (b.re - a.re)) / d,
+    );
+    Circle { center, radius: (a - center).norm() }
+}
+
+// Per-pixel distance to the gasket in pixels, with the inversion count.
+pub fn render(gasket: &Apollonian, width: u32, height: u32, origin: (f64, f64), step: (f64, f64)) -> Vec<(f64, u32)> {
+    let Some((circles, duals)) = gasket.circles() else {
+        return vec![(f64::MAX, 0); (width * height) as usize];
+    };
+    (0..height)
+        .into_par_iter()
+        .flat_map_iter(|py| {
+            let (circles, duals) = (&circles, &duals);
+            (0..width).map(move |px| {
+                let p = Complex64::new(origin.0 + (px as f64 + 0.5) * step.0, origin.1 + (py as f64 + 0.5) * step.1);
+                let (distance, inversions) = gasket.estimate(p, circles, duals);
+                (distance / step.0, inversions)
+            })
+        })
+        .collect()
+}
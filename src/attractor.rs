@@ -0,0 +1,177 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rayon::prelude::*;
+
+// Points discarded at the start of each chain while it falls onto the attractor.
+const SETTLE_POINTS: usize = 100;
+// Iterations a random candidate gets before it is judged.
+const SEARCH_POINTS: usize = 4000;
+const SEARCH_ATTEMPTS: usize = 1000;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum AttractorKind {
+    Clifford,
+    DeJong,
+}
+
+impl AttractorKind {
+    pub const ALL: [AttractorKind; 2] = [AttractorKind::Clifford, AttractorKind::DeJong];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AttractorKind::Clifford => "Clifford",
+            AttractorKind::DeJong => "De Jong",
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct Attractor {
+    pub kind: AttractorKind,
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+}
+
+impl Attractor {
+    // Clifford:  x' = sin(a y) + c cos(a x),  y' = sin(b x) + d cos(b y)
+    // De Jong:   x' = sin(a y) - cos(b x),    y' = sin(c x) - cos(d y)
+    #[inline]
+    pub fn step(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        match self.kind {
+            AttractorKind::Clifford => (
+                (self.a * y).sin() + self.c * (self.a * x).cos(),
+                (self.b * x).sin() + self.d * (self.b * y).cos(),
+            ),
+            AttractorKind::DeJong => (
+                (self.a * y).sin() - (self.b * x).cos(),
+                (self.c * x).sin() - (self.d * y).cos(),
+            ),
+        }
+    }
+
+    // Half-width of a square that always contains the orbit.
+    pub fn extent(&self) -> f64 {
+        match self.kind {
+            AttractorKind::Clifford => 1.0 + self.c.abs().max(self.d.abs()),
+            AttractorKind::DeJong => 2.0,
+        }
+    }
+
+    // Zoom and center (x, y) framing the bounding square.
+    pub fn view(&self) -> (f64, f64, f64) {
+        let scale = self.extent() * 1.05;
+        (2.5 / scale, 0.75 * scale, 0.0)
+    }
+
+    // Rolls parameters until the orbit is chaotic and fills a reasonable part of
+    // the plane, so fixed points, cycles and thin curves are skipped.
+    pub fn randomize(&mut self, seed: u64) -> bool {
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        for _ in 0..SEARCH_ATTEMPTS {
+            let candidate = Attractor {
+                kind: self.kind,
+                a: rng.gen_range(-3.0..3.0),
+                b: rng.gen_range(-3.0..3.0),
+                c: rng.gen_range(-3.0..3.0),
+                d: rng.gen_range(-3.0..3.0),
+            };
+            if candidate.is_interesting() {
+                *self = candidate;
+                return true;
+            }
+        }
+        false
+    }
+
+    // Positive Lyapunov exponent, estimated from a shadow orbit, and a spread
+    // covering at least a tenth of the bounding square on each axis.
+    fn is_interesting(&self) -> bool {
+        let mut p = (0.1, 0.1);
+        let mut q = (0.1 + 1e-8, 0.1);
+        let mut lyapunov = 0.0;
+        let (mut min, mut max) = ((f64::MAX, f64::MAX), (f64::MIN, f64::MIN));
+        for n in 0..SEARCH_POINTS {
+            p = self.step(p);
+            q = self.step(q);
+            let d = ((q.0 - p.0).powi(2) + (q.1 - p.1).powi(2)).sqrt();
+            if d == 0.0 {
+                return false;
+            }
+            if n >= SETTLE_POINTS {
+                lyapunov += (d / 1e-8).ln();
+                min = (min.0.min(p.0), min.1.min(p.1));
+                max = (max.0.max(p.0), max.1.max(p.1));
+            }
+            // Renormalize the shadow back to the reference separation.
+            q = (p.0 + (q.0 - p.0) * 1e-8 / d, p.1 + (q.1 - p.1) * 1e-8 / d);
+        }
+        let lyapunov = lyapunov / (SEARCH_POINTS - SETTLE_POINTS) as f64;
+        let spread = 0.2 * self.extent();
+        lyapunov > 0.01 && max.0 - min.0 > spread && max.1 - min.1 > spread
+    }
+}
+
+#[derive(Clone)]
+pub struct AttractorSettings {
+    pub attractor: Attractor,
+    // Orbit points per output pixel.
+    pub density: f64,
+    pub search_seed: u64,
+    pub search_failed: bool,
+}
+
+impl Default for AttractorSettings {
+    fn default() -> Self {
+        Self {
+            attractor: Attractor { kind: AttractorKind::Clifford, a: -1.4, b: 1.6, c: 1.0, d: 0.7 },
+            density: 20.0,
+            search_seed: 0,
+            search_failed: false,
+        }
+    }
+}
+
+// Hit counts of the orbit; each worker follows its own chain from a random start.
+pub fn accumulate(
+    attractor: &Attractor,
+    width: u32,
+    height: u32,
+    origin: (f64, f64),
+    step: (f64, f64),
+    density: f64,
+    workers: usize,
+) -> Vec<u32> {
+    let len = (width * height) as usize;
+    let per_worker = (len as f64 * density) as usize / workers.max(1) + 1;
+
+    (0..workers.max(1))
+        .into_par_iter()
+        .map(|worker| {
+            let mut rng = ChaCha8Rng::seed_from_u64(worker as u64);
+            let mut hits = vec![0u32; len];
+            let mut point = (rng.gen_range(-0.5..0.5), rng.gen_range(-0.5..0.5));
+            for n in 0..per_worker + SETTLE_POINTS {
+                point = attractor.step(point);
+                if n < SETTLE_POINTS {
+                    continue;
+                }
+                let x = (point.0 - origin.0) / step.0;
+                let y = (point.1 - origin.1) / step.1;
+                if x >= 0.0 && y >= 0.0 && x < width as f64 && y < height as f64 {
+                    hits[y as usize * width as usize + x as usize] += 1;
+                }
+            }
+            hits
+        })
+        .reduce(
+            || vec![0u32; len],
+            |mut a, b| {
+                for (a, b) in a.iter_mut().zip(b) {
+                    *a += b;
+                }
+                a
+            },
+        )
+}
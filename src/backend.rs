@@ -0,0 +1,226 @@
+use crate::escape;
+use crate::gpu::EscapeKernel;
+use parking_lot::Mutex;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Backend {
+    Scalar,
+    Simd,
+    Gpu,
+}
+
+impl Backend {
+    pub const ALL: [Backend; 3] = [Backend::Scalar, Backend::Simd, Backend::Gpu];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Backend::Scalar => "Scalar",
+            Backend::Simd => "SIMD",
+            Backend::Gpu => "GPU",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Backend> {
+        Self::ALL.into_iter().find(|b| b.name() == name)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum SimdLevel {
+    Sse41,
+    Avx2,
+    Avx512,
+    Neon,
+}
+
+impl SimdLevel {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SimdLevel::Sse41 => "SSE4.1",
+            SimdLevel::Avx2 => "AVX2",
+            SimdLevel::Avx512 => "AVX-512",
+            SimdLevel::Neon => "NEON",
+        }
+    }
+
+    // Best instruction set the running CPU supports, if any.
+    pub fn detect() -> Option<SimdLevel> {
+        #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
+        {
+            if is_x86_feature_detected!("avx512f") {
+                return Some(SimdLevel::Avx512);
+            }
+            if is_x86_feature_detected!("avx2") {
+                return Some(SimdLevel::Avx2);
+            }
+            if is_x86_feature_detected!("sse4.1") {
+                return Some(SimdLevel::Sse41);
+            }
+        }
+        if cfg!(target_arch = "aarch64") {
+            return Some(SimdLevel::Neon);
+        }
+        None
+    }
+}
+
+// Held open while the GPU backend is active.
+pub struct GpuContext {
+    pub device: wgpu::Device,
+    pub queue: wgpu::Queue,
+    pub kernel: EscapeKernel,
+    // Why the last GPU render failed and fell back to the CPU, if it did.
+    pub failure: Mutex<Option<String>>,
+}
+
+// Probed once at startup; switching backends later only has to open the device.
+pub struct BackendManager {
+    pub simd: Option<SimdLevel>,
+    adapter: Option<wgpu::Adapter>,
+    pub adapter_name: Option<String>,
+    pub selected: Backend,
+    pub active: Backend,
+    pub gpu: Option<Arc<GpuContext>>,
+    // Shown in the Performance panel when the selected backend could not be used.
+    pub notice: Option<String>,
+}
+
+impl BackendManager {
+    pub fn detect() -> Self {
+        let instance = wgpu::Instance::default();
+        let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference: wgpu::PowerPreference::HighPerformance,
+            ..Default::default()
+        }));
+        let adapter_name = adapter.as_ref().map(|a| {
+            let info = a.get_info();
+            format!("{} ({:?})", info.name, info.backend)
+        });
+
+        let mut manager = Self {
+            simd: SimdLevel::detect(),
+            adapter,
+            adapter_name,
+            selected: Backend::Scalar,
+            active: Backend::Scalar,
+            gpu: None,
+            notice: None,
+        };
+        let saved = load_choice().unwrap_or_else(|| manager.best_cpu());
+        manager.switch(saved);
+        manager
+    }
+
+    pub fn is_available(&self, backend: Backend) -> bool {
+        match backend {
+            Backend::Scalar => true,
+            Backend::Simd => self.has_vector_kernel(),
+            Backend::Gpu => self.adapter.is_some(),
+        }
+    }
+
+    fn best_cpu(&self) -> Backend {
+        if self.has_vector_kernel() { Backend::Simd } else { Backend::Scalar }
+    }
+
+    // The SIMD backend's kernel is written for AVX; other instruction sets are only
+    // reported.
+    fn has_vector_kernel(&self) -> bool {
+        self.simd.is_some() && escape::has_vector_kernel()
+    }
+
+    // Makes `backend` active, falling back to the best CPU backend when it is
+    // missing or fails to initialize. The user's choice is remembered either way.
+    pub fn switch(&mut self, backend: Backend) {
+        self.selected = backend;
+        self.notice = None;
+        self.gpu = None;
+        self.active = match backend {
+            Backend::Gpu => match self.open_gpu() {
+                Ok(context) => {
+                    self.gpu = Some(Arc::new(context));
+                    Backend::Gpu
+                }
+                Err(err) => {
+                    let fallback = self.best_cpu();
+                    self.notice = Some(format!("GPU unavailable ({}); using {}", err, fallback.name()));
+                    fallback
+                }
+            },
+            Backend::Simd if !self.has_vector_kernel() => {
+                self.notice = Some("The SIMD backend needs AVX, which this CPU lacks; using Scalar".to_string());
+                Backend::Scalar
+            }
+            other => other,
+        };
+        save_choice(backend);
+    }
+
+    fn open_gpu(&self) -> Result<GpuContext, String> {
+        let adapter = self.adapter.as_ref().ok_or("no adapter found")?;
+        let (device, queue) = pollster::block_on(adapter.request_device(
+            &wgpu::DeviceDescriptor { label: Some("fractal"), ..Default::default() },
+            None,
+        ))
+        .map_err(|e| e.to_string())?;
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let kernel = EscapeKernel::new(&device);
+        if let Some(err) = pollster::block_on(device.pop_error_scope()) {
+            return Err(err.to_string());
+        }
+        Ok(GpuContext { device, queue, kernel, failure: Mutex::new(None) })
+    }
+}
+
+fn hostname() -> String {
+    std::env::var("HOSTNAME")
+        .or_else(|_| std::env::var("COMPUTERNAME"))
+        .ok()
+        .or_else(|| fs::read_to_string("/etc/hostname").ok())
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "default".to_string())
+}
+
+// Per-user settings directory, shared with the palette library.
+pub fn config_dir() -> Option<PathBuf> {
+    let dir = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("APPDATA").map(PathBuf::from))
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(dir.join("fractolrs"))
+}
+
+fn config_path() -> Option<PathBuf> {
+    Some(config_dir()?.join("backend.txt"))
+}
+
+// One `hostname=Backend` line per machine, so a shared home directory keeps
+// separate choices for each host.
+fn load_choice() -> Option<Backend> {
+    let contents = fs::read_to_string(config_path()?).ok()?;
+    let host = hostname();
+    contents.lines().find_map(|line| {
+        let (name, backend) = line.split_once('=')?;
+        (name == host).then_some(backend).and_then(Backend::from_name)
+    })
+}
+
+fn save_choice(backend: Backend) {
+    let Some(path) = config_path() else { return };
+    let host = hostname();
+    let mut lines: Vec<String> = fs::read_to_string(&path)
+        .unwrap_or_default()
+        .lines()
+        .filter(|line| line.split_once('=').is_some_and(|(name, _)| name != host))
+        .map(str::to_string)
+        .collect();
+    lines.push(format!("{}={}", host, backend.name()));
+    if let Some(dir) = path.parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    let _ = fs::write(path, lines.join("\n") + "\n");
+}
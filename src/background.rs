@@ -0,0 +1,104 @@
+use crate::color::srgb_to_linear;
+use crate::postprocess::ColorBuffer;
+use image::{ImageBuffer, Rgb};
+use std::sync::Arc;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum BackgroundKind {
+    None,
+    Solid,
+    Gradient,
+    Image,
+}
+
+impl BackgroundKind {
+    pub const ALL: [BackgroundKind; 4] = [
+        BackgroundKind::None,
+        BackgroundKind::Solid,
+        BackgroundKind::Gradient,
+        BackgroundKind::Image,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BackgroundKind::None => "None",
+            BackgroundKind::Solid => "Solid color",
+            BackgroundKind::Gradient => "Vertical gradient",
+            BackgroundKind::Image => "Image",
+        }
+    }
+}
+
+// Which part of the base render is cut away to reveal the background.
+#[derive(Clone, Copy, PartialEq)]
+pub enum TransparentRegion {
+    Interior,
+    Exterior,
+}
+
+#[derive(Clone)]
+pub struct Background {
+    pub kind: BackgroundKind,
+    pub region: TransparentRegion,
+    pub top: [f32; 3],
+    pub bottom: [f32; 3],
+    pub path: String,
+    pub image: Option<Arc<ImageBuffer<Rgb<u8>, Vec<u8>>>>,
+}
+
+impl Default for Background {
+    fn default() -> Self {
+        Self {
+            kind: BackgroundKind::None,
+            region: TransparentRegion::Interior,
+            top: [0.05, 0.05, 0.15],
+            bottom: [0.0, 0.0, 0.0],
+            path: String::new(),
+            image: None,
+        }
+    }
+}
+
+impl Background {
+    pub fn load_image(&mut self) -> image::ImageResult<()> {
+        self.image = Some(Arc::new(image::open(self.path.trim())?.to_rgb8()));
+        Ok(())
+    }
+
+    // The image is stretched to cover the whole frame, so it tracks any export size.
+    // Colors are picked in sRGB and returned linear to match the render.
+    fn color_at(&self, x: u32, y: u32, width: u32, height: u32) -> Option<[f32; 3]> {
+        let v = (y as f32 + 0.5) / height as f32;
+        match self.kind {
+            BackgroundKind::None => None,
+            BackgroundKind::Solid => Some(self.top.map(srgb_to_linear)),
+            BackgroundKind::Gradient => Some(std::array::from_fn(|c| {
+                srgb_to_linear(self.top[c] + (self.bottom[c] - self.top[c]) * v)
+            })),
+            BackgroundKind::Image => {
+                let img = self.image.as_ref().filter(|img| img.width() > 0 && img.height() > 0)?;
+                let sx = ((x as f32 + 0.5) / width as f32 * img.width() as f32) as u32;
+                let sy = (v * img.height() as f32) as u32;
+                let pixel = img.get_pixel(sx.min(img.width() - 1), sy.min(img.height() - 1));
+                Some(pixel.0.map(|c| srgb_to_linear(c as f32 / 255.0)))
+            }
+        }
+    }
+
+    pub fn underlay(&self, buffer: &mut ColorBuffer) {
+        if self.kind == BackgroundKind::None {
+            return;
+        }
+        let (width, height) = (buffer.width, buffer.height);
+        for (i, (pixel, alpha)) in buffer.pixels.iter_mut().zip(buffer.alpha.iter_mut()).enumerate() {
+            let x = i as u32 % width;
+            let y = i as u32 / width;
+            if let Some(bg) = self.color_at(x, y, width, height) {
+                for (p, b) in pixel.iter_mut().zip(bg) {
+                    *p = *p * *alpha + b * (1.0 - *alpha);
+                }
+                *alpha = 1.0;
+            }
+        }
+    }
+}
@@ -0,0 +1,7 @@
+use crate::FractalState;
+
+#[derive(Clone)]
+pub struct Bookmark {
+    pub name: String,
+    pub state: FractalState,
+}
@@ -0,0 +1,115 @@
+use crate::FractalState;
+use num_complex::Complex64;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rayon::prelude::*;
+
+// Orbits are only worth sampling from c inside this square; everything else escapes at once.
+const SAMPLE_EXTENT: f64 = 2.0;
+
+#[derive(Clone)]
+pub struct BuddhabrotSettings {
+    // Random c values per output pixel.
+    pub density: f64,
+    pub exposure: f32,
+    pub gamma: f32,
+    // Nebulabrot: separate passes feed red, green and blue, each with its own
+    // iteration limit, so long-lived orbits and short ones get different colors.
+    pub nebula: bool,
+    pub channel_iterations: [u32; 3],
+}
+
+impl Default for BuddhabrotSettings {
+    fn default() -> Self {
+        Self { density: 2.0, exposure: 1.0, gamma: 2.0, nebula: false, channel_iterations: [2000, 200, 20] }
+    }
+}
+
+// Points in the main cardioid or period-2 bulb never escape, so they add nothing.
+fn in_main_components(c: Complex64) -> bool {
+    let q = (c.re - 0.25).powi(2) + c.im * c.im;
+    q * (q + (c.re - 0.25)) <= 0.25 * c.im * c.im || (c.re + 1.0).powi(2) + c.im * c.im <= 0.0625
+}
+
+// Hit counts for every pixel of the view, accumulated from the orbits of randomly
+// chosen c that escape within `max_iter`. Each worker has its own histogram and
+// seed, so the result is the same from render to render.
+pub fn accumulate(
+    state: &FractalState,
+    width: u32,
+    height: u32,
+    origin: (f64, f64),
+    step: (f64, f64),
+    density: f64,
+    workers: usize,
+) -> Vec<u32> {
+    let (power, max_iter) = (state.power, state.max_iter);
+    let len = (width * height) as usize;
+    let total = (len as f64 * density) as usize;
+    let per_worker = total / workers.max(1) + 1;
+
+    (0..workers.max(1))
+        .into_par_iter()
+        .map(|worker| {
+            let mut rng = ChaCha8Rng::seed_from_u64(worker as u64);
+            let mut hits = vec![0u32; len];
+            let mut orbit = Vec::with_capacity(max_iter as usize);
+            for _ in 0..per_worker {
+                let c = Complex64::new(
+                    rng.gen_range(-SAMPLE_EXTENT..SAMPLE_EXTENT),
+                    rng.gen_range(-SAMPLE_EXTENT..SAMPLE_EXTENT),
+                );
+                if power == 2.0 && in_main_components(c) {
+                    continue;
+                }
+
+                orbit.clear();
+                let mut z = Complex64::new(0.0, 0.0);
+                let mut escaped = false;
+                for _ in 0..max_iter {
+                    z = if power == 2.0 { z * z + c } else { z.powf(power) + c };
+                    if z.norm_sqr() > 4.0 {
+                        escaped = true;
+                        break;
+                    }
+                    orbit.push(z);
+                }
+                if !escaped {
+                    continue;
+                }
+
+                for z in &orbit {
+                    let x = (z.re - origin.0) / step.0;
+                    let y = (z.im - origin.1) / step.1;
+                    if x >= 0.0 && y >= 0.0 && x < width as f64 && y < height as f64 {
+                        hits[y as usize * width as usize + x as usize] += 1;
+                    }
+                }
+            }
+            hits
+        })
+        .reduce(
+            || vec![0u32; len],
+            |mut a, b| {
+                for (a, b) in a.iter_mut().zip(b) {
+                    *a += b;
+                }
+                a
+            },
+        )
+}
+
+// Maps hit counts to 0..1. The brightest few pixels would otherwise set the scale
+// and leave the rest dark, so counts are normalized by a high percentile instead.
+pub fn tone_map(hits: &[u32], exposure: f32, gamma: f32) -> Vec<f32> {
+    let mut nonzero: Vec<u32> = hits.iter().copied().filter(|&h| h > 0).collect();
+    if nonzero.is_empty() {
+        return vec![0.0; hits.len()];
+    }
+    let index = (nonzero.len() - 1) * 995 / 1000;
+    let (_, reference, _) = nonzero.select_nth_unstable(index);
+    let scale = exposure / (*reference).max(1) as f32;
+    hits.iter()
+        .map(|&h| (h as f32 * scale).min(1.0).powf(1.0 / gamma))
+        .collect()
+}
@@ -0,0 +1,303 @@
+use crate::icc;
+use image::{ImageBuffer, Pixel};
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+// Color buffers hold linear-light values; these convert to and from the sRGB curve.
+pub fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.003_130_8 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+// Chroma or saturation below which a color counts as gray and has no hue of its own.
+const GRAY_CHROMA: f32 = 1e-4;
+
+// Space palettes blend and hues rotate in. Straight sRGB blends dip in brightness
+// between stops; HSV blends turn the short way round the hue wheel but stretch
+// over green; OKLab is built to be perceptually even, and OKLCh is its polar form.
+#[derive(Clone, Copy, PartialEq)]
+pub enum ColorSpace {
+    Rgb,
+    Hsv,
+    Oklab,
+    Oklch,
+}
+
+impl ColorSpace {
+    pub const ALL: [ColorSpace; 4] = [ColorSpace::Rgb, ColorSpace::Hsv, ColorSpace::Oklab, ColorSpace::Oklch];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColorSpace::Rgb => "RGB",
+            ColorSpace::Hsv => "HSV",
+            ColorSpace::Oklab => "OKLab",
+            ColorSpace::Oklch => "OKLCh",
+        }
+    }
+
+    // Blend of two sRGB colors, `f` of the way from `a` to `b`, returned as sRGB.
+    pub fn mix(&self, a: [f32; 3], b: [f32; 3], f: f32) -> [f32; 3] {
+        let mut ends = [self.to_coords(a), self.to_coords(b)];
+        self.unwrap_hues(&mut ends);
+        let [a, b] = ends;
+        self.to_srgb(std::array::from_fn(|c| a[c] + (b[c] - a[c]) * f))
+    }
+
+    // Coordinates of an sRGB color in this space, hues in degrees.
+    pub fn to_coords(self, srgb: [f32; 3]) -> [f32; 3] {
+        match self {
+            ColorSpace::Rgb => srgb,
+            ColorSpace::Hsv => srgb_to_hsv(srgb),
+            ColorSpace::Oklab => srgb_to_oklab(srgb),
+            ColorSpace::Oklch => oklab_to_oklch(srgb_to_oklab(srgb)),
+        }
+    }
+
+    // sRGB color at coordinates in this space, clipped to the gamut.
+    pub fn to_srgb(self, coords: [f32; 3]) -> [f32; 3] {
+        match self {
+            ColorSpace::Rgb => coords.map(|c| c.clamp(0.0, 1.0)),
+            ColorSpace::Hsv => {
+                let [h, s, v] = coords;
+                hsv_to_srgb(h.rem_euclid(360.0), s.clamp(0.0, 1.0), v.clamp(0.0, 1.0))
+            }
+            ColorSpace::Oklab => oklab_to_srgb(coords),
+            ColorSpace::Oklch => oklab_to_srgb(oklch_to_oklab(coords)),
+        }
+    }
+
+    // Indices of the hue and chroma coordinates, for the polar spaces.
+    fn polar_axes(&self) -> Option<(usize, usize)> {
+        match self {
+            ColorSpace::Rgb | ColorSpace::Oklab => None,
+            ColorSpace::Hsv => Some((0, 1)),
+            ColorSpace::Oklch => Some((2, 1)),
+        }
+    }
+
+    // Rewrites the hues of a run of colors so each is within half a turn of the one
+    // before, so blending along the run takes the short way round. A gray borrows
+    // the hue of the nearest color that has one.
+    pub fn unwrap_hues(&self, coords: &mut [[f32; 3]]) {
+        let Some((hue, chroma)) = self.polar_axes() else {
+            return;
+        };
+        let colored: Vec<usize> = (0..coords.len()).filter(|&i| coords[i][chroma] >= GRAY_CHROMA).collect();
+        for i in 0..coords.len() {
+            if coords[i][chroma] < GRAY_CHROMA
+                && let Some(&j) = colored.iter().min_by_key(|&&j| j.abs_diff(i))
+            {
+                coords[i][hue] = coords[j][hue];
+            }
+        }
+        for i in 1..coords.len() {
+            let turn = (coords[i][hue] - coords[i - 1][hue] + 540.0).rem_euclid(360.0) - 180.0;
+            coords[i][hue] = coords[i - 1][hue] + turn;
+        }
+    }
+
+    // sRGB color for a hue in degrees at the given saturation and value. The OK
+    // spaces hold lightness steady as the hue turns, with saturation as chroma.
+    pub fn hue(&self, h: f32, s: f32, v: f32) -> [f32; 3] {
+        match self {
+            ColorSpace::Rgb | ColorSpace::Hsv => hsv_to_srgb(h, s, v),
+            ColorSpace::Oklab | ColorSpace::Oklch => oklab_to_srgb(oklch_to_oklab([0.75 * v, 0.13 * s * v, h])),
+        }
+    }
+}
+
+pub fn hsv_to_srgb(h: f32, s: f32, v: f32) -> [f32; 3] {
+    let c = v * s;
+    let x = c * (1.0 - ((h / 60.0) % 2.0 - 1.0).abs());
+    let m = v - c;
+
+    let (r, g, b) = match h as i32 {
+        h if h < 60 => (c, x, 0.0),
+        h if h < 120 => (x, c, 0.0),
+        h if h < 180 => (0.0, c, x),
+        h if h < 240 => (0.0, x, c),
+        h if h < 300 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+
+    [r + m, g + m, b + m]
+}
+
+fn srgb_to_hsv([r, g, b]: [f32; 3]) -> [f32; 3] {
+    let max = r.max(g).max(b);
+    let delta = max - r.min(g).min(b);
+    let h = if delta <= 0.0 {
+        0.0
+    } else if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    let s = if max > 0.0 { delta / max } else { 0.0 };
+    [h, s, max]
+}
+
+// Björn Ottosson's OKLab, from and to sRGB through linear light.
+fn srgb_to_oklab(srgb: [f32; 3]) -> [f32; 3] {
+    let [r, g, b] = srgb.map(srgb_to_linear);
+    let l = (0.412_221_5 * r + 0.536_332_5 * g + 0.051_446 * b).cbrt();
+    let m = (0.211_903_5 * r + 0.680_699_5 * g + 0.107_397 * b).cbrt();
+    let s = (0.088_302_46 * r + 0.281_718_8 * g + 0.629_978_7 * b).cbrt();
+    [
+        0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+        1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+        0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+    ]
+}
+
+// Out-of-gamut results are clipped per channel.
+fn oklab_to_srgb([lightness, a, b]: [f32; 3]) -> [f32; 3] {
+    let l = (lightness + 0.396_337_78 * a + 0.215_803_76 * b).powi(3);
+    let m = (lightness - 0.105_561_35 * a - 0.063_854_17 * b).powi(3);
+    let s = (lightness - 0.089_484_18 * a - 1.291_485_5 * b).powi(3);
+    [
+        4.076_741_7 * l - 3.307_711_6 * m + 0.230_969_94 * s,
+        -1.268_438 * l + 2.609_757_4 * m - 0.341_319_4 * s,
+        -0.004_196_086_3 * l - 0.703_418_6 * m + 1.707_614_7 * s,
+    ]
+    .map(|c| linear_to_srgb(c.clamp(0.0, 1.0)))
+}
+
+fn oklab_to_oklch([l, a, b]: [f32; 3]) -> [f32; 3] {
+    [l, a.hypot(b), b.atan2(a).to_degrees()]
+}
+
+fn oklch_to_oklab([l, c, h]: [f32; 3]) -> [f32; 3] {
+    let h = h.to_radians();
+    [l, c * h.cos(), c * h.sin()]
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ColorProfile {
+    Srgb,
+    DisplayP3,
+    AdobeRgb,
+}
+
+impl ColorProfile {
+    pub const ALL: [ColorProfile; 3] = [ColorProfile::Srgb, ColorProfile::DisplayP3, ColorProfile::AdobeRgb];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColorProfile::Srgb => "sRGB",
+            ColorProfile::DisplayP3 => "Display P3",
+            ColorProfile::AdobeRgb => "Adobe RGB (1998)",
+        }
+    }
+
+    // Linear sRGB to this profile's linear primaries; all three share the D65 white point.
+    fn matrix(&self) -> [[f32; 3]; 3] {
+        match self {
+            ColorProfile::Srgb => [[1.0, 0.0, 0.0], [0.0, 1.0, 0.0], [0.0, 0.0, 1.0]],
+            ColorProfile::DisplayP3 => [
+                [0.822_462_1, 0.177_538, 0.0],
+                [0.033_194_2, 0.966_805_8, 0.0],
+                [0.017_082_7, 0.072_397_4, 0.910_519_9],
+            ],
+            ColorProfile::AdobeRgb => [
+                [0.715_162_7, 0.284_837_3, 0.0],
+                [0.0, 1.0, 0.0],
+                [0.0, 0.041_170_5, 0.958_829_5],
+            ],
+        }
+    }
+
+    fn transfer(&self, c: f32) -> f32 {
+        match self {
+            ColorProfile::Srgb | ColorProfile::DisplayP3 => linear_to_srgb(c),
+            ColorProfile::AdobeRgb => c.powf(256.0 / 563.0),
+        }
+    }
+
+    // Linear sRGB in, encoded values for this profile out. Out-of-gamut values are clipped.
+    pub fn encode(&self, linear: [f32; 3]) -> [f32; 3] {
+        let m = self.matrix();
+        std::array::from_fn(|row| {
+            let v = m[row][0] * linear[0] + m[row][1] * linear[1] + m[row][2] * linear[2];
+            self.transfer(v.clamp(0.0, 1.0))
+        })
+    }
+
+    // Red, green and blue primaries as CIE xy; the white point is D65 for all three.
+    fn primaries(&self) -> [(f64, f64); 3] {
+        match self {
+            ColorProfile::Srgb => [(0.64, 0.33), (0.30, 0.60), (0.15, 0.06)],
+            ColorProfile::DisplayP3 => [(0.680, 0.320), (0.265, 0.690), (0.150, 0.060)],
+            ColorProfile::AdobeRgb => [(0.64, 0.33), (0.21, 0.71), (0.15, 0.06)],
+        }
+    }
+
+    // For the PNG cHRM chunk.
+    fn chromaticities(&self) -> png::SourceChromaticities {
+        let [red, green, blue] = self.primaries().map(|(x, y)| (x as f32, y as f32));
+        png::SourceChromaticities::new((D65.0 as f32, D65.1 as f32), red, green, blue)
+    }
+
+    // ICC profile with the same primaries and curve `encode` uses.
+    fn icc_profile(&self) -> Vec<u8> {
+        let curve = match self {
+            ColorProfile::Srgb | ColorProfile::DisplayP3 => {
+                icc::Curve::Table((0..1024).map(|i| (srgb_to_linear(i as f32 / 1023.0) * 65535.0).round() as u16).collect())
+            }
+            ColorProfile::AdobeRgb => icc::Curve::Gamma(563.0 / 256.0),
+        };
+        icc::rgb_profile(self.name(), D65, self.primaries(), &curve)
+    }
+}
+
+// CIE xy of the D65 white point.
+const D65: (f64, f64) = (0.3127, 0.3290);
+
+// Writes a PNG tagged with the profile: an sRGB chunk for sRGB, otherwise an embedded
+// ICC profile, with cHRM primaries and the closest pure-power gAMA for viewers that
+// only read those.
+// RGB or RGBA, by the image's pixel type.
+pub fn save_png<P: Pixel<Subpixel = u8>>(
+    img: &ImageBuffer<P, Vec<u8>>,
+    path: impl AsRef<Path>,
+    profile: ColorProfile,
+) -> io::Result<()> {
+    write_png(img, BufWriter::new(File::create(path)?), profile)
+}
+
+pub fn write_png<P: Pixel<Subpixel = u8>, W: Write>(
+    img: &ImageBuffer<P, Vec<u8>>,
+    out: W,
+    profile: ColorProfile,
+) -> io::Result<()> {
+    let mut info = png::Info::with_size(img.width(), img.height());
+    info.color_type = if P::CHANNEL_COUNT == 4 { png::ColorType::Rgba } else { png::ColorType::Rgb };
+    info.bit_depth = png::BitDepth::Eight;
+    match profile {
+        ColorProfile::Srgb => info.srgb = Some(png::SrgbRenderingIntent::Perceptual),
+        ColorProfile::DisplayP3 | ColorProfile::AdobeRgb => {
+            let gamma = if profile == ColorProfile::AdobeRgb { 256.0 / 563.0 } else { 1.0 / 2.2 };
+            info.source_gamma = Some(png::ScaledFloat::new(gamma));
+            info.source_chromaticities = Some(profile.chromaticities());
+            info.icc_profile = Some(profile.icc_profile().into());
+        }
+    }
+    let encoder = png::Encoder::with_info(out, info).map_err(io::Error::other)?;
+    let mut writer = encoder.write_header().map_err(io::Error::other)?;
+    writer.write_image_data(img.as_raw()).map_err(io::Error::other)?;
+    Ok(())
+}
@@ -0,0 +1,244 @@
+use num_complex::Complex64;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ColoringMode {
+    EscapeTime,
+    // Hue by the iteration at which |z| came closest to 0, for interior and exterior alike.
+    AtomDomains,
+    // Shade by how close the orbit came to the trap shape.
+    OrbitTrap,
+    // Shade by the exterior distance estimate, which brings out thin filaments.
+    DistanceEstimate,
+    // Palette position from the average of sin(k·arg z) over the orbit.
+    StripeAverage,
+    // Palette position from the average of where each |z| falls within its triangle inequality bounds.
+    TriangleInequality,
+    // Palette position from the average turning angle between consecutive orbit steps.
+    CurvatureAverage,
+    // Split escaped pixels by the sign of Im z at escape.
+    BinaryDecomposition,
+    // Escape-time colors lit as a relief, with normals from the distance estimate.
+    SlopeShading,
+    // Texels of a loaded image, addressed by the final iterate or the trap approach.
+    Texture,
+    // Each RGB channel a sine wave over the escape count.
+    SineWaves,
+    // Palette position from arg z at escape, which follows the field lines.
+    FinalAngle,
+    // Palette position from a sum of e^(-|z|) while diverging and e^(-1/|Δz|) while
+    // converging, smooth for both kinds of orbit.
+    ExponentialSmoothing,
+    // Palette position from log |dz/dc| at the last iterate, which peaks along the
+    // chaotic boundary on both sides of it.
+    DerivativeMagnitude,
+}
+
+impl ColoringMode {
+    pub const ALL: [ColoringMode; 14] = [
+        ColoringMode::EscapeTime,
+        ColoringMode::AtomDomains,
+        ColoringMode::OrbitTrap,
+        ColoringMode::DistanceEstimate,
+        ColoringMode::StripeAverage,
+        ColoringMode::TriangleInequality,
+        ColoringMode::CurvatureAverage,
+        ColoringMode::BinaryDecomposition,
+        ColoringMode::SlopeShading,
+        ColoringMode::Texture,
+        ColoringMode::SineWaves,
+        ColoringMode::FinalAngle,
+        ColoringMode::ExponentialSmoothing,
+        ColoringMode::DerivativeMagnitude,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ColoringMode::EscapeTime => "Escape time",
+            ColoringMode::AtomDomains => "Atom domains",
+            ColoringMode::OrbitTrap => "Orbit trap",
+            ColoringMode::DistanceEstimate => "Distance estimate",
+            ColoringMode::StripeAverage => "Stripe average",
+            ColoringMode::TriangleInequality => "Triangle inequality average",
+            ColoringMode::CurvatureAverage => "Curvature average",
+            ColoringMode::BinaryDecomposition => "Binary decomposition",
+            ColoringMode::SlopeShading => "Slope shading",
+            ColoringMode::Texture => "Image texture",
+            ColoringMode::SineWaves => "Sine waves",
+            ColoringMode::FinalAngle => "Final angle",
+            ColoringMode::ExponentialSmoothing => "Exponential smoothing",
+            ColoringMode::DerivativeMagnitude => "Derivative magnitude",
+        }
+    }
+
+    // Modes that sum a term over the orbit and color by its smoothed mean.
+    pub fn is_average(&self) -> bool {
+        matches!(
+            self,
+            ColoringMode::StripeAverage | ColoringMode::TriangleInequality | ColoringMode::CurvatureAverage
+        )
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum TrapShape {
+    Point,
+    Line,
+    Circle,
+    Cross,
+}
+
+impl TrapShape {
+    pub const ALL: [TrapShape; 4] = [TrapShape::Point, TrapShape::Line, TrapShape::Circle, TrapShape::Cross];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TrapShape::Point => "Point",
+            TrapShape::Line => "Line",
+            TrapShape::Circle => "Circle",
+            TrapShape::Cross => "Cross",
+        }
+    }
+}
+
+// A shape in the plane; the orbit's closest approach to it colors the pixel.
+#[derive(Clone, Copy, PartialEq)]
+pub struct OrbitTrap {
+    pub shape: TrapShape,
+    pub center: Complex64,
+    // Circle radius.
+    pub radius: f64,
+    // Direction of the line and of the cross's first arm, in radians.
+    pub angle: f64,
+}
+
+impl Default for OrbitTrap {
+    fn default() -> Self {
+        Self { shape: TrapShape::Cross, center: Complex64::new(0.0, 0.0), radius: 0.5, angle: 0.0 }
+    }
+}
+
+impl OrbitTrap {
+    #[inline]
+    pub fn distance(&self, z: Complex64) -> f64 {
+        let offset = z - self.center;
+        // Offset in the trap's own frame, with the line along the real axis.
+        let local = offset * Complex64::from_polar(1.0, -self.angle);
+        match self.shape {
+            TrapShape::Point => offset.norm(),
+            TrapShape::Line => local.im.abs(),
+            TrapShape::Circle => (offset.norm() - self.radius).abs(),
+            TrapShape::Cross => local.re.abs().min(local.im.abs()),
+        }
+    }
+}
+
+// Blinn-Phong light on a slope-shaded relief, seen from straight above.
+#[derive(Clone, Copy, PartialEq)]
+pub struct Lighting {
+    // Direction of the light, in degrees.
+    pub azimuth: f64,
+    pub elevation: f64,
+    pub ambient: f32,
+    pub diffuse: f32,
+    pub specular: f32,
+    pub shininess: f32,
+}
+
+impl Default for Lighting {
+    fn default() -> Self {
+        Self { azimuth: 45.0, elevation: 45.0, ambient: 0.2, diffuse: 0.8, specular: 0.3, shininess: 20.0 }
+    }
+}
+
+impl Lighting {
+    // Lit linear color of a surface whose downhill direction in the plane is
+    // `normal` (unit length), sloping at 45°. Highlights are white.
+    pub fn shade(&self, base: [f32; 3], normal: Complex64) -> [f32; 3] {
+        let (azimuth, elevation) = (self.azimuth.to_radians(), self.elevation.to_radians());
+        let light = [elevation.cos() * azimuth.cos(), elevation.cos() * azimuth.sin(), elevation.sin()];
+        let surface = [normal.re, normal.im, 1.0].map(|c| c / std::f64::consts::SQRT_2);
+        let dot = |a: [f64; 3], b: [f64; 3]| a.iter().zip(b).map(|(x, y)| x * y).sum::<f64>();
+        let lambert = dot(light, surface).max(0.0) as f32;
+        // Halfway between the light and the viewer at +z.
+        let halfway = [light[0], light[1], light[2] + 1.0];
+        let length = dot(halfway, halfway).sqrt();
+        let highlight = (dot(halfway.map(|c| c / length), surface).max(0.0) as f32).powf(self.shininess);
+        base.map(|c| c * (self.ambient + self.diffuse * lambert) + self.specular * highlight)
+    }
+}
+
+// Fractint-style channel waves: each of red, green and blue is 0.5 + 0.5 sin(f n + φ)
+// for the escape count n, with its own frequency (radians per iteration) and phase.
+#[derive(Clone, Copy, PartialEq)]
+pub struct SineWaves {
+    pub frequency: [f32; 3],
+    pub phase: [f32; 3],
+}
+
+impl Default for SineWaves {
+    fn default() -> Self {
+        Self { frequency: [0.05, 0.07, 0.11], phase: [0.0, 2.0, 4.0] }
+    }
+}
+
+impl SineWaves {
+    // sRGB color for an escape count, with `shift` radians added to every phase.
+    pub fn color(&self, iterations: f32, shift: f32) -> [f32; 3] {
+        std::array::from_fn(|c| 0.5 + 0.5 * (self.frequency[c] * iterations + self.phase[c] + shift).sin())
+    }
+}
+
+// Curve between the escape count and the palette. At high iteration limits most
+// escapes happen early, so a linear map crowds them into the palette's first colors.
+#[derive(Clone, Copy, PartialEq)]
+pub enum TransferCurve {
+    Linear,
+    Log,
+    Sqrt,
+    Power,
+}
+
+impl TransferCurve {
+    pub const ALL: [TransferCurve; 4] = [
+        TransferCurve::Linear,
+        TransferCurve::Log,
+        TransferCurve::Sqrt,
+        TransferCurve::Power,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TransferCurve::Linear => "Linear",
+            TransferCurve::Log => "Logarithmic",
+            TransferCurve::Sqrt => "Square root",
+            TransferCurve::Power => "Power",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct Transfer {
+    pub curve: TransferCurve,
+    // Exponent of the power curve.
+    pub exponent: f32,
+}
+
+impl Default for Transfer {
+    fn default() -> Self {
+        Self { curve: TransferCurve::Linear, exponent: 0.5 }
+    }
+}
+
+impl Transfer {
+    // Palette position in [0, 1] for an escape after `iterations` of `max_iter`,
+    // which may be a smoothed, fractional count.
+    pub fn apply(&self, iterations: f32, max_iter: u32) -> f32 {
+        let t = iterations / max_iter.max(1) as f32;
+        match self.curve {
+            TransferCurve::Linear => t,
+            TransferCurve::Log => (1.0 + iterations).ln() / (1.0 + max_iter as f32).ln(),
+            TransferCurve::Sqrt => t.sqrt(),
+            TransferCurve::Power => t.powf(self.exponent),
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use crate::color::linear_to_srgb;
+use crate::postprocess::ColorBuffer;
+use crate::quality::{Precision, QualityPreset, QualitySettings};
+use image::{ImageBuffer, Rgb};
+
+pub struct CompareTool {
+    pub open: bool,
+    pub a: QualitySettings,
+    pub b: QualitySettings,
+    // Multiplier applied to the per-channel difference before display.
+    pub gain: f32,
+    pub result: Option<Comparison>,
+}
+
+impl Default for CompareTool {
+    fn default() -> Self {
+        Self {
+            open: false,
+            a: QualityPreset::Normal.settings(),
+            b: QualitySettings { precision: Precision::Single, ..QualityPreset::Normal.settings() },
+            gain: 10.0,
+            result: None,
+        }
+    }
+}
+
+// Errors are measured on display-encoded values so they read as visible
+// differences on a 0..1 scale rather than linear-light energy.
+pub struct Comparison {
+    a: Vec<[f32; 3]>,
+    b: Vec<[f32; 3]>,
+    width: u32,
+    height: u32,
+    pub max_error: f32,
+    pub mean_error: f32,
+    // Share of pixels that would quantize to a different 8-bit value.
+    pub differing: f32,
+}
+
+fn encode(buffer: &ColorBuffer) -> Vec<[f32; 3]> {
+    buffer.pixels.iter().map(|p| p.map(|c| linear_to_srgb(c.clamp(0.0, 1.0)))).collect()
+}
+
+impl Comparison {
+    pub fn new(a: &ColorBuffer, b: &ColorBuffer) -> Self {
+        let (width, height) = (a.width, a.height);
+        let (a, b) = (encode(a), encode(b));
+        let errors: Vec<f32> = a
+            .iter()
+            .zip(&b)
+            .map(|(p, q)| p.iter().zip(q).map(|(x, y)| (x - y).abs()).fold(0.0, f32::max))
+            .collect();
+        let count = errors.len().max(1) as f32;
+        Self {
+            width,
+            height,
+            max_error: errors.iter().copied().fold(0.0, f32::max),
+            mean_error: errors.iter().sum::<f32>() / count,
+            differing: errors.iter().filter(|&&e| e >= 0.5 / 255.0).count() as f32 / count,
+            a,
+            b,
+        }
+    }
+
+    pub fn difference_image(&self, gain: f32) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+        let raw = self
+            .a
+            .iter()
+            .zip(&self.b)
+            .flat_map(|(p, q)| std::array::from_fn::<u8, 3, _>(|c| {
+                ((p[c] - q[c]).abs() * gain * 255.0).clamp(0.0, 255.0).round() as u8
+            }))
+            .collect();
+        ImageBuffer::from_raw(self.width, self.height, raw).unwrap()
+    }
+}
@@ -0,0 +1,37 @@
+use egui::Pos2;
+
+// A line segment in image pixel coordinates.
+pub struct CrossSection {
+    pub start: Pos2,
+    pub end: Pos2,
+}
+
+impl CrossSection {
+    // 0 for the start handle, 1 for the end handle.
+    pub fn endpoint_near(&self, pos: Pos2, radius: f32) -> Option<usize> {
+        if self.start.distance(pos) <= radius {
+            Some(0)
+        } else if self.end.distance(pos) <= radius {
+            Some(1)
+        } else {
+            None
+        }
+    }
+
+    pub fn set_endpoint(&mut self, handle: usize, pos: Pos2) {
+        if handle == 0 {
+            self.start = pos;
+        } else {
+            self.end = pos;
+        }
+    }
+
+    // Evenly spaced points from start to end, paired with their fraction along the line.
+    pub fn samples(&self, count: usize) -> impl Iterator<Item = (f64, Pos2)> + '_ {
+        let count = count.max(2);
+        (0..count).map(move |i| {
+            let t = i as f32 / (count - 1) as f32;
+            (t as f64, self.start.lerp(self.end, t))
+        })
+    }
+}
@@ -0,0 +1,60 @@
+use crate::coloring::OrbitTrap;
+use crate::orbit::Orbit;
+use num_complex::Complex64;
+
+// |z|^2 past which z^3 + a z + b escapes for any parameters on screen; the linear
+// term can pull orbits back from well beyond |z| = 2.
+const BAILOUT: f64 = 1e4;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum CubicParameter {
+    A,
+    B,
+}
+
+// Which of z^3 + a z + b's two parameters the screen scans; the other stays fixed.
+#[derive(Clone, Copy, PartialEq)]
+pub struct CubicSlice {
+    pub scan: CubicParameter,
+    pub fixed: Complex64,
+}
+
+impl Default for CubicSlice {
+    fn default() -> Self {
+        Self { scan: CubicParameter::B, fixed: Complex64::new(0.0, 0.0) }
+    }
+}
+
+impl CubicSlice {
+    // Follows both critical points ±sqrt(-a/3) together. The pixel is in the
+    // connectedness locus only if both stay bounded, so the first escape ends it.
+    pub fn orbit(&self, pixel: Complex64, max_iter: u32, trap: Option<&OrbitTrap>) -> Orbit {
+        let (a, b) = match self.scan {
+            CubicParameter::A => (pixel, self.fixed),
+            CubicParameter::B => (self.fixed, pixel),
+        };
+        let critical = (-a / 3.0).sqrt();
+        let mut zs = [critical, -critical];
+        let mut orbit = Orbit::new();
+        for i in 0..max_iter {
+            if let Some(&z) = zs.iter().find(|z| z.norm_sqr() > BAILOUT) {
+                orbit.iterations = i;
+                orbit.escaped = true;
+                orbit.final_z = z;
+                return orbit;
+            }
+            if i > 0 {
+                orbit.track(zs[0], i);
+                if let Some(trap) = trap {
+                    orbit.track_trap(zs[0], trap);
+                }
+            }
+            for z in &mut zs {
+                *z = *z * *z * *z + a * *z + b;
+            }
+        }
+        orbit.iterations = max_iter;
+        orbit.final_z = zs[0];
+        orbit
+    }
+}
@@ -0,0 +1,36 @@
+use std::time::Instant;
+
+// Classic palette rotation: the view is iterated once and every frame only
+// recolors the stored orbits with the hue offset advanced.
+pub struct ColorCycle {
+    pub enabled: bool,
+    // Degrees of hue offset per second; negative runs the palette backwards.
+    pub speed: f32,
+    // Rotation so far, added to the view's own hue offset.
+    offset: f32,
+    last_frame: Option<Instant>,
+}
+
+impl Default for ColorCycle {
+    fn default() -> Self {
+        Self { enabled: false, speed: 60.0, offset: 0.0, last_frame: None }
+    }
+}
+
+impl ColorCycle {
+    // Moves the rotation on by the time since the last frame and returns it.
+    pub fn advance(&mut self) -> f32 {
+        let now = Instant::now();
+        let elapsed = self.last_frame.map_or(0.0, |last| (now - last).as_secs_f32());
+        self.last_frame = Some(now);
+        self.offset = (self.offset + self.speed * elapsed).rem_euclid(360.0);
+        self.offset
+    }
+
+    // Ends cycling and returns the rotation reached, so the view can keep it.
+    pub fn stop(&mut self) -> f32 {
+        let offset = self.offset;
+        *self = Self { speed: self.speed, ..Self::default() };
+        offset
+    }
+}
@@ -0,0 +1,160 @@
+use crate::orbit::Orbit;
+use num_complex::Complex64;
+
+// Points the vectorized CPU kernel iterates in lockstep: one AVX register of f64s.
+pub const LANES: usize = 4;
+
+// Which map is applied before raising to the power.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Kernel {
+    Multibrot,
+    BurningShip,
+    Tricorn,
+}
+
+// The escape-time iteration shared by the SIMD and GPU backends, for the views
+// they can take over from trace_orbit.
+#[derive(Clone, Copy)]
+pub struct EscapeParams {
+    pub kernel: Kernel,
+    // Start from the point and iterate with `seed`, rather than from 0 with the point.
+    pub julia: bool,
+    pub seed: Complex64,
+    pub power: u32,
+    pub max_iter: u32,
+    // Squared radius past which an orbit has escaped.
+    pub bailout: f64,
+}
+
+// Whether the running CPU has the instructions iterate_lanes vectorizes with.
+pub fn has_vector_kernel() -> bool {
+    #[cfg(target_arch = "x86_64")]
+    {
+        is_x86_feature_detected!("avx")
+    }
+    #[cfg(not(target_arch = "x86_64"))]
+    {
+        false
+    }
+}
+
+// Iterates LANES points together and fills in the same fields as the GPU kernel.
+// Without AVX each point is iterated on its own.
+pub fn iterate_lanes(points: &[Complex64; LANES], params: &EscapeParams) -> [Orbit; LANES] {
+    #[cfg(target_arch = "x86_64")]
+    if is_x86_feature_detected!("avx") {
+        // SAFETY: the CPU supports AVX, checked just above.
+        return unsafe { iterate_avx(points, params) };
+    }
+    points.map(|c| iterate_point(c, params))
+}
+
+// One point at a time, in the same order of operations as the vector kernel.
+fn iterate_point(point: Complex64, params: &EscapeParams) -> Orbit {
+    let (mut z, c) = if params.julia { (point, params.seed) } else { (Complex64::new(0.0, 0.0), point) };
+    let mut orbit = Orbit::new();
+    orbit.iterations = params.max_iter;
+    for n in 0..params.max_iter {
+        if z.norm_sqr() > params.bailout {
+            orbit.iterations = n;
+            orbit.escaped = true;
+            break;
+        }
+        // z0 is not tracked, as in trace_orbit.
+        if n > 0 {
+            orbit.track(z, n);
+        }
+        let w = match params.kernel {
+            Kernel::Multibrot => z,
+            Kernel::BurningShip => Complex64::new(z.re.abs(), z.im.abs()),
+            Kernel::Tricorn => z.conj(),
+        };
+        let mut p = w;
+        for _ in 1..params.power {
+            p = Complex64::new(p.re * w.re - p.im * w.im, p.re * w.im + p.im * w.re);
+        }
+        z = p + c;
+    }
+    orbit.final_z = z;
+    orbit
+}
+
+// Four points in the lanes of AVX registers. A lane that escapes is masked: blends
+// keep its state while the others go on, and the loop ends once every lane is done.
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx")]
+fn iterate_avx(points: &[Complex64; LANES], params: &EscapeParams) -> [Orbit; LANES] {
+    use std::arch::x86_64::*;
+
+    let [a, b, c, d] = *points;
+    let point_re = _mm256_set_pd(d.re, c.re, b.re, a.re);
+    let point_im = _mm256_set_pd(d.im, c.im, b.im, a.im);
+    let zero = _mm256_setzero_pd();
+    let (mut zr, mut zi, cr, ci) = if params.julia {
+        (point_re, point_im, _mm256_set1_pd(params.seed.re), _mm256_set1_pd(params.seed.im))
+    } else {
+        (zero, zero, point_re, point_im)
+    };
+    let bailout = _mm256_set1_pd(params.bailout);
+    // Only the sign bit set: andnot clears it, xor flips it.
+    let sign = _mm256_set1_pd(-0.0);
+    let mut active = _mm256_cmp_pd::<_CMP_EQ_OQ>(zero, zero);
+    let mut iterations = _mm256_set1_pd(params.max_iter as f64);
+    let mut min_distance = _mm256_set1_pd(f64::INFINITY);
+    let mut min_iteration = zero;
+    let mut magnitude_sum = zero;
+
+    for n in 0..params.max_iter {
+        let step = _mm256_set1_pd(n as f64);
+        let norm = _mm256_add_pd(_mm256_mul_pd(zr, zr), _mm256_mul_pd(zi, zi));
+        let escaping = _mm256_and_pd(active, _mm256_cmp_pd::<_CMP_GT_OQ>(norm, bailout));
+        iterations = _mm256_blendv_pd(iterations, step, escaping);
+        active = _mm256_andnot_pd(escaping, active);
+        if _mm256_movemask_pd(active) == 0 {
+            break;
+        }
+        // z0 is not tracked, as in trace_orbit.
+        if n > 0 {
+            let distance = _mm256_sqrt_pd(norm);
+            magnitude_sum = _mm256_add_pd(magnitude_sum, _mm256_and_pd(distance, active));
+            let closer = _mm256_and_pd(active, _mm256_cmp_pd::<_CMP_LT_OQ>(distance, min_distance));
+            min_distance = _mm256_blendv_pd(min_distance, distance, closer);
+            min_iteration = _mm256_blendv_pd(min_iteration, step, closer);
+        }
+
+        let (wr, wi) = match params.kernel {
+            Kernel::Multibrot => (zr, zi),
+            Kernel::BurningShip => (_mm256_andnot_pd(sign, zr), _mm256_andnot_pd(sign, zi)),
+            Kernel::Tricorn => (zr, _mm256_xor_pd(zi, sign)),
+        };
+        let (mut pr, mut pi) = (wr, wi);
+        for _ in 1..params.power {
+            let re = _mm256_sub_pd(_mm256_mul_pd(pr, wr), _mm256_mul_pd(pi, wi));
+            pi = _mm256_add_pd(_mm256_mul_pd(pr, wi), _mm256_mul_pd(pi, wr));
+            pr = re;
+        }
+        zr = _mm256_blendv_pd(zr, _mm256_add_pd(pr, cr), active);
+        zi = _mm256_blendv_pd(zi, _mm256_add_pd(pi, ci), active);
+    }
+
+    let unpack = |v: __m256d| -> [f64; LANES] {
+        let mut out = [0.0; LANES];
+        // SAFETY: `out` holds exactly the four f64s an unaligned store writes.
+        unsafe { _mm256_storeu_pd(out.as_mut_ptr(), v) };
+        out
+    };
+    let still_active = _mm256_movemask_pd(active);
+    let (zr, zi) = (unpack(zr), unpack(zi));
+    let (iterations, min_iteration) = (unpack(iterations), unpack(min_iteration));
+    let (min_distance, magnitude_sum) = (unpack(min_distance), unpack(magnitude_sum));
+    std::array::from_fn(|l| {
+        let mut orbit = Orbit::new();
+        orbit.iterations = iterations[l] as u32;
+        orbit.escaped = still_active & (1 << l) == 0;
+        orbit.final_z = Complex64::new(zr[l], zi[l]);
+        orbit.min_distance = min_distance[l];
+        orbit.min_iteration = min_iteration[l] as u32;
+        orbit.magnitude_sum = magnitude_sum[l];
+        orbit
+    })
+}
@@ -0,0 +1,80 @@
+// Escape-time iteration of z^p + c and its Burning Ship and Tricorn variants, one
+// invocation per point. Mirrors the CPU loop in trace_orbit at f32 precision.
+
+struct Params {
+    seed: vec2<f32>,
+    bailout: f32,
+    max_iter: u32,
+    count: u32,
+    kernel: u32,
+    power: u32,
+    julia: u32,
+}
+
+struct Traced {
+    z: vec2<f32>,
+    iterations: u32,
+    escaped: u32,
+    min_distance: f32,
+    min_iteration: u32,
+    magnitude_sum: f32,
+    pad: u32,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> points: array<vec2<f32>>;
+@group(0) @binding(2) var<storage, read_write> results: array<Traced>;
+
+fn mul(a: vec2<f32>, b: vec2<f32>) -> vec2<f32> {
+    return vec2<f32>(a.x * b.x - a.y * b.y, a.x * b.y + a.y * b.x);
+}
+
+fn next(z: vec2<f32>, c: vec2<f32>) -> vec2<f32> {
+    var w = z;
+    if params.kernel == 1u {
+        w = abs(z);
+    } else if params.kernel == 2u {
+        w = vec2<f32>(z.x, -z.y);
+    }
+    var p = w;
+    for (var k = 1u; k < params.power; k++) {
+        p = mul(p, w);
+    }
+    return p + c;
+}
+
+@compute @workgroup_size(64)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if i >= params.count {
+        return;
+    }
+    var z = vec2<f32>(0.0, 0.0);
+    var c = points[i];
+    if params.julia != 0u {
+        z = c;
+        c = params.seed;
+    }
+    // min_iteration stays 0 when no iterate was tracked.
+    var out: Traced;
+    out.iterations = params.max_iter;
+    for (var n = 0u; n < params.max_iter; n++) {
+        if dot(z, z) > params.bailout {
+            out.iterations = n;
+            out.escaped = 1u;
+            break;
+        }
+        // z0 is not tracked, as on the CPU.
+        if n > 0u {
+            let r = length(z);
+            out.magnitude_sum += r;
+            if out.min_iteration == 0u || r < out.min_distance {
+                out.min_distance = r;
+                out.min_iteration = n;
+            }
+        }
+        z = next(z, c);
+    }
+    out.z = z;
+    results[i] = out;
+}
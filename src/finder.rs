@@ -0,0 +1,100 @@
+use num_complex::Complex64;
+
+const NEWTON_STEPS: usize = 64;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum FinderTarget {
+    // Center of a minibrot: f^p(0) = 0.
+    Nucleus,
+    // Pre-periodic point: f^(k+p)(0) = f^k(0).
+    Misiurewicz,
+}
+
+pub struct Found {
+    pub c: Complex64,
+    // Estimated minibrot size, available for quadratic nuclei only.
+    pub size: Option<f64>,
+}
+
+pub struct FinderTool {
+    pub target: FinderTarget,
+    pub period: u32,
+    pub preperiod: u32,
+    pub result: Option<Found>,
+    pub status: String,
+}
+
+impl Default for FinderTool {
+    fn default() -> Self {
+        Self { target: FinderTarget::Nucleus, period: 1, preperiod: 2, result: None, status: String::new() }
+    }
+}
+
+fn pow(z: Complex64, d: f64) -> Complex64 {
+    if d == 2.0 { z * z } else { z.powf(d) }
+}
+
+// Iterates f(z) = z^d + c from 0, returning f^n(0) and its derivative with respect to c.
+fn iterate(c: Complex64, d: f64, n: u32) -> (Complex64, Complex64) {
+    let mut z = Complex64::new(0.0, 0.0);
+    let mut dz = Complex64::new(0.0, 0.0);
+    for _ in 0..n {
+        dz = pow(z, d - 1.0) * dz * d + 1.0;
+        z = pow(z, d) + c;
+    }
+    (z, dz)
+}
+
+// Newton's method on g(c), where `g` returns the value and derivative.
+fn newton(start: Complex64, g: impl Fn(Complex64) -> (Complex64, Complex64)) -> Option<Complex64> {
+    let mut c = start;
+    for _ in 0..NEWTON_STEPS {
+        let (value, derivative) = g(c);
+        let step = value / derivative;
+        if !step.re.is_finite() || !step.im.is_finite() {
+            return None;
+        }
+        c -= step;
+        if step.norm() <= 1e-15 * c.norm().max(1.0) {
+            return Some(c);
+        }
+    }
+    None
+}
+
+pub fn nucleus(start: Complex64, period: u32, power: f64) -> Option<Found> {
+    let c = newton(start, |c| iterate(c, power, period))?;
+    let size = (power == 2.0).then(|| atom_size(c, period));
+    Some(Found { c, size })
+}
+
+// Nuclei of period dividing `period`, and points with a lower preperiod, are roots of
+// the same equation, so a root only counts if it is neither.
+pub fn misiurewicz(start: Complex64, preperiod: u32, period: u32, power: f64) -> Option<Found> {
+    let c = newton(start, |c| {
+        let (a, da) = iterate(c, power, preperiod + period);
+        let (b, db) = iterate(c, power, preperiod);
+        (a - b, da - db)
+    })?;
+    let distinct = |a: Complex64, b: Complex64| (a - b).norm() > 1e-9 * a.norm().max(1.0);
+    let periodic = !distinct(iterate(c, power, period).0, Complex64::new(0.0, 0.0));
+    let lower = preperiod > 0
+        && !distinct(iterate(c, power, preperiod - 1 + period).0, iterate(c, power, preperiod - 1).0);
+    if periodic || lower {
+        return None;
+    }
+    Some(Found { c, size: None })
+}
+
+// Standard size estimate for the period-p minibrot at nucleus c of z^2 + c.
+fn atom_size(c: Complex64, period: u32) -> f64 {
+    let mut z = Complex64::new(0.0, 0.0);
+    let mut l = Complex64::new(1.0, 0.0);
+    let mut b = Complex64::new(1.0, 0.0);
+    for _ in 1..period {
+        z = z * z + c;
+        l = z * l * 2.0;
+        b += l.inv();
+    }
+    (b * l * l).inv().norm()
+}
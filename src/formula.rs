@@ -0,0 +1,302 @@
+use num_complex::Complex64;
+
+// A user-typed iteration such as `z = z^3 + sin(z)*c`, parsed once into a tree
+// and evaluated per iteration with the current z and the pixel's c.
+#[derive(Clone)]
+pub struct Formula {
+    pub source: String,
+    expr: Expr,
+}
+
+// Two formulas are the same when their text is; the tree is derived from it.
+impl PartialEq for Formula {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Default for Formula {
+    fn default() -> Self {
+        Self::parse("z = z^3 + sin(z)*c").expect("default formula parses")
+    }
+}
+
+impl Formula {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        // The `z =` prefix is optional.
+        let body = match source.split_once('=') {
+            Some((lhs, rhs)) if lhs.trim() == "z" => rhs,
+            Some(_) => return Err("only `z = ...` assignments are supported".to_string()),
+            None => source,
+        };
+        let tokens = tokenize(body)?;
+        let mut parser = Parser { tokens: &tokens, pos: 0 };
+        let expr = parser.expr()?;
+        if parser.pos < tokens.len() {
+            return Err(format!("unexpected {:?}", tokens[parser.pos]));
+        }
+        Ok(Self { source: source.trim().to_string(), expr })
+    }
+
+    #[inline]
+    pub fn eval(&self, z: Complex64, c: Complex64) -> Complex64 {
+        self.expr.eval(z, c)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Function {
+    Sin,
+    Cos,
+    Tan,
+    Sinh,
+    Cosh,
+    Tanh,
+    Exp,
+    Log,
+    Sqrt,
+    Abs,
+    Conj,
+    Re,
+    Im,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Self> {
+        Some(match name {
+            "sin" => Function::Sin,
+            "cos" => Function::Cos,
+            "tan" => Function::Tan,
+            "sinh" => Function::Sinh,
+            "cosh" => Function::Cosh,
+            "tanh" => Function::Tanh,
+            "exp" => Function::Exp,
+            "log" | "ln" => Function::Log,
+            "sqrt" => Function::Sqrt,
+            "abs" => Function::Abs,
+            "conj" => Function::Conj,
+            "re" => Function::Re,
+            "im" => Function::Im,
+            _ => return None,
+        })
+    }
+
+    fn apply(self, z: Complex64) -> Complex64 {
+        match self {
+            Function::Sin => z.sin(),
+            Function::Cos => z.cos(),
+            Function::Tan => z.tan(),
+            Function::Sinh => z.sinh(),
+            Function::Cosh => z.cosh(),
+            Function::Tanh => z.tanh(),
+            Function::Exp => z.exp(),
+            Function::Log => z.ln(),
+            Function::Sqrt => z.sqrt(),
+            Function::Abs => Complex64::new(z.norm(), 0.0),
+            Function::Conj => z.conj(),
+            Function::Re => Complex64::new(z.re, 0.0),
+            Function::Im => Complex64::new(z.im, 0.0),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Expr {
+    Constant(Complex64),
+    Z,
+    C,
+    Neg(Box<Expr>),
+    Add(Box<Expr>, Box<Expr>),
+    Sub(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
+    Div(Box<Expr>, Box<Expr>),
+    Pow(Box<Expr>, Box<Expr>),
+    Call(Function, Box<Expr>),
+}
+
+impl Expr {
+    fn eval(&self, z: Complex64, c: Complex64) -> Complex64 {
+        match self {
+            Expr::Constant(k) => *k,
+            Expr::Z => z,
+            Expr::C => c,
+            Expr::Neg(a) => -a.eval(z, c),
+            Expr::Add(a, b) => a.eval(z, c) + b.eval(z, c),
+            Expr::Sub(a, b) => a.eval(z, c) - b.eval(z, c),
+            Expr::Mul(a, b) => a.eval(z, c) * b.eval(z, c),
+            Expr::Div(a, b) => a.eval(z, c) / b.eval(z, c),
+            Expr::Pow(a, b) => pow(a.eval(z, c), b.eval(z, c)),
+            Expr::Call(f, a) => f.apply(a.eval(z, c)),
+        }
+    }
+}
+
+// Small integer powers are multiplied out so z^2 stays exact and fast.
+fn pow(base: Complex64, exponent: Complex64) -> Complex64 {
+    if exponent.im == 0.0 {
+        if exponent.re.fract() == 0.0 && exponent.re.abs() <= 64.0 {
+            return base.powi(exponent.re as i32);
+        }
+        return base.powf(exponent.re);
+    }
+    base.powc(exponent)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Plus,
+    Minus,
+    Star,
+    Slash,
+    Caret,
+    Open,
+    Close,
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(&ch) = chars.peek() {
+        if ch.is_whitespace() {
+            chars.next();
+        } else if ch.is_ascii_digit() || ch == '.' {
+            let mut number = String::new();
+            while let Some(&d) = chars.peek().filter(|d| d.is_ascii_digit() || **d == '.') {
+                number.push(d);
+                chars.next();
+            }
+            tokens.push(Token::Number(number.parse().map_err(|_| format!("bad number `{}`", number))?));
+        } else if ch.is_alphabetic() {
+            let mut name = String::new();
+            while let Some(&a) = chars.peek().filter(|a| a.is_alphanumeric() || **a == '_') {
+                name.push(a);
+                chars.next();
+            }
+            tokens.push(Token::Ident(name.to_lowercase()));
+        } else {
+            tokens.push(match ch {
+                '+' => Token::Plus,
+                '-' => Token::Minus,
+                '*' => Token::Star,
+                '/' => Token::Slash,
+                '^' => Token::Caret,
+                '(' => Token::Open,
+                ')' => Token::Close,
+                other => return Err(format!("unexpected character `{}`", other)),
+            });
+            chars.next();
+        }
+    }
+    Ok(tokens)
+}
+
+// Recursive descent, lowest precedence first:
+//   expr  = term (("+" | "-") term)*
+//   term  = unary (("*" | "/" | implicit) unary)*
+//   unary = "-" unary | power
+//   power = atom ("^" unary)?
+//   atom  = number | name | name "(" expr ")" | "(" expr ")"
+struct Parser<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expr(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.term()?;
+        loop {
+            match self.peek() {
+                Some(Token::Plus) => {
+                    self.pos += 1;
+                    lhs = Expr::Add(Box::new(lhs), Box::new(self.term()?));
+                }
+                Some(Token::Minus) => {
+                    self.pos += 1;
+                    lhs = Expr::Sub(Box::new(lhs), Box::new(self.term()?));
+                }
+                _ => return Ok(lhs),
+            }
+        }
+    }
+
+    fn term(&mut self) -> Result<Expr, String> {
+        let mut lhs = self.unary()?;
+        loop {
+            match self.peek() {
+                Some(Token::Star) => {
+                    self.pos += 1;
+                    lhs = Expr::Mul(Box::new(lhs), Box::new(self.unary()?));
+                }
+                Some(Token::Slash) => {
+                    self.pos += 1;
+                    lhs = Expr::Div(Box::new(lhs), Box::new(self.unary()?));
+                }
+                // Juxtaposition such as `2z` or `c(z + 1)` multiplies.
+                Some(Token::Number(_) | Token::Ident(_) | Token::Open) => {
+                    lhs = Expr::Mul(Box::new(lhs), Box::new(self.unary()?));
+                }
+                _ => return Ok(lhs),
+            }
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr, String> {
+        if self.peek() == Some(&Token::Minus) {
+            self.pos += 1;
+            return Ok(Expr::Neg(Box::new(self.unary()?)));
+        }
+        let base = self.atom()?;
+        if self.peek() == Some(&Token::Caret) {
+            self.pos += 1;
+            return Ok(Expr::Pow(Box::new(base), Box::new(self.unary()?)));
+        }
+        Ok(base)
+    }
+
+    fn atom(&mut self) -> Result<Expr, String> {
+        match self.next() {
+            Some(Token::Number(value)) => Ok(Expr::Constant(Complex64::new(value, 0.0))),
+            Some(Token::Open) => {
+                let inner = self.expr()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(inner),
+                    _ => Err("missing `)`".to_string()),
+                }
+            }
+            Some(Token::Ident(name)) => {
+                if let Some(function) = Function::from_name(&name) {
+                    if self.next() != Some(Token::Open) {
+                        return Err(format!("`{}` needs an argument in parentheses", name));
+                    }
+                    let argument = self.expr()?;
+                    if self.next() != Some(Token::Close) {
+                        return Err("missing `)`".to_string());
+                    }
+                    return Ok(Expr::Call(function, Box::new(argument)));
+                }
+                match name.as_str() {
+                    "z" => Ok(Expr::Z),
+                    "c" => Ok(Expr::C),
+                    "i" => Ok(Expr::Constant(Complex64::new(0.0, 1.0))),
+                    "pi" => Ok(Expr::Constant(Complex64::new(std::f64::consts::PI, 0.0))),
+                    "e" => Ok(Expr::Constant(Complex64::new(std::f64::consts::E, 0.0))),
+                    _ => Err(format!("unknown name `{}`", name)),
+                }
+            }
+            Some(token) => Err(format!("unexpected {:?}", token)),
+            None => Err("formula ends too early".to_string()),
+        }
+    }
+}
@@ -0,0 +1,235 @@
+use crate::cubic::CubicParameter;
+use crate::formula::Formula;
+use crate::lyapunov::Sequence;
+use crate::palette::{ColorStop, Palette, MIN_STOPS};
+use crate::polynomial::RootMethod;
+use crate::{FractalState, FractalType};
+use base64::Engine;
+use num_complex::Complex64;
+use parking_lot::Mutex;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::thread;
+
+// Endpoint protocol:
+//   POST <endpoint>/locations  {"name", "params", "thumbnail": base64 PNG}  ->  {"url"}
+//   GET  <endpoint>/locations  ->  [{"name", "url", "params"}, ...]
+
+pub struct SharedLocation {
+    pub name: String,
+    pub url: String,
+    pub state: FractalState,
+}
+
+pub enum GalleryReply {
+    Shared(Result<String, String>),
+    Listing(Result<Vec<SharedLocation>, String>),
+}
+
+fn params_to_json(state: &FractalState) -> Value {
+    json!({
+        "fractal_type": state.fractal_type.name(),
+        "zoom": state.zoom,
+        "center_x": state.center_x,
+        "center_y": state.center_y,
+        "max_iter": state.max_iter,
+        "power": state.power,
+        "power_im": state.power_im,
+        "phoenix_p": [state.phoenix_p.re, state.phoenix_p.im],
+        "julia_seed": [state.julia_seed.re, state.julia_seed.im],
+        "julia_plane": state.julia_plane,
+        "secondary_param": state.secondary_param,
+        "hue_offset": state.hue_offset,
+        "saturation": state.saturation,
+        "value": state.value,
+        "palette": state.palette.stops.iter().map(|stop| {
+            let [r, g, b] = stop.color;
+            [stop.position, r, g, b]
+        }).collect::<Vec<_>>(),
+        "formula": state.formula.source,
+        "root_method": state.root_method.name(),
+        "biomorph": state.biomorph,
+        "lyapunov_sequence": state.lyapunov_sequence.source,
+        "cubic_scan": if state.cubic.scan == CubicParameter::A { "a" } else { "b" },
+        "cubic_fixed": [state.cubic.fixed.re, state.cubic.fixed.im],
+    })
+}
+
+// Missing or malformed fields reject the whole entry rather than loading half a location.
+// Entries come from a server that may not be trustworthy, so non-finite numbers, a
+// zoom that is not positive and a zero iteration count are malformed too. Values past
+// the slider ranges are kept, as the app's own views and presets go there.
+fn params_from_json(params: &Value, base: &FractalState) -> Option<FractalState> {
+    let number = |key: &str| params.get(key)?.as_f64().filter(|v| v.is_finite());
+    let number32 = |key: &str| number(key).map(|v| v as f32).filter(|v| v.is_finite());
+    let name = params.get("fractal_type")?.as_str()?;
+    let mut state = base.clone();
+    state.fractal_type = FractalType::ALL.into_iter().find(|t| t.name() == name)?;
+    state.zoom = number("zoom").filter(|&zoom| zoom > 0.0)?;
+    state.center_x = number("center_x")?;
+    state.center_y = number("center_y")?;
+    state.max_iter = number("max_iter").map(|n| n as u32).filter(|&n| n > 0)?;
+    state.power = number("power")?;
+    state.power_im = number("power_im").unwrap_or(0.0);
+    let finite = |v: &Value| v.as_f64().filter(|v| v.is_finite());
+    let complex = |key: &str| match params.get(key)?.as_array()?.as_slice() {
+        [re, im] => Some(Complex64::new(finite(re)?, finite(im)?)),
+        _ => None,
+    };
+    state.phoenix_p = complex("phoenix_p").unwrap_or(state.phoenix_p);
+    state.julia_seed = complex("julia_seed").unwrap_or(state.julia_seed);
+    state.julia_plane = params.get("julia_plane").and_then(Value::as_bool).unwrap_or(false);
+    state.biomorph = params.get("biomorph").and_then(Value::as_bool).unwrap_or(false);
+    state.cubic.scan = match params.get("cubic_scan").and_then(Value::as_str) {
+        Some("a") => CubicParameter::A,
+        _ => CubicParameter::B,
+    };
+    state.cubic.fixed = complex("cubic_fixed").unwrap_or(state.cubic.fixed);
+    state.secondary_param = number("secondary_param")?;
+    state.hue_offset = number32("hue_offset")?;
+    state.saturation = number32("saturation")?;
+    state.value = number32("value")?;
+    let finite32 = |v: &Value| finite(v).map(|v| v as f32).filter(|v| v.is_finite());
+    if let Some(stops) = params.get("palette").and_then(Value::as_array) {
+        let stops = stops.iter().map(|stop| match stop.as_array()?.as_slice() {
+            [position, r, g, b] => Some(ColorStop {
+                position: finite32(position)?,
+                color: [finite32(r)?, finite32(g)?, finite32(b)?],
+            }),
+            _ => None,
+        }).collect::<Option<Vec<_>>>()?;
+        if stops.len() < MIN_STOPS || stops.windows(2).any(|pair| pair[0].position > pair[1].position) {
+            return None;
+        }
+        state.palette = Palette { stops };
+    }
+    // Entries shared before custom formulas existed have none.
+    if let Some(source) = params.get("formula").and_then(Value::as_str) {
+        state.formula = Formula::parse(source).ok()?;
+    }
+    if let Some(source) = params.get("lyapunov_sequence").and_then(Value::as_str) {
+        state.lyapunov_sequence = Sequence::parse(source).ok()?;
+    }
+    if let Some(name) = params.get("root_method").and_then(Value::as_str) {
+        state.root_method = RootMethod::ALL.into_iter().find(|m| m.name() == name)?;
+    }
+    Some(state)
+}
+
+fn locations_url(endpoint: &str) -> String {
+    format!("{}/locations", endpoint.trim().trim_end_matches('/'))
+}
+
+fn share(endpoint: &str, name: &str, state: &FractalState, thumbnail_png: &[u8]) -> Result<String, String> {
+    let body = json!({
+        "name": name,
+        "params": params_to_json(state),
+        "thumbnail": base64::engine::general_purpose::STANDARD.encode(thumbnail_png),
+    });
+    let reply: Value = ureq::post(&locations_url(endpoint))
+        .send_json(body)
+        .map_err(|e| e.to_string())?
+        .into_json()
+        .map_err(|e| e.to_string())?;
+    reply
+        .get("url")
+        .and_then(Value::as_str)
+        .map(str::to_owned)
+        .ok_or_else(|| "gallery reply had no link".to_owned())
+}
+
+fn fetch(endpoint: &str, base: &FractalState) -> Result<Vec<SharedLocation>, String> {
+    let reply: Value = ureq::get(&locations_url(endpoint))
+        .call()
+        .map_err(|e| e.to_string())?
+        .into_json()
+        .map_err(|e| e.to_string())?;
+    let entries = reply.as_array().ok_or_else(|| "gallery listing was not a list".to_owned())?;
+    Ok(entries
+        .iter()
+        .filter_map(|entry| {
+            Some(SharedLocation {
+                name: entry.get("name")?.as_str()?.to_owned(),
+                url: entry.get("url").and_then(Value::as_str).unwrap_or_default().to_owned(),
+                state: params_from_json(entry.get("params")?, base)?,
+            })
+        })
+        .collect())
+}
+
+// Requests run on a worker thread so a slow server never stalls the UI; the worker
+// drops its reply into the slot and `poll` picks it up.
+#[derive(Default)]
+pub struct GalleryClient {
+    pending: Option<Arc<Mutex<Option<GalleryReply>>>>,
+}
+
+impl GalleryClient {
+    pub fn is_busy(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    fn spawn(&mut self, request: impl FnOnce() -> GalleryReply + Send + 'static) {
+        let slot = Arc::new(Mutex::new(None));
+        let worker_slot = slot.clone();
+        thread::spawn(move || {
+            *worker_slot.lock() = Some(request());
+        });
+        self.pending = Some(slot);
+    }
+
+    pub fn share(&mut self, endpoint: String, name: String, state: FractalState, thumbnail_png: Vec<u8>) {
+        self.spawn(move || GalleryReply::Shared(share(&endpoint, &name, &state, &thumbnail_png)));
+    }
+
+    pub fn browse(&mut self, endpoint: String, base: FractalState) {
+        self.spawn(move || GalleryReply::Listing(fetch(&endpoint, &base)));
+    }
+
+    pub fn poll(&mut self) -> Option<GalleryReply> {
+        let reply = self.pending.as_ref()?.lock().take()?;
+        self.pending = None;
+        Some(reply)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hostile(field: &str, value: Value) -> Option<FractalState> {
+        let base = FractalState::default();
+        let mut params = params_to_json(&base);
+        params[field] = value;
+        params_from_json(&params, &base)
+    }
+
+    #[test]
+    fn keeps_views_past_the_slider_ranges() {
+        // The Lyapunov home view, and a finder zoom well past the slider.
+        assert_eq!(hostile("center_x", json!(3.5)).unwrap().center_x, 3.5);
+        assert_eq!(hostile("center_y", json!(-3.0)).unwrap().center_y, -3.0);
+        assert_eq!(hostile("zoom", json!(1e9)).unwrap().zoom, 1e9);
+        assert_eq!(hostile("zoom", json!(0.05)).unwrap().zoom, 0.05);
+        assert_eq!(hostile("max_iter", json!(20000)).unwrap().max_iter, 20000);
+        assert_eq!(hostile("power", json!(9.5)).unwrap().power, 9.5);
+        assert_eq!(hostile("hue_offset", json!(720.0)).unwrap().hue_offset, 720.0);
+    }
+
+    #[test]
+    fn rejects_non_finite_and_malformed_fields() {
+        // JSON has no NaN or infinity: serde_json writes them as null.
+        assert!(hostile("zoom", json!(f64::NAN)).is_none());
+        assert!(hostile("center_x", json!(f64::INFINITY)).is_none());
+        assert!(hostile("max_iter", json!("NaN")).is_none());
+        assert!(hostile("zoom", json!(0.0)).is_none());
+        assert!(hostile("zoom", json!(-5.0)).is_none());
+        assert!(hostile("max_iter", json!(0)).is_none());
+        assert!(hostile("max_iter", json!(-1)).is_none());
+        assert!(hostile("hue_offset", json!(1e300)).is_none());
+        assert!(hostile("palette", json!([[0.0, null, 0.0, 0.0], [1.0, 0.0, 0.0, 0.0]])).is_none());
+        assert!(serde_json::from_str::<Value>(r#"{"zoom": 1e400}"#).is_err());
+        let seed = hostile("julia_seed", json!([null, 0.0])).unwrap().julia_seed;
+        assert_eq!(seed, FractalState::default().julia_seed);
+    }
+}
@@ -0,0 +1,150 @@
+use crate::escape::{EscapeParams, Kernel};
+use crate::orbit::Orbit;
+use num_complex::Complex64;
+use wgpu::util::DeviceExt;
+
+const SHADER: &str = include_str!("escape.wgsl");
+const WORKGROUP_SIZE: u32 = 64;
+// Points per dispatch, which keeps every buffer well under the default 128 MiB
+// storage binding limit.
+const TILE_POINTS: usize = 1 << 20;
+// Bytes per traced point in the shader's result array.
+const TRACED_SIZE: usize = 32;
+
+pub struct EscapeKernel {
+    pipeline: wgpu::ComputePipeline,
+}
+
+impl EscapeKernel {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+            label: Some("escape"),
+            source: wgpu::ShaderSource::Wgsl(SHADER.into()),
+        });
+        let pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+            label: Some("escape"),
+            layout: None,
+            module: &module,
+            entry_point: "main",
+        });
+        Self { pipeline }
+    }
+
+    // Iterates every point and returns their orbits in the same order. Only the
+    // escape count, final iterate and closest approach to 0 are filled in.
+    pub fn run(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        params: &EscapeParams,
+        points: &[[f32; 2]],
+    ) -> Result<Vec<Orbit>, String> {
+        device.push_error_scope(wgpu::ErrorFilter::Validation);
+        let tiles: Result<Vec<Vec<Orbit>>, String> =
+            points.chunks(TILE_POINTS).map(|tile| self.run_tile(device, queue, params, tile)).collect();
+        if let Some(err) = pollster::block_on(device.pop_error_scope()) {
+            return Err(err.to_string());
+        }
+        Ok(tiles?.into_iter().flatten().collect())
+    }
+
+    fn run_tile(
+        &self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        params: &EscapeParams,
+        points: &[[f32; 2]],
+    ) -> Result<Vec<Orbit>, String> {
+        let kernel = match params.kernel {
+            Kernel::Multibrot => 0,
+            Kernel::BurningShip => 1,
+            Kernel::Tricorn => 2,
+        };
+        let uniforms: [u32; 8] = [
+            (params.seed.re as f32).to_bits(),
+            (params.seed.im as f32).to_bits(),
+            (params.bailout as f32).to_bits(),
+            params.max_iter,
+            points.len() as u32,
+            kernel,
+            params.power,
+            params.julia as u32,
+        ];
+        let uniforms = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("escape params"),
+            contents: bytemuck::cast_slice(&uniforms),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let input = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("escape points"),
+            contents: bytemuck::cast_slice(points),
+            usage: wgpu::BufferUsages::STORAGE,
+        });
+        let size = (points.len() * TRACED_SIZE) as wgpu::BufferAddress;
+        let output = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("escape results"),
+            size,
+            usage: wgpu::BufferUsages::STORAGE | wgpu::BufferUsages::COPY_SRC,
+            mapped_at_creation: false,
+        });
+        let staging = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("escape readback"),
+            size,
+            usage: wgpu::BufferUsages::MAP_READ | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("escape"),
+            layout: &self.pipeline.get_bind_group_layout(0),
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: uniforms.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 1, resource: input.as_entire_binding() },
+                wgpu::BindGroupEntry { binding: 2, resource: output.as_entire_binding() },
+            ],
+        });
+
+        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("escape") });
+        {
+            let mut pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor {
+                label: Some("escape"),
+                timestamp_writes: None,
+            });
+            pass.set_pipeline(&self.pipeline);
+            pass.set_bind_group(0, &bind_group, &[]);
+            pass.dispatch_workgroups((points.len() as u32).div_ceil(WORKGROUP_SIZE), 1, 1);
+        }
+        encoder.copy_buffer_to_buffer(&output, 0, &staging, 0, size);
+        queue.submit(Some(encoder.finish()));
+
+        let slice = staging.slice(..);
+        let (sender, receiver) = std::sync::mpsc::channel();
+        slice.map_async(wgpu::MapMode::Read, move |result| {
+            let _ = sender.send(result);
+        });
+        let _ = device.poll(wgpu::Maintain::Wait);
+        receiver
+            .recv()
+            .map_err(|e| e.to_string())?
+            .map_err(|e| e.to_string())?;
+
+        let orbits: Vec<Orbit> = slice.get_mapped_range().chunks_exact(TRACED_SIZE).map(read_orbit).collect();
+        staging.unmap();
+        Ok(orbits)
+    }
+}
+
+// Decodes one of the shader's `Traced` records.
+fn read_orbit(bytes: &[u8]) -> Orbit {
+    let word = |i: usize| u32::from_le_bytes([bytes[4 * i], bytes[4 * i + 1], bytes[4 * i + 2], bytes[4 * i + 3]]);
+    let float = |i: usize| f32::from_bits(word(i)) as f64;
+    let mut orbit = Orbit::new();
+    orbit.final_z = Complex64::new(float(0), float(1));
+    orbit.iterations = word(2);
+    orbit.escaped = word(3) != 0;
+    orbit.min_iteration = word(5);
+    if orbit.min_iteration > 0 {
+        orbit.min_distance = float(4);
+    }
+    orbit.magnitude_sum = float(6);
+    orbit
+}
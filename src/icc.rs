@@ -0,0 +1,168 @@
+// Minimal ICC v2 display profiles, for the iCCP chunk of exported PNGs. A profile is
+// a header, a tag table and the tags it points at, all big-endian.
+
+// Tone response of each channel, from encoded value to linear light.
+pub enum Curve {
+    Gamma(f64),
+    // Samples spread evenly over 0..=1, scaled to 0..=65535.
+    Table(Vec<u16>),
+}
+
+// The D50 illuminant of the profile connection space.
+const D50: [f64; 3] = [0.9642, 1.0, 0.8249];
+
+// Colorant and white point tags carry XYZ under D50, so the primaries are adapted
+// from the white point with the Bradford transform, as color management systems expect.
+const BRADFORD: [[f64; 3]; 3] = [
+    [0.8951, 0.2664, -0.1614],
+    [-0.7502, 1.7135, 0.0367],
+    [0.0389, -0.0685, 1.0296],
+];
+
+fn xy_to_xyz((x, y): (f64, f64)) -> [f64; 3] {
+    [x / y, 1.0, (1.0 - x - y) / y]
+}
+
+fn multiply(a: &[[f64; 3]; 3], b: &[[f64; 3]; 3]) -> [[f64; 3]; 3] {
+    std::array::from_fn(|i| std::array::from_fn(|j| (0..3).map(|k| a[i][k] * b[k][j]).sum()))
+}
+
+fn apply(m: &[[f64; 3]; 3], v: [f64; 3]) -> [f64; 3] {
+    std::array::from_fn(|i| (0..3).map(|k| m[i][k] * v[k]).sum())
+}
+
+fn invert(m: &[[f64; 3]; 3]) -> [[f64; 3]; 3] {
+    let cofactor = |i: usize, j: usize| {
+        let (r0, r1) = ((i + 1) % 3, (i + 2) % 3);
+        let (c0, c1) = ((j + 1) % 3, (j + 2) % 3);
+        m[r0][c0] * m[r1][c1] - m[r0][c1] * m[r1][c0]
+    };
+    let det: f64 = (0..3).map(|j| m[0][j] * cofactor(0, j)).sum();
+    std::array::from_fn(|i| std::array::from_fn(|j| cofactor(j, i) / det))
+}
+
+// Columns are the XYZ of full red, green and blue, adapted to D50.
+fn colorants(white: (f64, f64), primaries: [(f64, f64); 3]) -> [[f64; 3]; 3] {
+    let white = xy_to_xyz(white);
+    let columns = primaries.map(xy_to_xyz);
+    let m: [[f64; 3]; 3] = std::array::from_fn(|i| std::array::from_fn(|j| columns[j][i]));
+    let scale = apply(&invert(&m), white);
+    let rgb_to_xyz: [[f64; 3]; 3] = std::array::from_fn(|i| std::array::from_fn(|j| m[i][j] * scale[j]));
+
+    let (from, to) = (apply(&BRADFORD, white), apply(&BRADFORD, D50));
+    let cone_scale: [[f64; 3]; 3] =
+        std::array::from_fn(|i| std::array::from_fn(|j| if i == j { to[i] / from[i] } else { 0.0 }));
+    let adapt = multiply(&invert(&BRADFORD), &multiply(&cone_scale, &BRADFORD));
+    multiply(&adapt, &rgb_to_xyz)
+}
+
+fn s15_fixed16(v: f64) -> [u8; 4] {
+    ((v * 65536.0).round() as i32).to_be_bytes()
+}
+
+fn xyz_tag(xyz: [f64; 3]) -> Vec<u8> {
+    let mut tag = b"XYZ \0\0\0\0".to_vec();
+    for v in xyz {
+        tag.extend(s15_fixed16(v));
+    }
+    tag
+}
+
+fn curve_tag(curve: &Curve) -> Vec<u8> {
+    let mut tag = b"curv\0\0\0\0".to_vec();
+    match curve {
+        Curve::Gamma(gamma) => {
+            tag.extend(1u32.to_be_bytes());
+            // u8Fixed8Number
+            tag.extend(((gamma * 256.0).round() as u16).to_be_bytes());
+        }
+        Curve::Table(samples) => {
+            tag.extend((samples.len() as u32).to_be_bytes());
+            for sample in samples {
+                tag.extend(sample.to_be_bytes());
+            }
+        }
+    }
+    tag
+}
+
+fn description_tag(text: &str) -> Vec<u8> {
+    let mut tag = b"desc\0\0\0\0".to_vec();
+    tag.extend((text.len() as u32 + 1).to_be_bytes());
+    tag.extend(text.as_bytes());
+    tag.push(0);
+    // Empty Unicode and ScriptCode descriptions.
+    tag.extend([0; 4 + 4 + 2 + 1 + 67]);
+    tag
+}
+
+fn text_tag(text: &str) -> Vec<u8> {
+    let mut tag = b"text\0\0\0\0".to_vec();
+    tag.extend(text.as_bytes());
+    tag.push(0);
+    tag
+}
+
+// An RGB display profile with the given white point and primaries as CIE xy, and the
+// same curve on all three channels.
+pub fn rgb_profile(description: &str, white: (f64, f64), primaries: [(f64, f64); 3], curve: &Curve) -> Vec<u8> {
+    let colorants = colorants(white, primaries);
+    let column = |j: usize| [colorants[0][j], colorants[1][j], colorants[2][j]];
+    let white_xyz = xy_to_xyz(white);
+    let trc = curve_tag(curve);
+    // The three curve tags share one copy of the data.
+    let tags: [(&[u8; 4], Vec<u8>); 9] = [
+        (b"desc", description_tag(description)),
+        (b"cprt", text_tag("No copyright, use freely")),
+        (b"wtpt", xyz_tag(white_xyz)),
+        (b"rXYZ", xyz_tag(column(0))),
+        (b"gXYZ", xyz_tag(column(1))),
+        (b"bXYZ", xyz_tag(column(2))),
+        (b"rTRC", trc),
+        (b"gTRC", Vec::new()),
+        (b"bTRC", Vec::new()),
+    ];
+
+    let table_len = 4 + 12 * tags.len();
+    let mut table = (tags.len() as u32).to_be_bytes().to_vec();
+    let mut data = Vec::new();
+    let mut trc_entry = (0, 0);
+    for (signature, tag) in &tags {
+        let entry = if tag.is_empty() {
+            trc_entry
+        } else {
+            // Tags start on four-byte boundaries.
+            data.resize(data.len().next_multiple_of(4), 0);
+            let entry = ((128 + table_len + data.len()) as u32, tag.len() as u32);
+            data.extend(tag);
+            entry
+        };
+        if *signature == b"rTRC" {
+            trc_entry = entry;
+        }
+        table.extend(*signature);
+        table.extend(entry.0.to_be_bytes());
+        table.extend(entry.1.to_be_bytes());
+    }
+
+    data.resize(data.len().next_multiple_of(4), 0);
+    let size = 128 + table_len + data.len();
+    let mut profile = Vec::with_capacity(size);
+    profile.extend((size as u32).to_be_bytes());
+    profile.extend([0; 4]);
+    // Version 2.1.
+    profile.extend([2, 0x10, 0, 0]);
+    profile.extend(b"mntrRGB XYZ ");
+    // Creation date and time.
+    profile.extend([0; 12]);
+    profile.extend(b"acsp");
+    // Platform, flags, device manufacturer, model and attributes, perceptual intent.
+    profile.extend([0; 28]);
+    for v in D50 {
+        profile.extend(s15_fixed16(v));
+    }
+    profile.resize(128, 0);
+    profile.extend(table);
+    profile.extend(data);
+    profile
+}
@@ -0,0 +1,148 @@
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use rayon::prelude::*;
+
+// Points discarded at the start of each chain while it falls onto the attractor.
+const SETTLE_POINTS: usize = 20;
+
+// (x, y) -> (a x + b y + e, c x + d y + f), picked with relative weight `weight`.
+#[derive(Clone, Copy, PartialEq)]
+pub struct Affine {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    pub e: f64,
+    pub f: f64,
+    pub weight: f64,
+}
+
+impl Affine {
+    const fn new(a: f64, b: f64, c: f64, d: f64, e: f64, f: f64, weight: f64) -> Self {
+        Self { a, b, c, d, e, f, weight }
+    }
+
+    fn apply(&self, (x, y): (f64, f64)) -> (f64, f64) {
+        (self.a * x + self.b * y + self.e, self.c * x + self.d * y + self.f)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum IfsPreset {
+    BarnsleyFern,
+    Sierpinski,
+    Dragon,
+}
+
+impl IfsPreset {
+    pub const ALL: [IfsPreset; 3] = [IfsPreset::BarnsleyFern, IfsPreset::Sierpinski, IfsPreset::Dragon];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            IfsPreset::BarnsleyFern => "Barnsley fern",
+            IfsPreset::Sierpinski => "Sierpinski triangle",
+            IfsPreset::Dragon => "Heighway dragon",
+        }
+    }
+
+    pub fn transforms(&self) -> Vec<Affine> {
+        match self {
+            IfsPreset::BarnsleyFern => vec![
+                Affine::new(0.0, 0.0, 0.0, 0.16, 0.0, 0.0, 0.01),
+                Affine::new(0.85, 0.04, -0.04, 0.85, 0.0, 1.6, 0.85),
+                Affine::new(0.2, -0.26, 0.23, 0.22, 0.0, 1.6, 0.07),
+                Affine::new(-0.15, 0.28, 0.26, 0.24, 0.0, 0.44, 0.07),
+            ],
+            IfsPreset::Sierpinski => vec![
+                Affine::new(0.5, 0.0, 0.0, 0.5, 0.0, 0.0, 1.0),
+                Affine::new(0.5, 0.0, 0.0, 0.5, 0.5, 0.0, 1.0),
+                Affine::new(0.5, 0.0, 0.0, 0.5, 0.25, 0.5, 1.0),
+            ],
+            IfsPreset::Dragon => vec![
+                Affine::new(0.5, -0.5, 0.5, 0.5, 0.0, 0.0, 1.0),
+                Affine::new(-0.5, -0.5, 0.5, -0.5, 1.0, 0.0, 1.0),
+            ],
+        }
+    }
+
+    // Zoom and center (x, y) framing the attractor. The view maps y downwards, so
+    // points are plotted at -y to keep the fern upright.
+    pub fn view(&self) -> (f64, f64, f64) {
+        match self {
+            IfsPreset::BarnsleyFern => (0.48, 4.15, -5.0),
+            IfsPreset::Sierpinski => (4.5, 0.92, -0.5),
+            IfsPreset::Dragon => (4.0, 0.9, -0.2),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct IfsSettings {
+    pub preset: IfsPreset,
+    pub transforms: Vec<Affine>,
+    // Chaos game points per output pixel.
+    pub density: f64,
+}
+
+impl Default for IfsSettings {
+    fn default() -> Self {
+        let preset = IfsPreset::BarnsleyFern;
+        Self { preset, transforms: preset.transforms(), density: 20.0 }
+    }
+}
+
+// Hit counts from the chaos game: each worker follows its own chain, applying a
+// transform picked at random by weight and counting the pixel it lands on.
+pub fn accumulate(
+    transforms: &[Affine],
+    width: u32,
+    height: u32,
+    origin: (f64, f64),
+    step: (f64, f64),
+    density: f64,
+    workers: usize,
+) -> Vec<u32> {
+    let len = (width * height) as usize;
+    let total_weight: f64 = transforms.iter().map(|t| t.weight.max(0.0)).sum();
+    if transforms.is_empty() || total_weight <= 0.0 {
+        return vec![0; len];
+    }
+    let per_worker = (len as f64 * density) as usize / workers.max(1) + 1;
+
+    (0..workers.max(1))
+        .into_par_iter()
+        .map(|worker| {
+            let mut rng = ChaCha8Rng::seed_from_u64(worker as u64);
+            let mut hits = vec![0u32; len];
+            let mut point = (0.0, 0.0);
+            for n in 0..per_worker + SETTLE_POINTS {
+                let mut pick = rng.gen_range(0.0..total_weight);
+                let transform = transforms
+                    .iter()
+                    .find(|t| {
+                        pick -= t.weight.max(0.0);
+                        pick < 0.0
+                    })
+                    .unwrap_or(&transforms[transforms.len() - 1]);
+                point = transform.apply(point);
+                if n < SETTLE_POINTS {
+                    continue;
+                }
+                let x = (point.0 - origin.0) / step.0;
+                let y = (-point.1 - origin.1) / step.1;
+                if x >= 0.0 && y >= 0.0 && x < width as f64 && y < height as f64 {
+                    hits[y as usize * width as usize + x as usize] += 1;
+                }
+            }
+            hits
+        })
+        .reduce(
+            || vec![0u32; len],
+            |mut a, b| {
+                for (a, b) in a.iter_mut().zip(b) {
+                    *a += b;
+                }
+                a
+            },
+        )
+}
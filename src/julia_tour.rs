@@ -0,0 +1,62 @@
+use crate::animation::Timeline;
+use crate::{FractalState, FractalType};
+use num_complex::Complex64;
+use std::f64::consts::TAU;
+
+// Keyframes per loop; the seed moves along a chord between them.
+const TOUR_KEYFRAMES: usize = 120;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum TourPath {
+    MainCardioid,
+    PeriodTwoBulb,
+}
+
+impl TourPath {
+    pub const ALL: [TourPath; 2] = [TourPath::MainCardioid, TourPath::PeriodTwoBulb];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TourPath::MainCardioid => "Main cardioid",
+            TourPath::PeriodTwoBulb => "Period-2 bulb",
+        }
+    }
+}
+
+pub struct JuliaTour {
+    pub path: TourPath,
+    // Radius relative to the boundary: 1 walks the boundary itself, above 1
+    // steps outside where the Julia sets break into dust.
+    pub offset: f64,
+    pub seconds: f64,
+}
+
+impl Default for JuliaTour {
+    fn default() -> Self {
+        Self { path: TourPath::MainCardioid, offset: 1.0, seconds: 20.0 }
+    }
+}
+
+impl JuliaTour {
+    // Seed at internal angle `turn` (0..1 around the component).
+    pub fn seed_at(&self, turn: f64) -> Complex64 {
+        let w = Complex64::from_polar(self.offset, TAU * turn);
+        match self.path {
+            TourPath::MainCardioid => w / 2.0 - w * w / 4.0,
+            TourPath::PeriodTwoBulb => w / 4.0 - 1.0,
+        }
+    }
+
+    pub fn to_timeline(&self, base: &FractalState) -> Timeline {
+        let mut timeline = Timeline::default();
+        for i in 0..=TOUR_KEYFRAMES {
+            let turn = i as f64 / TOUR_KEYFRAMES as f64;
+            let mut state = base.clone();
+            state.fractal_type = FractalType::Julia;
+            state.julia_seed = self.seed_at(turn);
+            (state.zoom, state.center_x, state.center_y) = FractalType::Julia.home_view();
+            timeline.push(turn * self.seconds, state);
+        }
+        timeline
+    }
+}
@@ -0,0 +1,75 @@
+use rayon::prelude::*;
+
+// Iterates closer than this to the one two steps back are in a 2-cycle.
+const CYCLE_TOLERANCE: f64 = 1e-6;
+
+// Limit set of the Maskit slice group generated by z -> z + 2 and the parabolic
+// map with translation μ = -b + a·i, after Jos Leys' folding algorithm.
+#[derive(Clone, Copy)]
+pub struct Kleinian {
+    pub a: f64,
+    pub b: f64,
+    pub iterations: u32,
+}
+
+impl Default for Kleinian {
+    fn default() -> Self {
+        Self { a: 1.8462756, b: 0.09627581, iterations: 80 }
+    }
+}
+
+impl Kleinian {
+    // Distance estimate to the limit set and the number of folds it took.
+    pub fn estimate(&self, (mut x, mut y): (f64, f64)) -> (f64, u32) {
+        let (a, b) = (self.a, self.b);
+        let f = if b < 0.0 { -1.0 } else { 1.0 };
+        let mut previous = (x + 1.0, y);
+        let mut before = (x - 1.0, y);
+        let mut derivative = 1.0f64;
+        let mut n = 0;
+        while n < self.iterations {
+            // Translate into the fundamental strip, sheared along the group's lattice.
+            x += f * b / a * y;
+            x = (x + 1.0).rem_euclid(2.0) - 1.0;
+            x -= f * b / a * y;
+
+            // Above the separation line, rotate by 180° about (-b/2, a/2).
+            let side = (x + b * 0.5).signum();
+            let falloff = 1.0 - (-(7.2 - (1.95 - a) * 15.0) * (x + b * 0.5).abs()).exp();
+            if y >= a * 0.5 + f * (2.0 * a - 1.95) / 4.0 * side * falloff {
+                x = -b - x;
+                y = a - y;
+            }
+
+            // The parabolic generator: inversion in the unit circle, then translation.
+            let inverse = 1.0 / (x * x + y * y);
+            x = -b + x * inverse;
+            y = a - y * inverse;
+            derivative *= inverse.max(1.0);
+            n += 1;
+
+            if (x - before.0).powi(2) + (y - before.1).powi(2) < CYCLE_TOLERANCE {
+                break;
+            }
+            before = previous;
+            previous = (x, y);
+        }
+        let edge = y.min(a - y);
+        (edge / derivative.min(3.0 / edge), n)
+    }
+}
+
+// Per-pixel distance to the limit set in pixels, with the fold count.
+pub fn render(group: &Kleinian, width: u32, height: u32, origin: (f64, f64), step: (f64, f64)) -> Vec<(f64, u32)> {
+    (0..height)
+        .into_par_iter()
+        .flat_map_iter(|py| {
+            (0..width).map(move |px| {
+                let x = origin.0 + (px as f64 + 0.5) * step.0;
+                let y = origin.1 + (py as f64 + 0.5) * step.1;
+                let (distance, folds) = group.estimate((x, y));
+                (distance / step.0, folds)
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,196 @@
+use crate::orbit::{Orbit, OrbitMetric};
+use crate::postprocess::ColorBuffer;
+use crate::FractalState;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum BlendMode {
+    Normal,
+    Multiply,
+    Screen,
+    Overlay,
+    SoftLight,
+    Difference,
+}
+
+impl BlendMode {
+    pub const ALL: [BlendMode; 6] = [
+        BlendMode::Normal,
+        BlendMode::Multiply,
+        BlendMode::Screen,
+        BlendMode::Overlay,
+        BlendMode::SoftLight,
+        BlendMode::Difference,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BlendMode::Normal => "Normal",
+            BlendMode::Multiply => "Multiply",
+            BlendMode::Screen => "Screen",
+            BlendMode::Overlay => "Overlay",
+            BlendMode::SoftLight => "Soft Light",
+            BlendMode::Difference => "Difference",
+        }
+    }
+
+    // Per-channel blend of `top` onto `base`, following the W3C compositing formulas.
+    pub fn blend(&self, base: f32, top: f32) -> f32 {
+        match self {
+            BlendMode::Normal => top,
+            BlendMode::Multiply => base * top,
+            BlendMode::Screen => base + top - base * top,
+            BlendMode::Overlay => BlendMode::hard_light(top, base),
+            BlendMode::SoftLight => {
+                let (b, t) = (base.clamp(0.0, 1.0), top.clamp(0.0, 1.0));
+                if t <= 0.5 {
+                    b - (1.0 - 2.0 * t) * b * (1.0 - b)
+                } else {
+                    let d = if b <= 0.25 {
+                        ((16.0 * b - 12.0) * b + 4.0) * b
+                    } else {
+                        b.sqrt()
+                    };
+                    b + (2.0 * t - 1.0) * (d - b)
+                }
+            }
+            BlendMode::Difference => (base - top).abs(),
+        }
+    }
+
+    fn hard_light(base: f32, top: f32) -> f32 {
+        let (b, t) = (base.clamp(0.0, 1.0), top.clamp(0.0, 1.0));
+        if t <= 0.5 {
+            2.0 * b * t
+        } else {
+            let t = 2.0 * t - 1.0;
+            b + t - b * t
+        }
+    }
+}
+
+// Restricts a layer to pixels where an orbit metric of another layer is below a threshold.
+#[derive(Clone)]
+pub struct LayerMask {
+    // None selects the base render, otherwise an index into the layer list.
+    pub source: Option<usize>,
+    pub metric: OrbitMetric,
+    pub threshold: f64,
+    pub invert: bool,
+}
+
+impl LayerMask {
+    pub fn evaluate(&self, orbits: &[Orbit], max_iter: u32) -> Vec<f32> {
+        orbits
+            .iter()
+            .map(|orbit| {
+                let inside = self.metric.value(orbit, max_iter) < self.threshold;
+                if inside != self.invert { 1.0 } else { 0.0 }
+            })
+            .collect()
+    }
+}
+
+// An extra fractal + coloring pass drawn over the base render. The viewport
+// (center and zoom) always follows the base state; everything else is the layer's own.
+#[derive(Clone)]
+pub struct Layer {
+    pub name: String,
+    pub visible: bool,
+    // Draws the base fractal with only this layer's coloring settings, so several
+    // coloring algorithms can be stacked over the same orbits.
+    pub recolors_base: bool,
+    // When false the layer's interior is transparent and lower layers show through.
+    pub fill_interior: bool,
+    pub blend_mode: BlendMode,
+    pub opacity: f32,
+    pub mask: Option<LayerMask>,
+    pub state: FractalState,
+}
+
+impl Layer {
+    pub fn new(name: String, state: FractalState) -> Self {
+        Self {
+            name,
+            visible: true,
+            recolors_base: false,
+            fill_interior: false,
+            blend_mode: BlendMode::Normal,
+            opacity: 1.0,
+            mask: None,
+            state,
+        }
+    }
+
+    pub fn state_for_view(&self, view: &FractalState) -> FractalState {
+        if self.recolors_base {
+            return view.with_coloring_of(&self.state);
+        }
+        let mut state = self.state.clone();
+        state.center_x = view.center_x;
+        state.center_y = view.center_y;
+        state.zoom = view.zoom;
+        state.width = view.width;
+        state.height = view.height;
+        state
+    }
+}
+
+pub fn composite(base: &mut ColorBuffer, rendered: &ColorBuffer, layer: &Layer, mask: Option<&[f32]>) {
+    for (i, ((dst, dst_alpha), (src, src_alpha))) in base
+        .pixels
+        .iter_mut()
+        .zip(base.alpha.iter_mut())
+        .zip(rendered.pixels.iter().zip(&rendered.alpha))
+        .enumerate()
+    {
+        let coverage = if layer.fill_interior { 1.0 } else { *src_alpha };
+        let masked = mask.map_or(1.0, |m| m[i]);
+        let a = coverage * layer.opacity * masked;
+        for (d, s) in dst.iter_mut().zip(src) {
+            let blended = layer.blend_mode.blend(*d, *s);
+            *d = *d * (1.0 - a) + blended * a;
+        }
+        *dst_alpha = a + *dst_alpha * (1.0 - a);
+    }
+}
+
+pub enum LayerAction {
+    MoveUp,
+    MoveDown,
+    Remove,
+}
+
+// Masks refer to their source layer by index, so they follow it as the list changes.
+pub fn apply_action(layers: &mut Vec<Layer>, index: usize, action: LayerAction) {
+    let other = match action {
+        LayerAction::MoveUp if index > 0 => index - 1,
+        LayerAction::MoveDown if index + 1 < layers.len() => index + 1,
+        LayerAction::Remove => {
+            layers.remove(index);
+            // A mask on the removed layer falls back to the base render.
+            remap_sources(layers, |j| match j.cmp(&index) {
+                std::cmp::Ordering::Less => Some(j),
+                std::cmp::Ordering::Equal => None,
+                std::cmp::Ordering::Greater => Some(j - 1),
+            });
+            return;
+        }
+        _ => return,
+    };
+    layers.swap(index, other);
+    remap_sources(layers, |j| {
+        Some(if j == index {
+            other
+        } else if j == other {
+            index
+        } else {
+            j
+        })
+    });
+}
+
+fn remap_sources(layers: &mut [Layer], remap: impl Fn(usize) -> Option<usize>) {
+    for mask in layers.iter_mut().filter_map(|layer| layer.mask.as_mut()) {
+        mask.source = mask.source.and_then(&remap);
+    }
+}
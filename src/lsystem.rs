@@ -0,0 +1,214 @@
+use std::collections::HashMap;
+
+// Expansions longer than this are refused rather than exhausting memory.
+const MAX_SYMBOLS: usize = 4_000_000;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum LSystemPreset {
+    Dragon,
+    KochSnowflake,
+    SierpinskiArrowhead,
+    HilbertCurve,
+    Plant,
+}
+
+impl LSystemPreset {
+    pub const ALL: [LSystemPreset; 5] = [
+        LSystemPreset::Dragon,
+        LSystemPreset::KochSnowflake,
+        LSystemPreset::SierpinskiArrowhead,
+        LSystemPreset::HilbertCurve,
+        LSystemPreset::Plant,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            LSystemPreset::Dragon => "Dragon curve",
+            LSystemPreset::KochSnowflake => "Koch snowflake",
+            LSystemPreset::SierpinskiArrowhead => "Sierpinski arrowhead",
+            LSystemPreset::HilbertCurve => "Hilbert curve",
+            LSystemPreset::Plant => "Fractal plant",
+        }
+    }
+
+    // Axiom, rules (one `X=...` per line), turn angle in degrees and depth.
+    fn definition(&self) -> (&'static str, &'static str, f64, u32) {
+        match self {
+            LSystemPreset::Dragon => ("FX", "X=X+YF+\nY=-FX-Y", 90.0, 12),
+            LSystemPreset::KochSnowflake => ("F--F--F", "F=F+F--F+F", 60.0, 5),
+            LSystemPreset::SierpinskiArrowhead => ("A", "A=B-A-B\nB=A+B+A", 60.0, 7),
+            LSystemPreset::HilbertCurve => ("A", "A=+BF-AFA-FB+\nB=-AF+BFB+FA-", 90.0, 6),
+            LSystemPreset::Plant => ("X", "X=F+[[X]-X]-F[-FX]+X\nF=FF", 25.0, 6),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct LSystem {
+    pub preset: LSystemPreset,
+    pub axiom: String,
+    pub rules: String,
+    pub angle: f64,
+    pub iterations: u32,
+    // Turtle path normalized into [-1, 1]², each segment tagged with its position
+    // along the drawing in [0, 1]. Rebuilt only when the settings change.
+    pub segments: Vec<Segment>,
+    pub error: Option<String>,
+}
+
+#[derive(Clone, Copy)]
+pub struct Segment {
+    pub from: (f64, f64),
+    pub to: (f64, f64),
+    pub t: f32,
+}
+
+impl Default for LSystem {
+    fn default() -> Self {
+        let mut system = Self {
+            preset: LSystemPreset::Dragon,
+            axiom: String::new(),
+            rules: String::new(),
+            angle: 0.0,
+            iterations: 0,
+            segments: Vec::new(),
+            error: None,
+        };
+        system.load(LSystemPreset::Dragon);
+        system
+    }
+}
+
+impl LSystem {
+    pub fn load(&mut self, preset: LSystemPreset) {
+        let (axiom, rules, angle, iterations) = preset.definition();
+        self.preset = preset;
+        self.axiom = axiom.to_string();
+        self.rules = rules.to_string();
+        self.angle = angle;
+        self.iterations = iterations;
+        self.rebuild();
+    }
+
+    pub fn rebuild(&mut self) {
+        match parse_rules(&self.rules).and_then(|rules| expand(&self.axiom, &rules, self.iterations)) {
+            Ok(program) => {
+                self.segments = normalize(turtle(&program, self.angle.to_radians()));
+                self.error = None;
+            }
+            Err(message) => {
+                self.segments.clear();
+                self.error = Some(message);
+            }
+        }
+    }
+}
+
+fn parse_rules(text: &str) -> Result<HashMap<char, String>, String> {
+    let mut rules = HashMap::new();
+    for line in text.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        let Some((lhs, rhs)) = line.split_once('=') else {
+            return Err(format!("`{}` is not of the form X=...", line));
+        };
+        let mut symbol = lhs.trim().chars();
+        match (symbol.next(), symbol.next()) {
+            (Some(ch), None) => {
+                rules.insert(ch, rhs.trim().to_string());
+            }
+            _ => return Err(format!("rule `{}` must rewrite a single symbol", line)),
+        }
+    }
+    Ok(rules)
+}
+
+fn expand(axiom: &str, rules: &HashMap<char, String>, iterations: u32) -> Result<String, String> {
+    let mut current = axiom.to_string();
+    for _ in 0..iterations {
+        let mut next = String::with_capacity(current.len() * 2);
+        for ch in current.chars() {
+            match rules.get(&ch) {
+                Some(replacement) => next.push_str(replacement),
+                None => next.push(ch),
+            }
+            if next.len() > MAX_SYMBOLS {
+                return Err("expansion too long; lower the iterations".to_string());
+            }
+        }
+        current = next;
+    }
+    Ok(current)
+}
+
+// F, G, A and B draw a unit step, f moves without drawing, + and - turn, and
+// [ ] push and pop the turtle. Everything else only takes part in rewriting.
+fn turtle(program: &str, angle: f64) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut position = (0.0, 0.0);
+    let mut heading = 0.0f64;
+    let mut stack = Vec::new();
+    for ch in program.chars() {
+        match ch {
+            'F' | 'G' | 'A' | 'B' | 'f' => {
+                let next = (position.0 + heading.cos(), position.1 + heading.sin());
+                if ch != 'f' {
+                    segments.push(Segment { from: position, to: next, t: 0.0 });
+                }
+                position = next;
+            }
+            '+' => heading += angle,
+            '-' => heading -= angle,
+            '[' => stack.push((position, heading)),
+            ']' => {
+                if let Some((p, h)) = stack.pop() {
+                    position = p;
+                    heading = h;
+                }
+            }
+            _ => {}
+        }
+    }
+    segments
+}
+
+// Scales the drawing to fit [-1, 1]² about the origin, with y pointing up.
+fn normalize(mut segments: Vec<Segment>) -> Vec<Segment> {
+    if segments.is_empty() {
+        return segments;
+    }
+    let (mut min, mut max) = ((f64::MAX, f64::MAX), (f64::MIN, f64::MIN));
+    for s in &segments {
+        for p in [s.from, s.to] {
+            min = (min.0.min(p.0), min.1.min(p.1));
+            max = (max.0.max(p.0), max.1.max(p.1));
+        }
+    }
+    let center = ((min.0 + max.0) / 2.0, (min.1 + max.1) / 2.0);
+    let scale = 2.0 / (max.0 - min.0).max(max.1 - min.1).max(1e-9);
+    let count = segments.len() as f32;
+    for (i, s) in segments.iter_mut().enumerate() {
+        s.from = ((s.from.0 - center.0) * scale, (s.from.1 - center.1) * scale);
+        s.to = ((s.to.0 - center.0) * scale, (s.to.1 - center.1) * scale);
+        s.t = i as f32 / count;
+    }
+    segments
+}
+
+// Coverage and path position per pixel. Segments are sampled at half-pixel
+// steps; later segments paint over earlier ones.
+pub fn rasterize(segments: &[Segment], width: u32, height: u32, origin: (f64, f64), step: (f64, f64)) -> Vec<Option<f32>> {
+    let mut out = vec![None; (width * height) as usize];
+    let to_pixel = |(x, y): (f64, f64)| ((x - origin.0) / step.0, (-y - origin.1) / step.1);
+    for s in segments {
+        let (x0, y0) = to_pixel(s.from);
+        let (x1, y1) = to_pixel(s.to);
+        let samples = ((x1 - x0).abs().max((y1 - y0).abs()) * 2.0).ceil().clamp(1.0, 1e5) as usize;
+        for k in 0..=samples {
+            let f = k as f64 / samples as f64;
+            let (x, y) = (x0 + (x1 - x0) * f, y0 + (y1 - y0) * f);
+            if x >= 0.0 && y >= 0.0 && x < width as f64 && y < height as f64 {
+                out[y as usize * width as usize + x as usize] = Some(s.t);
+            }
+        }
+    }
+    out
+}
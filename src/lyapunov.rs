@@ -0,0 +1,75 @@
+use crate::orbit::Orbit;
+
+// Transient iterations skipped before averaging, as a fraction of the total.
+const WARMUP_FRACTION: u32 = 10;
+// x0 of the logistic map; 0.5 is the critical point, so it finds stable cycles fastest.
+const START: f64 = 0.5;
+
+// Forcing string such as "AABAB": which of the two rates drives each step,
+// repeated for the length of the orbit.
+#[derive(Clone)]
+pub struct Sequence {
+    pub source: String,
+    // true where the step uses B.
+    steps: Vec<bool>,
+}
+
+impl PartialEq for Sequence {
+    fn eq(&self, other: &Self) -> bool {
+        self.steps == other.steps
+    }
+}
+
+impl Default for Sequence {
+    fn default() -> Self {
+        Self::parse("AB").expect("default sequence parses")
+    }
+}
+
+impl Sequence {
+    // Letters are case-insensitive and whitespace is ignored.
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let steps = source
+            .chars()
+            .filter(|ch| !ch.is_whitespace())
+            .map(|ch| match ch.to_ascii_uppercase() {
+                'A' => Ok(false),
+                'B' => Ok(true),
+                other => Err(format!("`{}` is not A or B", other)),
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if steps.is_empty() {
+            return Err("the sequence needs at least one letter".to_string());
+        }
+        Ok(Self { source: source.trim().to_uppercase(), steps })
+    }
+}
+
+// Lyapunov exponent of the logistic map x -> r x (1 - x), where r follows the
+// sequence with A = a and B = b. Negative means the orbit settles on a stable
+// cycle, positive means chaos.
+pub fn exponent(a: f64, b: f64, sequence: &Sequence, iterations: u32) -> f64 {
+    let warmup = iterations / WARMUP_FRACTION;
+    let mut x = START;
+    let mut sum = 0.0;
+    for n in 0..warmup + iterations {
+        let r = if sequence.steps[n as usize % sequence.steps.len()] { b } else { a };
+        if n >= warmup {
+            // Superstable points give ln(0); clamp so they read as very stable.
+            sum += (r * (1.0 - 2.0 * x)).abs().max(1e-300).ln();
+        }
+        x = r * x * (1.0 - x);
+        if !x.is_finite() {
+            return f64::INFINITY;
+        }
+    }
+    sum / iterations.max(1) as f64
+}
+
+pub fn orbit(a: f64, b: f64, sequence: &Sequence, iterations: u32) -> Orbit {
+    let mut orbit = Orbit::new();
+    orbit.iterations = iterations;
+    orbit.exponent = exponent(a, b, sequence, iterations);
+    orbit.escaped = orbit.exponent > 0.0;
+    orbit
+}
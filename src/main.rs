@@ -5,9 +5,142 @@ use num_complex::Complex64;
 use rayon::prelude::*;
 use parking_lot::RwLock;
 use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
 use chrono::Local;
 use num_cpus;
-use rand::Rng;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+mod animation;
+mod antialias;
+mod apollonian;
+mod attractor;
+mod backend;
+mod background;
+mod bookmarks;
+mod buddhabrot;
+mod color;
+mod coloring;
+mod compare;
+mod cross_section;
+mod cubic;
+mod cycling;
+mod escape;
+mod finder;
+mod formula;
+mod gallery;
+mod gpu;
+mod icc;
+mod ifs;
+mod julia_tour;
+mod kleinian;
+mod layers;
+mod lsystem;
+mod lyapunov;
+mod mandelbulb;
+mod navigation;
+mod orbit;
+mod orbitplot;
+mod palette;
+mod perf;
+mod polynomial;
+mod postprocess;
+mod quality;
+mod quaternion;
+mod rays;
+mod raymarch;
+mod region;
+mod tetration;
+mod texture;
+mod tiles;
+mod recorder;
+mod wallpaper;
+
+use animation::{Easing, Morph, MorphFrame, Playback, Timeline};
+use antialias::{Accumulator, Refinement, SamplePattern, ADAPTIVE_INITIAL_SAMPLES, SAMPLE_COUNTS};
+use background::{Background, BackgroundKind, TransparentRegion};
+use bookmarks::Bookmark;
+use color::{ColorProfile, ColorSpace};
+use coloring::{ColoringMode, Lighting, OrbitTrap, SineWaves, Transfer, TransferCurve, TrapShape};
+use compare::{CompareTool, Comparison};
+use cross_section::CrossSection;
+use finder::{FinderTarget, FinderTool};
+use gallery::{GalleryClient, GalleryReply, SharedLocation};
+use julia_tour::{JuliaTour, TourPath};
+use layers::{BlendMode, Layer, LayerAction, LayerMask};
+use navigation::Navigator;
+use orbit::{Orbit, OrbitMetric, RecentIterates};
+use orbitplot::{OrbitMap, OrbitPlot};
+use palette::{Interpolation, Palette, MIN_STOPS};
+use backend::{Backend, BackendManager, GpuContext};
+use buddhabrot::BuddhabrotSettings;
+use perf::{PerfOverlay, TileRecorder, TileTiming};
+use apollonian::Apollonian;
+use attractor::{AttractorKind, AttractorSettings};
+use cubic::{CubicParameter, CubicSlice};
+use cycling::ColorCycle;
+use tiles::{CancelToken, RenderJob, RenderedFrame, TILE_ROWS};
+use escape::{EscapeParams, Kernel, LANES};
+use formula::Formula;
+use ifs::{IfsPreset, IfsSettings};
+use kleinian::Kleinian;
+use lsystem::{LSystem, LSystemPreset};
+use lyapunov::Sequence;
+use polynomial::{Polynomial, RootMethod};
+use texture::{TextureImage, TextureMapping, TextureSource};
+use mandelbulb::Mandelbulb;
+use quaternion::QuaternionJulia;
+use raymarch::{Scene3d, Surface};
+use rays::RayOverlay;
+use postprocess::{ColorBuffer, Dither, PostSettings};
+use quality::{Precision, QualityPreset, QualitySettings};
+use recorder::PathRecorder;
+use region::RegionBoost;
+use wallpaper::Monitor;
+
+const RANDOMIZE_ATTEMPTS: usize = 12;
+const RANDOMIZE_MIN_SCORE: f64 = 0.2;
+
+// Distance at which a Newton orbit counts as converged to a root.
+const NEWTON_TOLERANCE: f64 = 1e-6;
+
+// Magnet, Nova and Lambda orbits can wander past |z| = 2 and still stay bounded,
+// so they only count as diverged past this |z|^2. Magnet orbits settle on the fixed point z = 1.
+const CONVERGENT_BAILOUT: f64 = 1e4;
+const MAGNET_TOLERANCE: f64 = 1e-6;
+// Nova orbits have no fixed target; they settle once a step moves z less than this.
+const NOVA_TOLERANCE: f64 = 1e-6;
+// cos(πz) grows like e^(π|Im z|), so Collatz orbits either stay near the real axis
+// or overflow within a step or two of leaving it; a large |z|^2 catches them before
+// they turn into infinities.
+const COLLATZ_BAILOUT: f64 = 1e10;
+// The reciprocal families map large z back near c, so they never escape in the
+// usual sense. A huge iterate instead means the previous one all but hit a pole,
+// and exact hits give infinities, so both end the orbit.
+const POLE_BAILOUT: f64 = 1e6;
+// c·sin(z) and c·cosh(z) diverge through |Im z|, c·exp(z) through Re z; past this the
+// next iterate is astronomically large.
+const TRANSCENDENTAL_BAILOUT: f64 = 50.0;
+// Pickover's biomorph test: an orbit leaves once either component passes this.
+const BIOMORPH_BAILOUT: f64 = 10.0;
+// The distance estimate |z| ln|z| / |dz| and the smoothing of averaging colorings
+// are only accurate once |z| is large.
+const SMOOTH_BAILOUT: f64 = 1e6;
+// Highest integer power the SIMD and GPU kernels raise z to, by repeated multiplication.
+const KERNEL_MAX_POWER: u32 = 16;
+// Smallest pixel step, relative to the largest coordinate on screen, the GPU's f32
+// arithmetic resolves without visible blocking; deeper views stay on the CPU.
+const GPU_MIN_STEP: f64 = 1.0 / 65536.0;
+// How long after the last drag or zoom step the live view switches from coarse
+// frames back to full resolution.
+const INTERACTION_SETTLE: Duration = Duration::from_millis(150);
+
+// Sub-pixel offset for pixel (x, y), or None when the pixel is not sampled this pass.
+type SampleFn<'a> = dyn Fn(u32, u32) -> Option<(f64, f64)> + Sync + 'a;
+// Receives the first row and the orbits of each finished tile.
+type TileFn<'a> = dyn Fn(u32, &[Orbit]) + Sync + 'a;
 
 #[derive(Clone, Copy, PartialEq)]
 enum FractalType {
@@ -16,8 +149,244 @@ enum FractalType {
     Flower,
     Phoenix,
     Butterfly,
+    Newton,
+    Julia,
+    BurningShip,
+    Tricorn,
+    Buddhabrot,
+    Lyapunov,
+    MagnetI,
+    MagnetII,
+    Nova,
+    Custom,
+    Lambda,
+    Mandelbulb,
+    QuaternionJulia,
+    Ifs,
+    LSystem,
+    Attractor,
+    Kleinian,
+    Collatz,
+    SinZ,
+    ExpZ,
+    CoshZ,
+    Apollonian,
+    Ducks,
+    Cubic,
+    InverseSquare,
+    Quotient,
+    OrbitPlot,
+    Tetration,
+}
+
+impl FractalType {
+    const ALL: [FractalType; 33] = [
+        FractalType::Classic,
+        FractalType::Spiral,
+        FractalType::Flower,
+        FractalType::Phoenix,
+        FractalType::Butterfly,
+        FractalType::Newton,
+        FractalType::Julia,
+        FractalType::BurningShip,
+        FractalType::Tricorn,
+        FractalType::Buddhabrot,
+        FractalType::Lyapunov,
+        FractalType::MagnetI,
+        FractalType::MagnetII,
+        FractalType::Nova,
+        FractalType::Custom,
+        FractalType::Lambda,
+        FractalType::Mandelbulb,
+        FractalType::QuaternionJulia,
+        FractalType::Ifs,
+        FractalType::LSystem,
+        FractalType::Attractor,
+        FractalType::Kleinian,
+        FractalType::Collatz,
+        FractalType::SinZ,
+        FractalType::ExpZ,
+        FractalType::CoshZ,
+        FractalType::Apollonian,
+        FractalType::Ducks,
+        FractalType::Cubic,
+        FractalType::InverseSquare,
+        FractalType::Quotient,
+        FractalType::OrbitPlot,
+        FractalType::Tetration,
+    ];
+
+    fn name(&self) -> &'static str {
+        match self {
+            FractalType::Classic => "Classic",
+            FractalType::Spiral => "Spiral",
+            FractalType::Flower => "Flower",
+            FractalType::Phoenix => "Phoenix",
+            FractalType::Butterfly => "Butterfly",
+            FractalType::Newton => "Newton",
+            FractalType::Julia => "Julia",
+            FractalType::BurningShip => "Burning Ship",
+            FractalType::Tricorn => "Tricorn",
+            FractalType::Buddhabrot => "Buddhabrot",
+            FractalType::Lyapunov => "Lyapunov",
+            FractalType::MagnetI => "Magnet I",
+            FractalType::MagnetII => "Magnet II",
+            FractalType::Nova => "Nova",
+            FractalType::Custom => "Custom",
+            FractalType::Lambda => "Lambda",
+            FractalType::Mandelbulb => "Mandelbulb",
+            FractalType::QuaternionJulia => "Quaternion Julia",
+            FractalType::Ifs => "IFS",
+            FractalType::LSystem => "L-System",
+            FractalType::Attractor => "Strange Attractor",
+            FractalType::Kleinian => "Kleinian",
+            FractalType::Collatz => "Collatz",
+            FractalType::SinZ => "c·sin(z)",
+            FractalType::ExpZ => "c·exp(z)",
+            FractalType::CoshZ => "c·cosh(z)",
+            FractalType::Apollonian => "Apollonian Gasket",
+            FractalType::Ducks => "Ducks",
+            FractalType::Cubic => "Cubic Slice",
+            FractalType::InverseSquare => "1/z² + c",
+            FractalType::Quotient => "(z² + c)/(z² − c)",
+            FractalType::OrbitPlot => "Orbit Plot",
+            FractalType::Tetration => "Tetration",
+        }
+    }
+
+    // Raymarched types: the view is a camera orbiting the origin rather than a window on the plane.
+    fn is_3d(&self) -> bool {
+        matches!(self, FractalType::Mandelbulb | FractalType::QuaternionJulia)
+    }
+
+    // Types whose pixels are colored by an orbit escaping a bailout, so alternate
+    // escape tests apply to them.
+    fn is_escape_time(&self) -> bool {
+        !self.is_3d()
+            && !matches!(
+                self,
+                FractalType::Newton
+                    | FractalType::Lyapunov
+                    | FractalType::Buddhabrot
+                    | FractalType::Ifs
+                    | FractalType::LSystem
+                    | FractalType::Attractor
+                    | FractalType::Kleinian
+                    | FractalType::Apollonian
+                    | FractalType::OrbitPlot
+                    // These two run their own kernels with their own escape tests.
+                    | FractalType::Cubic
+                    | FractalType::Tetration
+            )
+    }
+
+    // Escape-time types whose bounded orbits settle on attracting cycles. Ducks
+    // never escape, so they are left out.
+    fn has_interior(&self) -> bool {
+        self.is_escape_time() && *self != FractalType::Ducks
+    }
+
+    // Types rendered by coloring one orbit per pixel, as opposed to plotted,
+    // accumulated or raymarched ones.
+    fn colors_orbits(&self) -> bool {
+        !self.is_3d()
+            && !matches!(
+                self,
+                FractalType::Buddhabrot
+                    | FractalType::Ifs
+                    | FractalType::LSystem
+                    | FractalType::Attractor
+                    | FractalType::Kleinian
+                    | FractalType::Apollonian
+                    | FractalType::OrbitPlot
+            )
+    }
+
+    // Zoom and center (x, y) that frame the set when the type is selected, for
+    // types whose interesting region is away from the default view.
+    fn default_view(&self) -> Option<(f64, f64, f64)> {
+        match self {
+            FractalType::BurningShip => Some((1.8, 0.1, -0.5)),
+            // The logistic map is interesting for rates between 2 and 4.
+            FractalType::Lyapunov => Some((2.5, 3.5, 3.0)),
+            FractalType::MagnetI | FractalType::MagnetII | FractalType::Lambda => Some((1.2, 2.7, 0.0)),
+            FractalType::Nova => Some((1.5, 0.8, 0.0)),
+            // L-system drawings and the gasket's outer circle are normalized to [-1, 1]².
+            FractalType::LSystem | FractalType::Apollonian => Some((2.0, 0.9375, 0.0)),
+            // The Maskit limit set lives in the strip 0 <= im <= 2.
+            FractalType::Kleinian => Some((2.0, 0.9375, 0.95)),
+            // The real axis from about -3.5 to 3.5, where the integer orbits live.
+            FractalType::Collatz => Some((1.2, 1.55, 0.0)),
+            FractalType::SinZ | FractalType::ExpZ | FractalType::CoshZ => Some((0.8, 2.35, 0.0)),
+            FractalType::Ducks => Some((1.2, 1.0, 0.0)),
+            FractalType::Cubic => Some((1.6, 1.17, 0.0)),
+            // Both reciprocal families are centered on the origin; 1/z² + c spreads further.
+            FractalType::InverseSquare => Some((0.6, 3.125, 0.0)),
+            FractalType::Quotient => Some((1.0, 1.875, 0.0)),
+            // Centered near e^(1/e), where the infinite tower stops converging on the real axis.
+            FractalType::Tetration => Some((1.0, 2.875, 0.0)),
+            _ => None,
+        }
+    }
+
+    // Framing applied with the type defaults: the type's own view, else the
+    // startup view, or for Julia sets one centered on the origin.
+    fn home_view(&self) -> (f64, f64, f64) {
+        self.default_view().unwrap_or(match self {
+            FractalType::Julia => (1.6, 1.17, 0.0),
+            _ => (1.0, -0.5, 0.0),
+        })
+    }
+
+    // Framing for the type's Julia plane, where pixels are starting points. Most sets
+    // sit around the origin like the quadratic one.
+    fn julia_view(&self) -> (f64, f64, f64) {
+        match self {
+            // λz(1 - z) is symmetric about z = 1/2.
+            FractalType::Lambda => (1.6, 1.671875, 0.0),
+            // Matches the Classic Phoenix button.
+            FractalType::Phoenix => (1.5, 1.25, 0.0),
+            _ => FractalType::Julia.home_view(),
+        }
+    }
+
+    // Palette and iteration count that suit the type, applied with its framing when
+    // type defaults are on. Types that color without the palette have none.
+    fn default_look(&self) -> Option<(&'static [[u8; 3]], u32)> {
+        // Deep blue through white to amber, after Ultra Fractal's default gradient.
+        const CLASSIC: &[[u8; 3]] = &[[0, 7, 100], [32, 107, 203], [237, 255, 255], [255, 170, 0], [0, 2, 0]];
+        const ICE: &[[u8; 3]] = &[[4, 12, 40], [40, 90, 170], [150, 210, 240], [250, 250, 255], [90, 150, 200]];
+        const FIRE: &[[u8; 3]] = &[[0, 0, 0], [120, 10, 0], [230, 80, 0], [255, 200, 40], [255, 255, 220]];
+        const OCEAN: &[[u8; 3]] = &[[2, 20, 45], [0, 95, 115], [80, 185, 170], [235, 225, 180], [20, 60, 90]];
+        const SUNSET: &[[u8; 3]] = &[[40, 10, 70], [160, 30, 110], [245, 110, 70], [255, 220, 150], [90, 30, 100]];
+        match self {
+            FractalType::Classic
+            | FractalType::Spiral
+            | FractalType::Flower
+            | FractalType::Phoenix
+            | FractalType::Butterfly
+            | FractalType::Tricorn
+            | FractalType::Custom => Some((CLASSIC, 1000)),
+            FractalType::Cubic => Some((CLASSIC, 500)),
+            FractalType::Julia => Some((ICE, 500)),
+            FractalType::BurningShip => Some((FIRE, 500)),
+            FractalType::MagnetI
+            | FractalType::MagnetII
+            | FractalType::Nova
+            | FractalType::Lambda
+            | FractalType::InverseSquare
+            | FractalType::Quotient => Some((OCEAN, 300)),
+            FractalType::SinZ
+            | FractalType::ExpZ
+            | FractalType::CoshZ
+            | FractalType::Tetration
+            | FractalType::Collatz => Some((SUNSET, 200)),
+            _ => None,
+        }
+    }
 }
 
+#[derive(Clone)]
 struct FractalState {
     fractal_type: FractalType,
     zoom: f64,
@@ -27,11 +396,230 @@ struct FractalState {
     hue_offset: f32,
     saturation: f32,
     value: f32,
+    // Gradient the escape-time value is mapped through, shifted by the hue offset.
+    palette: Palette,
+    // Space the palette blends and hues rotate in, and the curve between stops.
+    color_space: ColorSpace,
+    interpolation: Interpolation,
+    // Number of stripes per turn of arg z for stripe-average coloring.
+    stripe_density: f64,
+    // Binary decomposition shades by the escaping iterate's full angle instead of its sign.
+    external_angle: bool,
+    // Final-angle coloring multiplies its color by the escape-time color.
+    angle_by_escape: bool,
+    // Bounded orbits are colored by the period of the cycle they settle on.
+    interior_period: bool,
+    lighting: Lighting,
+    texture: TextureMapping,
+    transfer: Transfer,
+    waves: SineWaves,
     width: u32,
     height: u32,
     needs_update: bool,
     power: f64,
     secondary_param: f64,  // For additional variations
+    coloring: ColoringMode,
+    trap: OrbitTrap,
+    polynomial: Polynomial,
+    root_method: RootMethod,
+    // Pickover's per-component escape test in place of the usual bailout.
+    biomorph: bool,
+    cubic: CubicSlice,
+    lyapunov_sequence: Sequence,
+    julia_seed: Complex64,
+    // How strongly slow-converging Newton pixels are darkened; 0 gives flat basins.
+    convergence_shading: f32,
+    // Types with their own Julia plane (Nova, Lambda, Phoenix, Burning Ship, Ducks) render it instead of the
+    // parameter plane: the pixel is z0 and the seed is c.
+    julia_plane: bool,
+    formula: Formula,
+    // Imaginary part of the exponent for Classic and Julia; `power` is the real part.
+    power_im: f64,
+    // Weight of the previous iterate in the Phoenix recurrence.
+    phoenix_p: Complex64,
+}
+
+impl FractalState {
+    // Origin and per-pixel step of the on-screen mapping at the given resolution.
+    fn view_mapping(&self, width: u32, height: u32) -> ((f64, f64), (f64, f64)) {
+        let scale = 2.5 / self.zoom;
+        (
+            (self.center_x - 2.5 * scale, self.center_y - scale),
+            (3.5 * scale / width as f64, 2.0 * scale / height as f64),
+        )
+    }
+
+    // Classic and Julia take a full complex exponent, including zero and negative ones.
+    fn has_complex_power(&self) -> bool {
+        matches!(self.fractal_type, FractalType::Classic | FractalType::Julia)
+    }
+
+    // This fractal and view, colored the way `other` is.
+    fn with_coloring_of(&self, other: &FractalState) -> FractalState {
+        FractalState {
+            hue_offset: other.hue_offset,
+            saturation: other.saturation,
+            value: other.value,
+            palette: other.palette.clone(),
+            color_space: other.color_space,
+            interpolation: other.interpolation,
+            stripe_density: other.stripe_density,
+            external_angle: other.external_angle,
+            angle_by_escape: other.angle_by_escape,
+            interior_period: other.interior_period,
+            lighting: other.lighting,
+            texture: other.texture,
+            transfer: other.transfer,
+            waves: other.waves,
+            coloring: other.coloring,
+            trap: other.trap,
+            convergence_shading: other.convergence_shading,
+            ..self.clone()
+        }
+    }
+
+    // The trap to measure orbits against, only while it is used for coloring.
+    fn active_trap(&self) -> Option<&OrbitTrap> {
+        self.uses_trap().then_some(&self.trap)
+    }
+
+    // Palette position of an escape count, through the transfer curve.
+    fn escape_position(&self, iterations: u32) -> f32 {
+        self.transfer.apply(iterations as f32, self.max_iter)
+    }
+
+    fn uses_trap(&self) -> bool {
+        self.coloring == ColoringMode::OrbitTrap
+            || (self.coloring == ColoringMode::Texture && self.texture.source == TextureSource::Trap)
+    }
+
+    // Distance estimation and slope shading need the derivative of the iteration,
+    // which only the z^p + c families carry in closed form.
+    fn tracks_derivative(&self) -> bool {
+        matches!(
+            self.coloring,
+            ColoringMode::DistanceEstimate | ColoringMode::SlopeShading | ColoringMode::DerivativeMagnitude
+        ) && matches!(self.fractal_type, FractalType::Classic | FractalType::Julia)
+    }
+
+    // Squared radius past which an orbit counts as escaped. Convergent types leave
+    // room for orbits that wander before settling; smooth colorings need a large
+    // radius for the fractional escape count to be accurate.
+    fn bailout(&self) -> f64 {
+        if self.fractal_type == FractalType::Collatz {
+            COLLATZ_BAILOUT
+        } else if matches!(self.fractal_type, FractalType::InverseSquare | FractalType::Quotient) {
+            POLE_BAILOUT
+        } else if matches!(
+            self.fractal_type,
+            FractalType::MagnetI | FractalType::MagnetII | FractalType::Nova | FractalType::Lambda
+        ) {
+            CONVERGENT_BAILOUT
+        } else if self.tracks_derivative()
+            || self.coloring.is_average()
+            || matches!(
+                self.coloring,
+                ColoringMode::BinaryDecomposition
+                    | ColoringMode::Texture
+                    | ColoringMode::FinalAngle
+                    | ColoringMode::ExponentialSmoothing
+            )
+        {
+            SMOOTH_BAILOUT
+        } else {
+            4.0
+        }
+    }
+
+    // The vectorized kernel's settings for this view, if it has one: the z^p + c
+    // families at integer powers, colored only from the escape count, final iterate
+    // and closest approach to 0.
+    fn escape_params(&self) -> Option<EscapeParams> {
+        let kernel = match self.fractal_type {
+            FractalType::Classic | FractalType::Julia => Kernel::Multibrot,
+            FractalType::BurningShip => Kernel::BurningShip,
+            FractalType::Tricorn => Kernel::Tricorn,
+            _ => return None,
+        };
+        let colored = matches!(
+            self.coloring,
+            ColoringMode::EscapeTime
+                | ColoringMode::AtomDomains
+                | ColoringMode::BinaryDecomposition
+                | ColoringMode::SineWaves
+                | ColoringMode::FinalAngle
+        ) || (self.coloring == ColoringMode::Texture && self.texture.source == TextureSource::FinalZ);
+        let power = self.power as u32;
+        let bailout = self.bailout();
+        let fits = self.power_im == 0.0 && self.power == power as f64 && (2..=KERNEL_MAX_POWER).contains(&power);
+        if !colored || !fits || self.biomorph || self.colors_periods() {
+            return None;
+        }
+        let julia = self.fractal_type == FractalType::Julia || (self.has_julia_plane() && self.julia_plane);
+        Some(EscapeParams {
+            kernel,
+            julia,
+            seed: self.julia_seed,
+            power,
+            max_iter: self.max_iter,
+            bailout,
+        })
+    }
+
+    // Whether pixels in the main cardioid and period-2 bulb can be filled in as bounded
+    // without iterating: the power-2 Mandelbrot set under a coloring that leaves
+    // bounded orbits black, so nothing reads the orbit they would have traced.
+    fn skips_main_components(&self) -> bool {
+        let colored = matches!(
+            self.coloring,
+            ColoringMode::EscapeTime
+                | ColoringMode::DistanceEstimate
+                | ColoringMode::StripeAverage
+                | ColoringMode::TriangleInequality
+                | ColoringMode::CurvatureAverage
+                | ColoringMode::BinaryDecomposition
+                | ColoringMode::SlopeShading
+                | ColoringMode::SineWaves
+                | ColoringMode::FinalAngle
+                | ColoringMode::ExponentialSmoothing
+        ) || (self.coloring == ColoringMode::Texture && self.texture.source == TextureSource::FinalZ);
+        self.fractal_type == FractalType::Classic
+            && self.power == 2.0
+            && self.power_im == 0.0
+            && colored
+            && !self.biomorph
+            && !self.colors_periods()
+    }
+
+    fn colors_periods(&self) -> bool {
+        self.interior_period && self.fractal_type.has_interior()
+    }
+
+    fn has_julia_plane(&self) -> bool {
+        matches!(
+            self.fractal_type,
+            FractalType::Nova
+                | FractalType::Lambda
+                | FractalType::Phoenix
+                | FractalType::BurningShip
+                | FractalType::Ducks
+        )
+    }
+
+    // Whether pixels are starting points iterated with `julia_seed` as the parameter.
+    fn in_julia_plane(&self) -> bool {
+        self.fractal_type == FractalType::Julia || (self.has_julia_plane() && self.julia_plane)
+    }
+
+    fn pixel_to_complex(&self, x: f64, y: f64) -> Complex64 {
+        let (origin, step) = self.view_mapping(self.width, self.height);
+        Complex64::new(origin.0 + x * step.0, origin.1 + y * step.1)
+    }
+
+    fn complex_to_pixel(&self, z: Complex64) -> Pos2 {
+        let (origin, step) = self.view_mapping(self.width, self.height);
+        Pos2::new(((z.re - origin.0) / step.0) as f32, ((z.im - origin.1) / step.1) as f32)
+    }
 }
 
 struct FractalApp {
@@ -40,352 +628,3555 @@ struct FractalApp {
     drag_start: Option<Pos2>,
     drag_start_center: Option<(f64, f64)>,
     thread_count: usize,
+    recorder: PathRecorder,
+    timeline: Timeline,
+    playback: Option<Playback>,
+    monitors: Vec<Monitor>,
+    wallpaper_status: String,
+    seed_input: String,
+    last_seed: Option<u64>,
+    // Why the seed field could not be used, shown until the next roll.
+    seed_error: String,
+    // Randomize keeps the current palette, hue offset, saturation and value.
+    lock_palette: bool,
+    // Switching fractal type also applies its curated palette, framing and iteration count.
+    type_defaults: bool,
+    bookmarks: Vec<Bookmark>,
+    bookmark_name: String,
+    morph_from: usize,
+    morph_to: usize,
+    morph_duration: f64,
+    morph: Option<Morph>,
+    morph_status: String,
+    cross_section_mode: bool,
+    cross_section: Option<CrossSection>,
+    cross_section_handle: Option<usize>,
+    image_origin: Pos2,
+    color_buffer: Option<ColorBuffer>,
+    post: PostSettings,
+    regrade: bool,
+    cycle: ColorCycle,
+    // Preview orbits and their per-pixel step, kept for recoloring until the view changes.
+    stored_orbits: Option<(Vec<Orbit>, (f64, f64))>,
+    // The view, quality and backend the stored orbits were traced with.
+    traced_view: Option<(FractalState, QualitySettings, Backend)>,
+    // Only coloring changed: recolor the stored orbits instead of iterating again.
+    recolor: bool,
+    // Preview rendering on a worker thread, if one is in progress.
+    render_job: Option<Arc<RenderJob>>,
+    // Last drag or zoom step, and whether the live view was last rendered coarse
+    // because of one.
+    last_interaction: Option<Instant>,
+    coarse_frame: bool,
+    // Full-resolution render for Save Image, posted back by its worker thread.
+    export_render: Option<Receiver<String>>,
+    export_status: String,
+    texture_image: TextureImage,
+    texture_status: String,
+    layers: Vec<Layer>,
+    background: Background,
+    background_status: String,
+    quality: QualitySettings,
+    navigator: Navigator,
+    export_profile: ColorProfile,
+    // Saved images get an alpha channel with this region cut away.
+    transparent_export: Option<TransparentRegion>,
+    gallery: GalleryClient,
+    gallery_endpoint: String,
+    gallery_name: String,
+    gallery_status: String,
+    gallery_link: Option<String>,
+    gallery_entries: Vec<SharedLocation>,
+    gallery_open: bool,
+    compare: CompareTool,
+    compare_texture: Option<egui::TextureHandle>,
+    compare_render: Option<Receiver<Comparison>>,
+    region: RegionBoost,
+    finder: FinderTool,
+    julia_tour: JuliaTour,
+    tour_status: String,
+    root_drag: Option<usize>,
+    perf: PerfOverlay,
+    backends: BackendManager,
+    rays: RayOverlay,
+    buddhabrot: BuddhabrotSettings,
+    scene: Scene3d,
+    bulb: Mandelbulb,
+    quaternion: QuaternionJulia,
+    ifs: IfsSettings,
+    lsystem: LSystem,
+    attractor: AttractorSettings,
+    kleinian: Kleinian,
+    apollonian: Apollonian,
+    orbit_plot: OrbitPlot,
+    formula_input: String,
+    formula_error: Option<String>,
+    sequence_input: String,
+    sequence_error: Option<String>,
+    // Index of the palette stop being edited.
+    palette_stop: usize,
+    palette_path: String,
+    palette_status: String,
+    // Palettes saved in the settings directory, by name.
+    saved_palettes: Vec<(String, Palette)>,
+    palette_name: String,
+    seed_pick: bool,
+    // Type and view (zoom, center) to return to after picking a Julia seed.
+    parameter_view: Option<(FractalType, f64, f64, f64)>,
+}
+
+impl Default for FractalState {
+    fn default() -> Self {
+        FractalState {
+            fractal_type: FractalType::Classic,
+            zoom: 1.0,
+            center_x: -0.5,
+            center_y: 0.0,
+            max_iter: 1000,
+            hue_offset: 0.0,
+            saturation: 1.0,
+            value: 1.0,
+            palette: Palette::default(),
+            color_space: ColorSpace::Rgb,
+            interpolation: Interpolation::Linear,
+            stripe_density: 5.0,
+            external_angle: false,
+            angle_by_escape: false,
+            interior_period: false,
+            lighting: Lighting::default(),
+            texture: TextureMapping::default(),
+            transfer: Transfer::default(),
+            waves: SineWaves::default(),
+            width: 800,
+            height: 600,
+            needs_update: true,
+            power: 2.0,
+            secondary_param: 0.5,
+            coloring: ColoringMode::EscapeTime,
+            trap: OrbitTrap::default(),
+            polynomial: Polynomial::default(),
+            root_method: RootMethod::Newton,
+            biomorph: false,
+            cubic: CubicSlice::default(),
+            lyapunov_sequence: Sequence::default(),
+            julia_seed: Complex64::new(-0.8, 0.156),
+            convergence_shading: 0.5,
+            julia_plane: false,
+            formula: Formula::default(),
+            power_im: 0.0,
+            phoenix_p: Complex64::new(-0.5, 0.0),
+        }
+    }
 }
 
 impl Default for FractalApp {
     fn default() -> Self {
         Self {
-            state: Arc::new(RwLock::new(FractalState {
-                fractal_type: FractalType::Classic,
-                zoom: 1.0,
-                center_x: -0.5,
-                center_y: 0.0,
-                max_iter: 1000,
-                hue_offset: 0.0,
-                saturation: 1.0,
-                value: 1.0,
-                width: 800,
-                height: 600,
-                needs_update: true,
-                power: 2.0,
-                secondary_param: 0.5,
-            })),
+            state: Arc::new(RwLock::new(FractalState::default())),
             image_texture: None,
             drag_start: None,
             drag_start_center: None,
             thread_count: num_cpus::get(),
+            recorder: PathRecorder::default(),
+            timeline: Timeline::default(),
+            playback: None,
+            monitors: Vec::new(),
+            wallpaper_status: String::new(),
+            seed_input: String::new(),
+            last_seed: None,
+            seed_error: String::new(),
+            lock_palette: false,
+            type_defaults: false,
+            bookmarks: Vec::new(),
+            bookmark_name: String::new(),
+            morph_from: 0,
+            morph_to: 1,
+            morph_duration: 4.0,
+            morph: None,
+            morph_status: String::new(),
+            cross_section_mode: false,
+            cross_section: None,
+            cross_section_handle: None,
+            image_origin: Pos2::ZERO,
+            color_buffer: None,
+            post: PostSettings::default(),
+            regrade: false,
+            cycle: ColorCycle::default(),
+            stored_orbits: None,
+            traced_view: None,
+            recolor: false,
+            render_job: None,
+            last_interaction: None,
+            coarse_frame: false,
+            export_render: None,
+            export_status: String::new(),
+            texture_image: TextureImage::default(),
+            texture_status: String::new(),
+            layers: Vec::new(),
+            background: Background::default(),
+            background_status: String::new(),
+            quality: QualitySettings::default(),
+            navigator: Navigator::default(),
+            export_profile: ColorProfile::Srgb,
+            transparent_export: None,
+            gallery: GalleryClient::default(),
+            gallery_endpoint: std::env::var("FRACTAL_GALLERY_URL").unwrap_or_default(),
+            gallery_name: String::new(),
+            gallery_status: String::new(),
+            gallery_link: None,
+            gallery_entries: Vec::new(),
+            gallery_open: false,
+            compare: CompareTool::default(),
+            compare_texture: None,
+            compare_render: None,
+            region: RegionBoost::default(),
+            finder: FinderTool::default(),
+            julia_tour: JuliaTour::default(),
+            tour_status: String::new(),
+            root_drag: None,
+            perf: PerfOverlay::default(),
+            backends: BackendManager::detect(),
+            rays: RayOverlay::default(),
+            buddhabrot: BuddhabrotSettings::default(),
+            scene: Scene3d::default(),
+            bulb: Mandelbulb::default(),
+            quaternion: QuaternionJulia::default(),
+            ifs: IfsSettings::default(),
+            lsystem: LSystem::default(),
+            attractor: AttractorSettings::default(),
+            kleinian: Kleinian::default(),
+            apollonian: Apollonian::default(),
+            orbit_plot: OrbitPlot::default(),
+            formula_input: Formula::default().source,
+            formula_error: None,
+            sequence_input: Sequence::default().source,
+            sequence_error: None,
+            palette_stop: 0,
+            palette_path: String::new(),
+            palette_status: String::new(),
+            saved_palettes: palette::load_library(),
+            palette_name: String::new(),
+            seed_pick: false,
+            parameter_view: None,
         }
     }
 }
 
-impl FractalApp {
+// Everything a render reads, copied from the app so a frame can render on a
+// worker thread while the UI goes on. `job` is set for live previews only, and
+// `cancel` is checked between rows so a stale render stops early.
+#[derive(Clone)]
+struct Renderer {
+    state: FractalState,
+    quality: QualitySettings,
+    thread_count: usize,
+    layers: Vec<Layer>,
+    background: Background,
+    texture_image: TextureImage,
+    perf: TileRecorder,
+    backend: Backend,
+    gpu: Option<Arc<GpuContext>>,
+    buddhabrot: BuddhabrotSettings,
+    scene: Scene3d,
+    bulb: Mandelbulb,
+    quaternion: QuaternionJulia,
+    ifs: IfsSettings,
+    lsystem: LSystem,
+    attractor: AttractorSettings,
+    kleinian: Kleinian,
+    apollonian: Apollonian,
+    orbit_plot: OrbitPlot,
+    job: Option<Arc<RenderJob>>,
+    cancel: CancelToken,
+}
+
+impl Renderer {
     fn iterate_fractal(&self, c: Complex64, state: &FractalState) -> u32 {
-        let mut z = Complex64::new(0.0, 0.0);
+        self.trace_orbit(c, state).iterations
+    }
+
+    fn trace_orbit(&self, c: Complex64, state: &FractalState) -> Orbit {
+        let single = self.quality.precision == Precision::Single;
+        let c = if single { round_to_f32(c) } else { c };
+        // Lyapunov pixels are a pair of growth rates for the logistic map, not a point to iterate.
+        if state.fractal_type == FractalType::Lyapunov {
+            return lyapunov::orbit(c.re, c.im, &state.lyapunov_sequence, state.max_iter);
+        }
+        // Cubic pixels follow two critical orbits at once.
+        if state.fractal_type == FractalType::Cubic {
+            return state.cubic.orbit(c, state.max_iter, state.active_trap());
+        }
+        if state.fractal_type == FractalType::Tetration {
+            return tetration::orbit(c, state.max_iter);
+        }
+        // Root finding starts from the pixel itself and "escapes" by converging;
+        // Julia sets start from the pixel and iterate with the fixed seed.
+        let newton = state.fractal_type == FractalType::Newton;
+        let magnet = matches!(state.fractal_type, FractalType::MagnetI | FractalType::MagnetII);
+        let nova = state.fractal_type == FractalType::Nova;
+        let derivative = state.tracks_derivative();
+        let bailout = state.bailout();
+        let (mut z, c) = match state.fractal_type {
+            FractalType::Newton | FractalType::Collatz | FractalType::Ducks => (c, c),
+            // sin's critical point π/2 maps straight to c, as does 1/z^2's critical point ∞.
+            FractalType::SinZ | FractalType::InverseSquare => (c, c),
+            FractalType::Julia => (c, state.julia_seed),
+            _ if state.has_julia_plane() && state.julia_plane => (c, state.julia_seed),
+            // z = 1 is a critical point of the relaxed Newton map for z^p - 1.
+            FractalType::Nova => (Complex64::new(1.0, 0.0), c),
+            // The critical point of c z (1 - z).
+            FractalType::Lambda => (Complex64::new(0.5, 0.0), c),
+            // 0^p is undefined unless p is a positive real, so start from the first iterate.
+            FractalType::Classic if state.power <= 0.0 || state.power_im != 0.0 => (c, c),
+            _ => (Complex64::new(0.0, 0.0), c),
+        };
+        // Phoenix's z_(-1) is 0; Spiral feeds back the starting point itself.
+        let mut prev = if state.fractal_type == FractalType::Phoenix { Complex64::new(0.0, 0.0) } else { z };
+        let mut before = prev;
         let power = state.power;
+        let exponent = Complex64::new(state.power, state.power_im);
         let param = state.secondary_param;
+        let trap = state.active_trap();
+        // dz/dc for the Mandelbrot family, dz/dz0 for Julia sets.
+        let julia = state.fractal_type == FractalType::Julia;
+        let starts_at_c = state.fractal_type == FractalType::Classic && (state.power <= 0.0 || state.power_im != 0.0);
+        let mut dz = if julia || starts_at_c { Complex64::new(1.0, 0.0) } else { Complex64::new(0.0, 0.0) };
+        // Per-iteration term of the colorings that sum over the orbit, given z_n, z_(n-1) and z_(n-2).
+        let average_term = |z: Complex64, prev: Complex64, before: Complex64| match state.coloring {
+            ColoringMode::StripeAverage => Some(0.5 + 0.5 * (state.stripe_density * z.arg()).sin()),
+            // Where |z_n| = |z_(n-1)^p + c| falls between the bounds the triangle inequality puts on it.
+            ColoringMode::TriangleInequality => {
+                let (previous, offset) = (prev.norm().powf(power), c.norm());
+                let (low, high) = ((previous - offset).abs(), previous + offset);
+                (high > low).then(|| (z.norm() - low) / (high - low))
+            }
+            // How sharply the orbit turned at z_(n-1), from 0 (straight on) to 1 (reversing).
+            ColoringMode::CurvatureAverage => {
+                let (step, last_step) = (z - prev, prev - before);
+                (step.norm_sqr() > 0.0 && last_step.norm_sqr() > 0.0)
+                    .then(|| (step / last_step).arg().abs() / std::f64::consts::PI)
+            }
+            // A zero step gives e^(-∞) = 0.
+            ColoringMode::ExponentialSmoothing => Some((-z.norm()).exp() + (-1.0 / (z - prev).norm()).exp()),
+            _ => None,
+        };
+        let periodic = state.colors_periods();
+        let mut recent = RecentIterates::new();
+        let mut orbit = Orbit::new();
 
-        match state.fractal_type {
-            FractalType::Classic => {
-                for i in 0..state.max_iter {
-                    if z.norm_sqr() > 4.0 {
-                        return i;
-                    }
-                    z = z.powf(power) + c;
+        for i in 0..state.max_iter {
+            if newton {
+                if let Some(root) = state.polynomial.root_near(z, NEWTON_TOLERANCE) {
+                    orbit.iterations = i;
+                    orbit.escaped = true;
+                    orbit.root = Some(root);
+                    orbit.final_z = z;
+                    return orbit;
                 }
-            }
-            FractalType::Spiral => {
-                let mut prev = z;
-                for i in 0..state.max_iter {
-                    if z.norm_sqr() > 4.0 {
-                        return i;
-                    }
-                    let temp = z;
-                    z = z.powf(power) + c + (prev * param);
-                    prev = temp;
+            } else if magnet && (z - 1.0).norm_sqr() < MAGNET_TOLERANCE * MAGNET_TOLERANCE {
+                // Converging to the fixed point counts as escaping, tagged like a Newton root.
+                orbit.iterations = i;
+                orbit.escaped = true;
+                orbit.root = Some(0);
+                orbit.final_z = z;
+                return orbit;
+            } else if nova && i > 0 && (z - prev).norm_sqr() < NOVA_TOLERANCE * NOVA_TOLERANCE {
+                orbit.iterations = i;
+                orbit.escaped = true;
+                orbit.root = Some(0);
+                orbit.final_z = z;
+                return orbit;
+            } else if diverged(state.fractal_type, z, bailout, state.biomorph) {
+                orbit.iterations = i;
+                // A biomorph's body is where one component was still small as the orbit left.
+                orbit.escaped = !state.biomorph || z.re.abs().min(z.im.abs()) >= BIOMORPH_BAILOUT;
+                orbit.final_z = z;
+                // The escaping iterate counts too; smoothing blends the means with and without it.
+                if let Some(term) = average_term(z, prev, before).filter(|_| i > 0) {
+                    orbit.track_average(term);
                 }
+                if derivative {
+                    orbit.log_derivative = dz.norm().ln();
+                }
+                if derivative && dz.norm_sqr() > 0.0 {
+                    let r = z.norm();
+                    orbit.distance = 0.5 * r * r.ln() / dz.norm();
+                    let u = z / dz;
+                    orbit.normal = u / u.norm();
+                }
+                return orbit;
             }
-            FractalType::Flower => {
-                for i in 0..state.max_iter {
-                    if z.norm_sqr() > 4.0 {
-                        return i;
-                    }
-                    z = (z * z.sin() + c) * Complex64::new(param.cos(), param.sin());
+            // z0 = 0 would always win, so the starting point is not tracked.
+            if i > 0 {
+                orbit.track(z, i);
+                if let Some(trap) = trap {
+                    orbit.track_trap(z, trap);
+                }
+                if let Some(term) = average_term(z, prev, before) {
+                    orbit.track_average(term);
                 }
             }
-            FractalType::Phoenix => {
-                let mut prev = z;
-                for i in 0..state.max_iter {
-                    if z.norm_sqr() > 4.0 {
-                        return i;
-                    }
-                    let temp = z;
-                    z = z.powf(power) - prev.sin() * param + c;
-                    prev = temp;
+            if periodic {
+                recent.push(z);
+            }
+            if derivative {
+                dz = exponent * complex_pow(z, exponent - 1.0) * dz;
+                if !julia {
+                    dz += 1.0;
                 }
             }
-            FractalType::Butterfly => {
-                for i in 0..state.max_iter {
-                    if z.norm_sqr() > 4.0 {
-                        return i;
-                    }
+
+            let next = match state.fractal_type {
+                FractalType::Classic => complex_pow(z, exponent) + c,
+                FractalType::Spiral => z.powf(power) + c + (prev * param),
+                FractalType::Flower => (z * z.sin() + c) * Complex64::new(param.cos(), param.sin()),
+                FractalType::Phoenix => z.powf(power) + c + state.phoenix_p * prev,
+                FractalType::Butterfly => {
                     let r = z.norm();
                     if r > 0.0 {
                         let theta = z.arg();
-                        z = Complex64::from_polar(r.powf(param), theta * power) + c;
+                        Complex64::from_polar(r.powf(param), theta * power) + c
+                    } else {
+                        z
                     }
                 }
-            }
+                FractalType::Newton => state.polynomial.root_step(state.root_method, z, prev),
+                FractalType::Julia => complex_pow(z, exponent) + c,
+                FractalType::BurningShip => Complex64::new(z.re.abs(), z.im.abs()).powf(power) + c,
+                // Power 2 is the Tricorn; higher powers give the multicorns.
+                FractalType::Tricorn => z.conj().powf(power) + c,
+                // Per-pixel queries (orbits, cross sections) see the underlying Mandelbrot.
+                FractalType::Buddhabrot => z.powf(power) + c,
+                FractalType::Lyapunov | FractalType::Cubic | FractalType::Tetration => unreachable!(),
+                FractalType::MagnetI => {
+                    let w = (z * z + c - 1.0) / (z * 2.0 + c - 2.0);
+                    w * w
+                }
+                FractalType::MagnetII => {
+                    let (c1, c2) = (c - 1.0, c - 2.0);
+                    let w = (z * z * z + z * c1 * 3.0 + c1 * c2) / (z * z * 3.0 + z * c2 * 3.0 + c1 * c2 + 1.0);
+                    w * w
+                }
+                // Relaxed Newton step for z^p - 1 plus c. The shape parameter's default
+                // of 0.5 maps to the classic relaxation of 1.
+                FractalType::Nova => {
+                    let relaxation = param * 2.0;
+                    z - (z.powf(power) - 1.0) / (z.powf(power - 1.0) * power) * relaxation + c
+                }
+                FractalType::Custom => state.formula.eval(z, c),
+                FractalType::Lambda => c * z * (1.0 - z),
+                // 2D queries (orbits, cross sections) see the equivalent Multibrot slice.
+                FractalType::Mandelbulb => z.powf(power) + c,
+                FractalType::QuaternionJulia => z * z + c,
+                // Folding the real part keeps the logarithm on the right half-plane's branch.
+                FractalType::Ducks => (Complex64::new(z.re.abs(), z.im) + c).ln(),
+                FractalType::InverseSquare => (z * z).inv() + c,
+                FractalType::Quotient => {
+                    let z2 = z * z;
+                    (z2 + c) / (z2 - c)
+                }
+                FractalType::SinZ => c * z.sin(),
+                FractalType::ExpZ => c * z.exp(),
+                FractalType::CoshZ => c * z.cosh(),
+                // Interpolates n/2 on even and 3n + 1 on odd integers.
+                FractalType::Collatz => {
+                    let cos = (z * std::f64::consts::PI).cos();
+                    (2.0 + z * 7.0 - (2.0 + z * 5.0) * cos) / 4.0
+                }
+                // Plotted types have no per-point iteration; 2D queries see the Mandelbrot.
+                FractalType::Ifs | FractalType::LSystem | FractalType::Attractor | FractalType::Kleinian | FractalType::Apollonian | FractalType::OrbitPlot => {
+                    z * z + c
+                }
+            };
+            before = prev;
+            prev = z;
+            z = if single { round_to_f32(next) } else { next };
+        }
+
+        orbit.iterations = state.max_iter;
+        orbit.final_z = z;
+        if periodic {
+            orbit.period = recent.period(z);
         }
-        state.max_iter
+        if derivative {
+            orbit.log_derivative = dz.norm().ln();
+        }
+        orbit
     }
 
-    fn generate_mandelbrot(&self) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
-        let state = self.state.read();
-        let mut img = ImageBuffer::new(state.width, state.height);
-        let scale = 2.5 / state.zoom;
-        
-        let chunks: Vec<_> = (0..state.height)
-            .collect::<Vec<_>>()
-            .chunks(state.height as usize / self.thread_count + 1)
-            .map(|c| c.to_vec())
-            .collect();
+    fn generate_mandelbrot(&self) -> ColorBuffer {
+        let state = &self.state;
+        let (origin, step) = state.view_mapping(state.width, state.height);
+        self.render_layers(state, state.width, state.height, origin, step)
+    }
 
-        let results: Vec<_> = chunks.into_par_iter().map(|rows| {
-            let mut buffer = Vec::new();
-            for y in rows {
-                for x in 0..state.width {
-                    let x_scaled = (x as f64 / state.width as f64) * 3.5 * scale - 2.5 * scale + state.center_x;
-                    let y_scaled = (y as f64 / state.height as f64) * 2.0 * scale - 1.0 * scale + state.center_y;
-                    
-                    let c = Complex64::new(x_scaled, y_scaled);
-                    let i = self.iterate_fractal(c, &state);
-                    
-                    let hue = ((i as f32 / state.max_iter as f32) * 360.0 + state.hue_offset) % 360.0;
-                    let color = if i == state.max_iter {
-                        Rgb([0, 0, 0])
-                    } else {
-                        let rgb = self.hsv_to_rgb(hue, state.saturation, state.value);
-                        Rgb([rgb.0, rgb.1, rgb.2])
-                    };
-                    buffer.push((x, y, color));
-                }
-            }
-            buffer
-        }).collect();
+    // Live view, rendered at the quality preset's preview scale.
+    fn generate_preview(&self) -> ColorBuffer {
+        let state = &self.state;
+        let (width, height) = self.quality.preview_size(state.width, state.height);
+        let (origin, step) = state.view_mapping(width, height);
+        self.render_layers(state, width, height, origin, step)
+    }
 
-        for chunk in results {
-            for (x, y, color) in chunk {
-                img.put_pixel(x, y, color);
+    // Orbits for every pixel of the live view at the preview scale, with their step.
+    // `panned` gives the previous frame's orbits and how many pixels the view moved
+    // since: pixels still in view are copied over and only the exposed strips are
+    // traced. Tiles are only shown for a full trace, as a panned one is mostly holes.
+    fn preview_orbits(&self, panned: Option<(&[Orbit], (i64, i64))>) -> (Vec<Orbit>, (f64, f64)) {
+        let mut state = self.state.clone();
+        state.max_iter = self.quality.scale_iterations(state.max_iter);
+        let (width, height) = self.quality.preview_size(state.width, state.height);
+        let (origin, step) = state.view_mapping(width, height);
+        let source = |x: u32, y: u32| -> Option<usize> {
+            let (_, (dx, dy)) = panned?;
+            let (sx, sy) = (x as i64 + dx, y as i64 + dy);
+            ((0..width as i64).contains(&sx) && (0..height as i64).contains(&sy))
+                .then(|| (sy * width as i64 + sx) as usize)
+        };
+        let mut orbits = self.compute_tiles(
+            &state,
+            width,
+            height,
+            origin,
+            step,
+            &|x, y| source(x, y).is_none().then_some((0.0, 0.0)),
+            &|y, tile| {
+                if panned.is_none() {
+                    self.show_tile(&state, width, y, step, tile);
+                }
+            },
+        );
+        if let Some((previous, _)) = panned {
+            for (i, orbit) in orbits.iter_mut().enumerate() {
+                if let Some(j) = source(i as u32 % width, i as u32 / width) {
+                    *orbit = previous[j];
+                }
             }
         }
-        
-        img
+        (orbits, step)
     }
 
-    #[inline(always)]
-    fn mandelbrot(&self, c: Complex64, max_iter: u32) -> u32 {
-        let mut z = Complex64::new(0.0, 0.0);
-        for i in 0..max_iter {
-            if z.norm_sqr() > 4.0 {
-                return i;
+    // The live view colored from preview orbits, with the hue offset turned by
+    // `hue_shift`. Layers and supersampling are left out so frames stay cheap.
+    fn color_preview(&self, orbits: &[Orbit], step: (f64, f64), hue_shift: f32) -> ColorBuffer {
+        let mut state = self.state.clone();
+        state.max_iter = self.quality.scale_iterations(state.max_iter);
+        state.hue_offset = (state.hue_offset + hue_shift) % 360.0;
+        let (width, height) = self.quality.preview_size(state.width, state.height);
+        let mut out = self.colorize(&state, width, height, step, orbits);
+        if self.background.kind != BackgroundKind::None && self.background.region == TransparentRegion::Exterior {
+            for alpha in &mut out.alpha {
+                *alpha = 1.0 - *alpha;
             }
-            z = z * z + c;
         }
-        max_iter
+        self.background.underlay(&mut out);
+        out
     }
 
-    #[inline(always)]
-    fn hsv_to_rgb(&self, h: f32, s: f32, v: f32) -> (u8, u8, u8) {
-        let c = v * s;
-        let x = c * (1.0 - ((h / 60.0) % 2.0 - 1.0).abs());
-        let m = v - c;
-        
-        let (r, g, b) = match h as i32 {
-            h if h < 60 => (c, x, 0.0),
-            h if h < 120 => (x, c, 0.0),
-            h if h < 180 => (0.0, c, x),
-            h if h < 240 => (0.0, x, c),
-            h if h < 300 => (x, 0.0, c),
-            _ => (c, 0.0, x)
+    // Supersampled render: each pass offsets every pixel's sample point by the
+    // selected pattern and the passes are averaged.
+    fn render_layers(
+        &self,
+        state: &FractalState,
+        width: u32,
+        height: u32,
+        origin: (f64, f64),
+        step: (f64, f64),
+    ) -> ColorBuffer {
+        if state.fractal_type.is_3d() {
+            let mut out = self.render_3d(state, width, height);
+            self.background.underlay(&mut out);
+            return out;
+        }
+        if state.fractal_type == FractalType::Ifs {
+            let mut out = self.render_ifs(state, width, height, origin, step);
+            self.background.underlay(&mut out);
+            return out;
+        }
+        if state.fractal_type == FractalType::LSystem {
+            let mut out = self.render_lsystem(state, width, height, origin, step);
+            self.background.underlay(&mut out);
+            return out;
+        }
+        if state.fractal_type == FractalType::Attractor {
+            let mut out = self.render_attractor(state, width, height, origin, step);
+            self.background.underlay(&mut out);
+            return out;
+        }
+        if state.fractal_type == FractalType::Kleinian {
+            let mut out = self.render_kleinian(state, width, height, origin, step);
+            self.background.underlay(&mut out);
+            return out;
+        }
+        if state.fractal_type == FractalType::Apollonian {
+            let mut out = self.render_apollonian(state, width, height, origin, step);
+            self.background.underlay(&mut out);
+            return out;
+        }
+        if state.fractal_type == FractalType::OrbitPlot {
+            let mut out = self.render_orbit_plot(state, width, height, origin, step);
+            self.background.underlay(&mut out);
+            return out;
+        }
+        if state.fractal_type == FractalType::Buddhabrot {
+            let mut state = state.clone();
+            state.max_iter = self.quality.scale_iterations(state.max_iter);
+            let mut out = self.render_buddhabrot(&state, width, height, origin, step);
+            self.background.underlay(&mut out);
+            return out;
+        }
+
+        let aa = self.quality.antialias;
+        if aa.samples <= 1 {
+            return self.render_pass(state, width, height, origin, step, &|_, _| Some((0.0, 0.0))).0;
+        }
+
+        let mut accumulator = Accumulator::new(width, height);
+        let refine = if aa.adaptive && aa.refinement == Refinement::EscapeEdges {
+            // One centered sample everywhere; the edges of its escape counts are refined.
+            let (pass, orbits) = self.render_pass(state, width, height, origin, step, &|_, _| Some((0.0, 0.0)));
+            accumulator.add(&pass, None);
+            Some(antialias::escape_edges(&orbits, width, height, aa.iteration_threshold))
+        } else {
+            let initial = if aa.adaptive { aa.samples.min(ADAPTIVE_INITIAL_SAMPLES) } else { aa.samples };
+            for k in 0..initial {
+                if self.cancelled() {
+                    break;
+                }
+                let (pass, _) = self.render_pass(state, width, height, origin, step, &|x, y| {
+                    Some(aa.pattern.offset(k, initial, x, y))
+                });
+                accumulator.add(&pass, None);
+            }
+            (aa.adaptive && initial < aa.samples).then(|| accumulator.needs_refinement(aa.variance_threshold))
         };
-        
-        (((r + m) * 255.0) as u8,
-         ((g + m) * 255.0) as u8,
-         ((b + m) * 255.0) as u8)
-    }
 
-    fn handle_mouse_input(&mut self, ui: &mut egui::Ui, available_size: Vec2) {
-        let rect = ui.max_rect();
-        let response = ui.allocate_rect(rect, egui::Sense::drag());
-        
-        if response.dragged() {
-            if let Some(drag_start) = self.drag_start {
-                if let Some((start_x, start_y)) = self.drag_start_center {
-                    let delta = response.drag_delta();
-                    let mut state = self.state.write();
-                    let scale = 2.5 / state.zoom;
-                    let sensitivity = 0.5;
-                    let dx = (delta.x as f64) * scale * sensitivity / (available_size.x as f64);
-                    let dy = (delta.y as f64) * scale * sensitivity / (available_size.y as f64);
-                    state.center_x = start_x - dx;
-                    state.center_y = start_y - dy;
-                    state.needs_update = true;
+        if let Some(refine) = refine {
+            for k in 0..aa.samples {
+                if self.cancelled() {
+                    break;
                 }
-            } else {
-                let state = self.state.read();
-                self.drag_start = Some(response.interact_pointer_pos().unwrap());
-                self.drag_start_center = Some((state.center_x, state.center_y));
+                let (pass, _) = self.render_pass(state, width, height, origin, step, &|x, y| {
+                    refine[(y * width + x) as usize].then(|| aa.pattern.offset(k, aa.samples, x, y))
+                });
+                accumulator.add(&pass, Some(&refine));
             }
-        } else {
-            self.drag_start = None;
-            self.drag_start_center = None;
         }
+        accumulator.resolve()
+    }
 
-        if response.hovered() {
-            ui.input(|i| {
-                let scroll = i.raw_scroll_delta.y;
-                if scroll != 0.0 {
-                    let mut state = self.state.write();
-                    let zoom_factor = if scroll > 0.0 { 1.05 } else { 0.95 };
-                    let new_zoom = state.zoom * zoom_factor;
-                    
-                    if new_zoom >= 0.1 && new_zoom <= 50.0 {
-                        state.zoom = new_zoom;
-                        state.needs_update = true;
-                    }
+    // Base render with every visible layer composited over it, bottom to top, and the
+    // base fractal's orbits. `sample` gives each pixel's sub-pixel offset, or None to
+    // skip it.
+    fn render_pass(
+        &self,
+        state: &FractalState,
+        width: u32,
+        height: u32,
+        origin: (f64, f64),
+        step: (f64, f64),
+        sample: &SampleFn<'_>,
+    ) -> (ColorBuffer, Vec<Orbit>) {
+        // Taken from the unscaled view, so layers that follow the base fractal do
+        // not have its iterations scaled twice.
+        let layer_states: Vec<FractalState> = self.layers.iter().map(|l| {
+            let mut layer_state = l.state_for_view(state);
+            layer_state.max_iter = self.quality.scale_iterations(layer_state.max_iter);
+            layer_state
+        }).collect();
+        let mut state = state.clone();
+        state.max_iter = self.quality.scale_iterations(state.max_iter);
+        let state = &state;
+        let base_orbits = self.compute_tiles(state, width, height, origin, step, sample, &|y, tile| {
+            self.show_tile(state, width, y, step, tile);
+        });
+        let mut out = self.colorize(state, width, height, step, &base_orbits);
+        if self.background.kind != BackgroundKind::None && self.background.region == TransparentRegion::Exterior {
+            for alpha in &mut out.alpha {
+                *alpha = 1.0 - *alpha;
+            }
+        }
+
+        // Hidden layers are still iterated when a visible layer uses them as a mask source.
+        let layer_orbits: Vec<Option<Vec<Orbit>>> = self.layers.iter().enumerate().map(|(i, layer)| {
+            let used_as_mask = self.layers.iter().any(|l| {
+                l.visible && l.mask.as_ref().is_some_and(|m| m.source == Some(i))
+            });
+            (layer.visible || used_as_mask)
+                .then(|| self.compute_orbits_sampled(&layer_states[i], width, height, origin, step, sample))
+        }).collect();
+
+        for (i, layer) in self.layers.iter().enumerate().filter(|(_, l)| l.visible) {
+            let Some(orbits) = &layer_orbits[i] else {
+                continue;
+            };
+            let rendered = self.colorize(&layer_states[i], width, height, step, orbits);
+            let mask = layer.mask.as_ref().and_then(|mask| match mask.source {
+                None => Some(mask.evaluate(&base_orbits, state.max_iter)),
+                Some(j) => {
+                    let source = layer_orbits.get(j)?.as_ref()?;
+                    Some(mask.evaluate(source, layer_states[j].max_iter))
                 }
             });
+            layers::composite(&mut out, &rendered, layer, mask.as_deref());
         }
+
+        self.background.underlay(&mut out);
+        (out, base_orbits)
     }
 
-    fn randomize_params(&mut self) {
-        use rand::Rng;
-        let mut rng = rand::thread_rng();
-        
-        let mut state = self.state.write();
-        state.hue_offset = rng.gen_range(0.0..360.0);
-        state.saturation = rng.gen_range(0.7..1.0);
-        state.value = rng.gen_range(0.7..1.0);
-        state.power = rng.gen_range(2.0..4.0);
-        state.secondary_param = rng.gen_range(0.1..0.9);
-        state.fractal_type = match rng.gen_range(0..5) {
-            0 => FractalType::Classic,
-            1 => FractalType::Spiral,
-            2 => FractalType::Flower,
-            3 => FractalType::Phoenix,
-            _ => FractalType::Butterfly,
-        };
-        state.needs_update = true;
+    // Renders the view described by `state` at an arbitrary resolution.
+    fn render_view(&self, state: &FractalState, width: u32, height: u32) -> ColorBuffer {
+        let (origin, step) = state.view_mapping(width, height);
+        self.render_area(state, width, height, origin, step)
     }
-}
 
-impl eframe::App for FractalApp {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        egui::SidePanel::left("controls").show(ctx, |ui| {
-            ui.heading("Fractal Controls");
-            
-            let mut state = self.state.write();
-            
-            ui.horizontal(|ui| {
-                ui.label("Fractal Type:");
-                if ui.radio_value(&mut state.fractal_type, FractalType::Classic, "Classic").clicked() {
-                    state.needs_update = true;
-                }
-                if ui.radio_value(&mut state.fractal_type, FractalType::Spiral, "Spiral").clicked() {
-                    state.needs_update = true;
-                }
-                if ui.radio_value(&mut state.fractal_type, FractalType::Flower, "Flower").clicked() {
-                    state.needs_update = true;
-                }
-                if ui.radio_value(&mut state.fractal_type, FractalType::Phoenix, "Phoenix").clicked() {
-                    state.needs_update = true;
-                }
-                if ui.radio_value(&mut state.fractal_type, FractalType::Butterfly, "Butterfly").clicked() {
-                    state.needs_update = true;
-                }
-            });
+    // Renders `width` x `height` pixels where pixel (x, y) samples origin + (x, y) * step.
+    fn render_area(
+        &self,
+        state: &FractalState,
+        width: u32,
+        height: u32,
+        origin: (f64, f64),
+        step: (f64, f64),
+    ) -> ColorBuffer {
+        if state.fractal_type.is_3d() {
+            return self.render_3d(state, width, height);
+        }
+        if state.fractal_type == FractalType::Ifs {
+            return self.render_ifs(state, width, height, origin, step);
+        }
+        if state.fractal_type == FractalType::LSystem {
+            return self.render_lsystem(state, width, height, origin, step);
+        }
+        if state.fractal_type == FractalType::Attractor {
+            return self.render_attractor(state, width, height, origin, step);
+        }
+        if state.fractal_type == FractalType::Kleinian {
+            return self.render_kleinian(state, width, height, origin, step);
+        }
+        if state.fractal_type == FractalType::Apollonian {
+            return self.render_apollonian(state, width, height, origin, step);
+        }
+        if state.fractal_type == FractalType::OrbitPlot {
+            return self.render_orbit_plot(state, width, height, origin, step);
+        }
+        if state.fractal_type == FractalType::Buddhabrot {
+            return self.render_buddhabrot(state, width, height, origin, step);
+        }
+        let orbits = self.compute_orbits(state, width, height, origin, step);
+        self.colorize(state, width, height, step, &orbits)
+    }
 
-            ui.add_space(10.0);
-            
-            if ui.button("🎲 Randomize").clicked() {
-                drop(state);  // Release the lock before calling randomize
-                self.randomize_params();
-                state = self.state.write();  // Reacquire the lock
+    // Raymarched view from the orbiting camera. Hue follows the orbit trap; misses
+    // are left transparent for the background.
+    fn render_3d(&self, state: &FractalState, width: u32, height: u32) -> ColorBuffer {
+        let surfaces = match state.fractal_type {
+            FractalType::QuaternionJulia => self.scene.render(&self.quaternion, width, height, &self.cancel),
+            _ => self.scene.render(&self.bulb, width, height, &self.cancel),
+        };
+        let mut out = ColorBuffer::new(width, height);
+        out.pixels = surfaces.par_iter().map(|surface| match surface {
+            Some(Surface { brightness, trap }) => {
+                let hue = (state.hue_offset + *trap as f32 * 180.0) % 360.0;
+                state.color_space.hue(hue, state.saturation, state.value * brightness).map(color::srgb_to_linear)
             }
+            None => [0.0, 0.0, 0.0],
+        }).collect();
+        out.alpha = surfaces.iter().map(|s| if s.is_some() { 1.0 } else { 0.0 }).collect();
+        out
+    }
 
-            ui.add_space(5.0);
-            
-            if ui.add(egui::Slider::new(&mut state.power, 2.0..=4.0)
-                .step_by(0.1)
-                .text("Power")).changed() {
-                state.needs_update = true;
-            }
-            
-            if ui.add(egui::Slider::new(&mut state.secondary_param, 0.1..=0.9)
-                .step_by(0.05)
-                .text("Shape Parameter")).changed() {
-                state.needs_update = true;
-            }
-            if ui.add(egui::Slider::new(&mut state.zoom, 0.1..=50.0)
-                .step_by(0.1)
-                .text("Zoom")).changed() {
-                state.needs_update = true;
-            }
-            if ui.add(egui::Slider::new(&mut state.center_x, -2.0..=1.0)
-                .step_by(0.01)
-                .text("X Position")).changed() {
-                state.needs_update = true;
-            }
-            if ui.add(egui::Slider::new(&mut state.center_y, -1.5..=1.5)
-                .step_by(0.01)
-                .text("Y Position")).changed() {
-                state.needs_update = true;
-            }
-            if ui.add(egui::Slider::new(&mut state.max_iter, 100..=5000)
-                .step_by(100.0)
-                .text("Max Iterations")).changed() {
-                state.needs_update = true;
-            }
-            
-            ui.separator();
-            ui.heading("Color Controls");
-            if ui.add(egui::Slider::new(&mut state.hue_offset, 0.0..=360.0).text("Hue Offset")).changed() {
-                state.needs_update = true;
-            }
-            if ui.add(egui::Slider::new(&mut state.saturation, 0.0..=1.0).text("Saturation")).changed() {
-                state.needs_update = true;
-            }
-            if ui.add(egui::Slider::new(&mut state.value, 0.0..=1.0).text("Value")).changed() {
-                state.needs_update = true;
-            }
-            
-            if ui.button("Save Image").clicked() {
-                let img = self.generate_mandelbrot();
-                let filename = format!("fractol_{}.png", 
-                    Local::now().format("%Y%m%d_%H%M%S"));
-                img.save(&filename).unwrap();
-            }
-            
-            ui.separator();
-            ui.heading("Controls");
-            ui.label("• Drag to pan");
-            ui.label("• Scroll to zoom");
-            ui.label("• Use sliders for fine control");
-            ui.label(format!("Using {} threads", self.thread_count));
-        });
+    // Chaos game density of the IFS attractor, tone-mapped like the Buddhabrot.
+    fn render_ifs(
+        &self,
+        state: &FractalState,
+        width: u32,
+        height: u32,
+        origin: (f64, f64),
+        step: (f64, f64),
+    ) -> ColorBuffer {
+        let hits = ifs::accumulate(&self.ifs.transforms, width, height, origin, step, self.ifs.density, self.thread_count);
+        self.shade_density(state, width, height, &hits, self.buddhabrot.exposure)
+    }
 
-        egui::CentralPanel::default().show(ctx, |ui| {
-            let available_size = ui.available_size();
-            let needs_update = {
-                let mut state = self.state.write();
-                let size_changed = state.width != available_size.x as u32 || 
-                                 state.height != available_size.y as u32;
-                
-                if size_changed {
-                    state.width = available_size.x as u32;
-                    state.height = available_size.y as u32;
-                    state.needs_update = true;
-                }
+    // Orbit density of a 2D map, tone-mapped the same way.
+    fn render_attractor(
+        &self,
+        state: &FractalState,
+        width: u32,
+        height: u32,
+        origin: (f64, f64),
+        step: (f64, f64),
+    ) -> ColorBuffer {
+        let settings = &self.attractor;
+        let hits = attractor::accumulate(&settings.attractor, width, height, origin, step, settings.density, self.thread_count);
+        self.shade_density(state, width, height, &hits, self.buddhabrot.exposure)
+    }
+
+    fn render_orbit_plot(
+        &self,
+        state: &FractalState,
+        width: u32,
+        height: u32,
+        origin: (f64, f64),
+        step: (f64, f64),
+    ) -> ColorBuffer {
+        let hits = orbitplot::accumulate(&self.orbit_plot, width, height, origin, step);
+        self.shade_density(state, width, height, &hits, self.orbit_plot.exposure)
+    }
+
+    fn render_kleinian(
+        &self,
+        state: &FractalState,
+        width: u32,
+        height: u32,
+        origin: (f64, f64),
+        step: (f64, f64),
+    ) -> ColorBuffer {
+        let samples = kleinian::render(&self.kleinian, width, height, origin, step);
+        self.shade_distance(state, width, height, &samples)
+    }
+
+    fn render_apollonian(
+        &self,
+        state: &FractalState,
+        width: u32,
+        height: u32,
+        origin: (f64, f64),
+        step: (f64, f64),
+    ) -> ColorBuffer {
+        let samples = apollonian::render(&self.apollonian, width, height, origin, step);
+        self.shade_distance(state, width, height, &samples)
+    }
+
+    // Sets drawn from a distance estimate in pixels: solid within a pixel or so, so
+    // edges anti-alias at any zoom, with a faint halo further out. Hue follows the
+    // number of folds or inversions.
+    fn shade_distance(&self, state: &FractalState, width: u32, height: u32, samples: &[(f64, u32)]) -> ColorBuffer {
+        let mut out = ColorBuffer::new(width, height);
+        let strength = |distance: f64| {
+            let line = (1.5 - distance).clamp(0.0, 1.0);
+            let halo = 0.4 * (-0.05 * distance.max(0.0)).exp();
+            line.max(halo) as f32
+        };
+        out.pixels = samples.par_iter().map(|&(distance, folds)| {
+            let hue = (state.hue_offset + 6.0 * folds as f32) % 360.0;
+            state.color_space.hue(hue, state.saturation, state.value * strength(distance)).map(color::srgb_to_linear)
+        }).collect();
+        out.alpha = samples.iter().map(|&(distance, _)| strength(distance)).collect();
+        out
+    }
+
+    // Single-hue rendering of point hit counts; empty pixels stay transparent.
+    fn shade_density(&self, state: &FractalState, width: u32, height: u32, hits: &[u32], exposure: f32) -> ColorBuffer {
+        let density = buddhabrot::tone_map(hits, exposure, self.buddhabrot.gamma);
+        let mut out = ColorBuffer::new(width, height);
+        out.pixels = density.par_iter().map(|&v| {
+            state.color_space.hue(state.hue_offset % 360.0, state.saturation, state.value * v).map(color::srgb_to_linear)
+        }).collect();
+        out.alpha = density;
+        out
+    }
+
+    // Turtle drawing of the expanded L-system; hue runs along the path.
+    fn render_lsystem(
+        &self,
+        state: &FractalState,
+        width: u32,
+        height: u32,
+        origin: (f64, f64),
+        step: (f64, f64),
+    ) -> ColorBuffer {
+        let coverage = lsystem::rasterize(&self.lsystem.segments, width, height, origin, step);
+        let mut out = ColorBuffer::new(width, height);
+        out.pixels = coverage.par_iter().map(|t| {
+            let hue = (state.hue_offset + 120.0 * t.unwrap_or(0.0)) % 360.0;
+            state.color_space.hue(hue, state.saturation, state.value).map(color::srgb_to_linear)
+        }).collect();
+        out.alpha = coverage.iter().map(|t| if t.is_some() { 1.0 } else { 0.0 }).collect();
+        out
+    }
+
+    // Density of escaping orbits rather than per-pixel escape times. Bright cores
+    // fade toward white so the hue only tints the fainter trails.
+    fn render_buddhabrot(
+        &self,
+        state: &FractalState,
+        width: u32,
+        height: u32,
+        origin: (f64, f64),
+        step: (f64, f64),
+    ) -> ColorBuffer {
+        let settings = &self.buddhabrot;
+        if settings.nebula {
+            return self.render_nebulabrot(state, width, height, origin, step);
+        }
+        let hits = buddhabrot::accumulate(state, width, height, origin, step, settings.density, self.thread_count);
+        let density = buddhabrot::tone_map(&hits, settings.exposure, settings.gamma);
+        let mut out = ColorBuffer::new(width, height);
+        out.pixels = density.par_iter().map(|&v| {
+            state.color_space.hue(state.hue_offset % 360.0, state.saturation * (1.0 - v), state.value * v)
+                .map(color::srgb_to_linear)
+        }).collect();
+        out.alpha = density;
+        out
+    }
+
+    // Three Buddhabrot passes with per-channel iteration limits composited as RGB.
+    fn render_nebulabrot(
+        &self,
+        state: &FractalState,
+        width: u32,
+        height: u32,
+        origin: (f64, f64),
+        step: (f64, f64),
+    ) -> ColorBuffer {
+        let settings = &self.buddhabrot;
+        let channels: Vec<Vec<f32>> = settings.channel_iterations.iter().map(|&limit| {
+            let mut channel_state = state.clone();
+            channel_state.max_iter = self.quality.scale_iterations(limit);
+            let hits = buddhabrot::accumulate(&channel_state, width, height, origin, step, settings.density, self.thread_count);
+            buddhabrot::tone_map(&hits, settings.exposure, settings.gamma)
+        }).collect();
+
+        let mut out = ColorBuffer::new(width, height);
+        out.pixels = (0..channels[0].len()).map(|i| {
+            [channels[0][i], channels[1][i], channels[2][i]].map(|v| color::srgb_to_linear(v * state.value))
+        }).collect();
+        out.alpha = (0..channels[0].len()).map(|i| channels[0][i].max(channels[1][i]).max(channels[2][i])).collect();
+        out
+    }
+
+    // Row-major orbit data for every pixel of the area.
+    fn compute_orbits(
+        &self,
+        state: &FractalState,
+        width: u32,
+        height: u32,
+        origin: (f64, f64),
+        step: (f64, f64),
+    ) -> Vec<Orbit> {
+        self.compute_orbits_sampled(state, width, height, origin, step, &|_, _| Some((0.0, 0.0)))
+    }
+
+    // Skipped pixels are left as non-escaped placeholder orbits.
+    fn compute_orbits_sampled(
+        &self,
+        state: &FractalState,
+        width: u32,
+        height: u32,
+        origin: (f64, f64),
+        step: (f64, f64),
+        sample: &SampleFn<'_>,
+    ) -> Vec<Orbit> {
+        self.compute_tiles(state, width, height, origin, step, sample, &|_, _| {})
+    }
+
+    // Tiles of TILE_ROWS rows go through the thread pool's queue; `finished` sees each
+    // tile's orbits as soon as they are done. Once the render is cancelled, rows not
+    // yet started are left as placeholders.
+    #[allow(clippy::too_many_arguments)]
+    fn compute_tiles(
+        &self,
+        state: &FractalState,
+        width: u32,
+        height: u32,
+        origin: (f64, f64),
+        step: (f64, f64),
+        sample: &SampleFn<'_>,
+        finished: &TileFn<'_>,
+    ) -> Vec<Orbit> {
+        if let Some(orbits) = self.gpu_orbits(state, width, height, origin, step, sample) {
+            finished(0, &orbits);
+            return orbits;
+        }
+        // The SIMD kernel works in f64, so single-precision emulation stays scalar.
+        let lanes = (self.backend == Backend::Simd && self.quality.precision == Precision::Double)
+            .then(|| state.escape_params())
+            .flatten();
+        // Layer masks can measure bounded orbits, so they keep every pixel iterated.
+        let skips_interior = state.skips_main_components() && !self.layers.iter().any(|l| l.mask.is_some());
+
+        let results: Vec<_> = (0..height.div_ceil(TILE_ROWS)).into_par_iter().map(|tile| {
+            let first_row = tile * TILE_ROWS;
+            let rows = first_row..(first_row + TILE_ROWS).min(height);
+            let row_count = rows.len() as u32;
+            if self.cancelled() {
+                return vec![Orbit::new(); (row_count * width) as usize];
+            }
+            let started = Instant::now();
+            let mut buffer = Vec::new();
+            for y in rows {
+                if self.cancelled() {
+                    buffer.resize(((y - first_row + 1) * width) as usize, Orbit::new());
+                    continue;
+                }
+                // Sampled pixels of the row waiting for the SIMD kernel, by buffer index.
+                let mut pending = Vec::new();
+                for x in 0..width {
+                    let Some((dx, dy)) = sample(x, y) else {
+                        buffer.push(Orbit::new());
+                        continue;
+                    };
+                    let x_scaled = origin.0 + (x as f64 + dx) * step.0;
+                    let y_scaled = origin.1 + (y as f64 + dy) * step.1;
+                    
+                    let c = Complex64::new(x_scaled, y_scaled);
+                    if skips_interior && in_main_components(c) {
+                        let mut orbit = Orbit::new();
+                        orbit.iterations = state.max_iter;
+                        buffer.push(orbit);
+                    } else if lanes.is_some() {
+                        pending.push((buffer.len(), c));
+                        buffer.push(Orbit::new());
+                    } else {
+                        buffer.push(self.trace_orbit(c, state));
+                    }
+                }
+                if let Some(params) = &lanes {
+                    // The last group is padded by repeating its final point.
+                    for group in pending.chunks(LANES) {
+                        let points = std::array::from_fn(|l| group[l.min(group.len() - 1)].1);
+                        for (&(i, _), orbit) in group.iter().zip(escape::iterate_lanes(&points, params)) {
+                            buffer[i] = orbit;
+                        }
+                    }
+                }
+            }
+            self.perf.record(TileTiming {
+                y: first_row,
+                rows: row_count,
+                millis: started.elapsed().as_secs_f64() * 1000.0,
+            });
+            finished(first_row, &buffer);
+            buffer
+        }).collect();
+
+        results.into_iter().flatten().collect()
+    }
+
+    // Shows a finished tile of the base pass in the live view while the frame renders.
+    fn show_tile(&self, state: &FractalState, width: u32, y: u32, step: (f64, f64), orbits: &[Orbit]) {
+        if let Some(job) = &self.job {
+            let rows = orbits.len() as u32 / width.max(1);
+            job.post_tile(y, self.colorize(state, width, rows, step, orbits));
+        }
+    }
+
+    fn cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    // Iterates the sampled pixels on the GPU when it is the active backend and covers
+    // the view. None leaves the pixels to the CPU, including when the GPU fails.
+    fn gpu_orbits(
+        &self,
+        state: &FractalState,
+        width: u32,
+        height: u32,
+        origin: (f64, f64),
+        step: (f64, f64),
+        sample: &SampleFn<'_>,
+    ) -> Option<Vec<Orbit>> {
+        let gpu = self.gpu.as_ref()?;
+        let params = state.escape_params()?;
+        // The escaping iterate is up to bailout^(power / 2) and must stay within f32.
+        if params.bailout.powi(params.power as i32) >= f32::MAX as f64 / 4.0 {
+            return None;
+        }
+        let far = (origin.0 + width as f64 * step.0, origin.1 + height as f64 * step.1);
+        let reach = origin.0.abs().max(origin.1.abs()).max(far.0.abs()).max(far.1.abs()).max(1.0);
+        if step.0.abs().min(step.1.abs()) < reach * GPU_MIN_STEP {
+            return None;
+        }
+
+        let started = Instant::now();
+        let mut indices = Vec::new();
+        let mut points = Vec::new();
+        for y in 0..height {
+            for x in 0..width {
+                if let Some((dx, dy)) = sample(x, y) {
+                    indices.push((y * width + x) as usize);
+                    points.push([
+                        (origin.0 + (x as f64 + dx) * step.0) as f32,
+                        (origin.1 + (y as f64 + dy) * step.1) as f32,
+                    ]);
+                }
+            }
+        }
+        let traced = match gpu.kernel.run(&gpu.device, &gpu.queue, &params, &points) {
+            Ok(traced) => traced,
+            Err(err) => {
+                *gpu.failure.lock() = Some(err);
+                return None;
+            }
+        };
+        *gpu.failure.lock() = None;
+        let mut orbits = vec![Orbit::new(); (width * height) as usize];
+        for (i, orbit) in indices.into_iter().zip(traced) {
+            orbits[i] = orbit;
+        }
+        self.perf.record(TileTiming {
+            y: 0,
+            rows: height,
+            millis: started.elapsed().as_secs_f64() * 1000.0,
+        });
+        Some(orbits)
+    }
+
+    // Colors the orbits by the coloring mode, then paints bounded orbits by their
+    // period when period coloring is on.
+    fn colorize(&self, state: &FractalState, width: u32, height: u32, step: (f64, f64), orbits: &[Orbit]) -> ColorBuffer {
+        let mut out = self.colorize_mode(state, width, height, step, orbits);
+        if state.colors_periods() {
+            for (i, orbit) in orbits.iter().enumerate() {
+                if !orbit.escaped && orbit.period > 0 {
+                    out.pixels[i] = self.period_color(state, orbit.period);
+                    out.alpha[i] = 1.0;
+                }
+            }
+        }
+        out
+    }
+
+    fn colorize_mode(&self, state: &FractalState, width: u32, height: u32, step: (f64, f64), orbits: &[Orbit]) -> ColorBuffer {
+        let mut out = ColorBuffer::new(width, height);
+        // Stable (negative exponent) and chaotic (positive) regions get opposite
+        // hues, brightening as the exponent moves away from zero.
+        if state.fractal_type == FractalType::Lyapunov {
+            out.pixels = orbits.par_iter().map(|orbit| {
+                let (hue, strength) = if orbit.exponent <= 0.0 {
+                    (state.hue_offset + 60.0, 1.0 - orbit.exponent.exp())
+                } else {
+                    (state.hue_offset + 240.0, 1.0 - (-3.0 * orbit.exponent).exp())
+                };
+                state.color_space.hue(hue % 360.0, state.saturation, state.value * strength as f32)
+                    .map(color::srgb_to_linear)
+            }).collect();
+            return out;
+        }
+
+        // Ducks never escape; the hue follows the orbit's mean |z| instead.
+        if state.fractal_type == FractalType::Ducks {
+            out.pixels = orbits.par_iter().map(|orbit| {
+                let mean = orbit.magnitude_sum / orbit.iterations.max(1) as f64;
+                let hue = (state.hue_offset + 90.0 * mean as f32) % 360.0;
+                state.color_space.hue(hue, state.saturation, state.value).map(color::srgb_to_linear)
+            }).collect();
+            return out;
+        }
+
+        // Tetration either overflows, colored by escape time, or settles on a
+        // cycle, colored by its period on the other half of the wheel.
+        if state.fractal_type == FractalType::Tetration {
+            out.pixels = orbits.par_iter().map(|orbit| {
+                if orbit.escaped {
+                    self.palette_color(state, state.escape_position(orbit.iterations))
+                } else if orbit.period > 0 {
+                    self.period_color(state, orbit.period)
+                } else {
+                    [0.0, 0.0, 0.0]
+                }
+            }).collect();
+            out.alpha = orbits.iter().map(|orbit| if orbit.escaped || orbit.period > 0 { 1.0 } else { 0.0 }).collect();
+            return out;
+        }
+
+        if state.coloring == ColoringMode::AtomDomains {
+            // Golden-angle hue steps keep neighbouring periods visually distinct.
+            out.pixels = orbits.par_iter().map(|orbit| {
+                let hue = (orbit.min_iteration as f32 * 137.508 + state.hue_offset) % 360.0;
+                state.color_space.hue(hue, state.saturation, state.value).map(color::srgb_to_linear)
+            }).collect();
+            return out;
+        }
+
+        // Orbits that pass close to the trap are bright; the hue drifts with distance.
+        if state.coloring == ColoringMode::OrbitTrap {
+            out.pixels = orbits.par_iter().map(|orbit| {
+                let closeness = (1.0 / (1.0 + 10.0 * orbit.trap_distance)) as f32;
+                let hue = (state.hue_offset + 240.0 * (1.0 - closeness)) % 360.0;
+                state.color_space.hue(hue, state.saturation, state.value * closeness.sqrt()).map(color::srgb_to_linear)
+            }).collect();
+            return out;
+        }
+
+        // The set and everything within a pixel or so of it draw solid, so filaments
+        // far thinner than a pixel still show; bounded orbits are at distance 0.
+        if state.coloring == ColoringMode::DistanceEstimate && state.tracks_derivative() {
+            let samples: Vec<(f64, u32)> = orbits.par_iter().map(|orbit| {
+                let distance = if orbit.escaped { orbit.distance / step.0 } else { 0.0 };
+                (distance, orbit.iterations)
+            }).collect();
+            return self.shade_distance(state, width, height, &samples);
+        }
+
+        // A loaded image wrapped around each escape band, or pasted wherever orbits pass
+        // through the trap; pixels that miss the picture keep their escape-time color.
+        if state.coloring == ColoringMode::Texture && self.texture_image.image.is_some() {
+            let texel = |orbit: &Orbit| match state.texture.source {
+                TextureSource::FinalZ => {
+                    if !orbit.escaped {
+                        return None;
+                    }
+                    let u = orbit.final_z.arg() / std::f64::consts::TAU + 0.5;
+                    let radius = orbit.final_z.norm().max(1.0 + 1e-9);
+                    let v = (radius.ln() / (0.5 * SMOOTH_BAILOUT.ln())).log2().clamp(0.0, 1.0);
+                    self.texture_image.sample(u, 1.0 - v)
+                }
+                TextureSource::Trap => {
+                    let offset = (orbit.trap_point - state.trap.center) / state.texture.size;
+                    let (u, v) = (offset.re + 0.5, 0.5 - offset.im);
+                    let inside = (0.0..1.0).contains(&u) && (0.0..1.0).contains(&v) && orbit.trap_distance.is_finite();
+                    if !inside {
+                        return None;
+                    }
+                    self.texture_image.sample(u, v)
+                }
+            };
+            out.pixels = orbits.par_iter().map(|orbit| {
+                texel(orbit).unwrap_or_else(|| {
+                    if orbit.escaped {
+                        self.palette_color(state, state.escape_position(orbit.iterations))
+                    } else {
+                        [0.0, 0.0, 0.0]
+                    }
+                })
+            }).collect();
+            out.alpha = orbits.iter().map(|orbit| if orbit.escaped || texel(orbit).is_some() { 1.0 } else { 0.0 }).collect();
+            return out;
+        }
+
+        if state.coloring == ColoringMode::SineWaves {
+            out.pixels = orbits.par_iter().map(|orbit| {
+                if orbit.escaped {
+                    // The hue offset turns all three waves together, so color cycling works here too.
+                    state.waves.color(orbit.iterations as f32, state.hue_offset.to_radians())
+                        .map(|c| color::srgb_to_linear(c * state.value))
+                } else {
+                    [0.0, 0.0, 0.0]
+                }
+            }).collect();
+            out.alpha = orbits.iter().map(|orbit| if orbit.escaped { 1.0 } else { 0.0 }).collect();
+            return out;
+        }
+
+        // Escape-time colors darkened where the relief faces away from the light.
+        if state.coloring == ColoringMode::SlopeShading && state.tracks_derivative() {
+            out.pixels = orbits.par_iter().map(|orbit| {
+                if orbit.escaped {
+                    let base = self.palette_color(state, state.escape_position(orbit.iterations));
+                    state.lighting.shade(base, orbit.normal)
+                } else {
+                    [0.0, 0.0, 0.0]
+                }
+            }).collect();
+            out.alpha = orbits.iter().map(|orbit| if orbit.escaped { 1.0 } else { 0.0 }).collect();
+            return out;
+        }
+
+        // Escaped pixels split by which half-plane the escaping iterate landed in, which
+        // traces the cells of the external angle binary expansion; or, with external
+        // angles on, by arg z itself, which approximates the angle of the ray through it.
+        if state.coloring == ColoringMode::BinaryDecomposition {
+            out.pixels = orbits.par_iter().map(|orbit| {
+                if !orbit.escaped {
+                    [0.0, 0.0, 0.0]
+                } else if state.external_angle {
+                    let angle = orbit.final_z.arg() / std::f64::consts::TAU + 0.5;
+                    self.palette_color(state, angle as f32)
+                } else if orbit.final_z.im >= 0.0 {
+                    self.palette_color(state, state.escape_position(orbit.iterations))
+                } else {
+                    [0.0, 0.0, 0.0]
+                }
+            }).collect();
+            out.alpha = orbits.iter().map(|orbit| if orbit.escaped { 1.0 } else { 0.0 }).collect();
+            return out;
+        }
+
+        // Multiplied by the escape-time color, the field lines cross the escape bands
+        // in a grid.
+        if state.coloring == ColoringMode::FinalAngle {
+            out.pixels = orbits.par_iter().map(|orbit| {
+                if !orbit.escaped {
+                    return [0.0, 0.0, 0.0];
+                }
+                let angle = orbit.final_z.arg() / std::f64::consts::TAU + 0.5;
+                let color = self.palette_color(state, angle as f32);
+                if state.angle_by_escape {
+                    let escape = self.palette_color(state, state.escape_position(orbit.iterations));
+                    std::array::from_fn(|c| color[c] * escape[c])
+                } else {
+                    color
+                }
+            }).collect();
+            out.alpha = orbits.iter().map(|orbit| if orbit.escaped { 1.0 } else { 0.0 }).collect();
+            return out;
+        }
+
+        // Averaging colorings replace the escape count with the orbit's smoothed mean term.
+        if state.coloring.is_average() {
+            out.pixels = orbits.par_iter().map(|orbit| {
+                if orbit.escaped {
+                    self.palette_color(state, orbit.smooth_average(SMOOTH_BAILOUT) as f32)
+                } else {
+                    [0.0, 0.0, 0.0]
+                }
+            }).collect();
+            out.alpha = orbits.iter().map(|orbit| if orbit.escaped { 1.0 } else { 0.0 }).collect();
+            return out;
+        }
+
+        // The sum stands in for a smooth iteration count; converged orbits are moved
+        // along the palette by their root, so basins stay apart.
+        if state.coloring == ColoringMode::ExponentialSmoothing {
+            let degree = state.polynomial.degree().max(1) as f32;
+            out.pixels = orbits.par_iter().map(|orbit| {
+                if orbit.escaped {
+                    let root = orbit.root.map_or(0.0, |root| root as f32 / degree);
+                    self.palette_color(state, state.transfer.apply(orbit.average_sum as f32, state.max_iter) + root)
+                } else {
+                    [0.0, 0.0, 0.0]
+                }
+            }).collect();
+            out.alpha = orbits.iter().map(|orbit| if orbit.escaped { 1.0 } else { 0.0 }).collect();
+            return out;
+        }
+
+        // Each doubling of |dz/dc| counts like an iteration, so the transfer curve spreads
+        // the range; interior orbits whose derivative overflowed stay black.
+        if state.coloring == ColoringMode::DerivativeMagnitude && state.tracks_derivative() {
+            out.pixels = orbits.par_iter().map(|orbit| {
+                if orbit.log_derivative.is_finite() {
+                    let doublings = (orbit.log_derivative / std::f64::consts::LN_2).max(0.0) as f32;
+                    self.palette_color(state, state.transfer.apply(doublings, state.max_iter))
+                } else {
+                    [0.0, 0.0, 0.0]
+                }
+            }).collect();
+            out.alpha = orbits.iter().map(|orbit| if orbit.log_derivative.is_finite() { 1.0 } else { 0.0 }).collect();
+            return out;
+        }
+
+        // Newton basins take their hue from the root the pixel converged to and
+        // darken with the number of steps it took to get there.
+        if state.fractal_type == FractalType::Newton {
+            let degree = state.polynomial.degree().max(1) as f32;
+            out.pixels = orbits.par_iter().map(|orbit| match orbit.root {
+                Some(root) => {
+                    let hue = (root as f32 * 360.0 / degree + state.hue_offset) % 360.0;
+                    let steps = newton_smooth_steps(orbit, state.polynomial.roots[root], state.root_method.order());
+                    let value = state.value * (-state.convergence_shading * steps / 10.0).exp();
+                    state.color_space.hue(hue, state.saturation, value).map(color::srgb_to_linear)
+                }
+                None => [0.0, 0.0, 0.0],
+            }).collect();
+            out.alpha = orbits.iter().map(|orbit| if orbit.root.is_some() { 1.0 } else { 0.0 }).collect();
+            return out;
+        }
+
+        out.pixels = orbits.par_iter().map(|orbit| {
+            if orbit.escaped {
+                // Orbits that converged rather than diverged sit on the opposite side of the wheel.
+                let converged = if orbit.root.is_some() { 0.5 } else { 0.0 };
+                self.palette_color(state, state.escape_position(orbit.iterations) + converged)
+            } else {
+                [0.0, 0.0, 0.0]
+            }
+        }).collect();
+        out.alpha = orbits.iter().map(|orbit| if orbit.escaped { 1.0 } else { 0.0 }).collect();
+        out
+    }
+
+
+    #[inline(always)]
+    fn mandelbrot(&self, c: Complex64, max_iter: u32) -> u32 {
+        let mut z = Complex64::new(0.0, 0.0);
+        for i in 0..max_iter {
+            if z.norm_sqr() > 4.0 {
+                return i;
+            }
+            z = z * z + c;
+        }
+        max_iter
+    }
+
+    // Golden-angle hue steps keep neighbouring periods apart, on the other half of the
+    // wheel from the exterior, dimming as the period grows.
+    fn period_color(&self, state: &FractalState, period: u32) -> [f32; 3] {
+        let hue = (state.hue_offset + 180.0 + period as f32 * 137.508) % 360.0;
+        let value = state.value * (0.4 + 0.6 / period as f32);
+        state.color_space.hue(hue, state.saturation, value).map(color::srgb_to_linear)
+    }
+
+    // Linear color for `t` along the palette, rotated by the hue offset and shaded by
+    // the saturation and value sliders the same way they shade an HSV hue.
+    #[inline(always)]
+    fn palette_color(&self, state: &FractalState, t: f32) -> [f32; 3] {
+        state.palette.sample(t + state.hue_offset / 360.0, state.color_space, state.interpolation)
+            .map(|c| color::srgb_to_linear(state.value * (1.0 - state.saturation + state.saturation * c)))
+    }
+}
+
+impl FractalApp {
+    fn renderer(&self) -> Renderer {
+        self.renderer_for(&self.state.read())
+    }
+
+    // For callers already holding the state lock.
+    fn renderer_for(&self, state: &FractalState) -> Renderer {
+        Renderer {
+            state: state.clone(),
+            quality: self.quality,
+            thread_count: self.thread_count,
+            layers: self.layers.clone(),
+            background: self.background.clone(),
+            texture_image: self.texture_image.clone(),
+            perf: TileRecorder::default(),
+            backend: self.backends.active,
+            gpu: self.backends.gpu.clone(),
+            buddhabrot: self.buddhabrot.clone(),
+            scene: self.scene.clone(),
+            bulb: self.bulb.clone(),
+            quaternion: self.quaternion.clone(),
+            ifs: self.ifs.clone(),
+            lsystem: self.lsystem.clone(),
+            attractor: self.attractor.clone(),
+            kleinian: self.kleinian,
+            apollonian: self.apollonian.clone(),
+            orbit_plot: self.orbit_plot.clone(),
+            job: None,
+            cancel: CancelToken::default(),
+        }
+    }
+
+    // Preview colored from stored orbits, for color cycling and color-only edits. The
+    // orbits are iterated only when the view changed; otherwise the stored ones are
+    // recolored, with the hue offset turned by `hue_shift`.
+    fn recolor_preview(&mut self, needs_update: bool, hue_shift: f32) -> ColorBuffer {
+        let renderer = self.renderer();
+        let (width, height) = self.quality.preview_size(renderer.state.width, renderer.state.height);
+        let (orbits, step) = match self.stored_orbits.take() {
+            Some(stored) if !needs_update && stored.0.len() == (width * height) as usize => stored,
+            _ => {
+                self.traced_view = Some((renderer.state.clone(), self.quality, renderer.backend));
+                renderer.preview_orbits(None)
+            }
+        };
+        let out = renderer.color_preview(&orbits, step, hue_shift);
+        self.stored_orbits = Some((orbits, step));
+        out
+    }
+
+    // Cancels the preview in progress and starts rendering the current view on a
+    // worker thread; poll_render shows its tiles as they finish, then the frame.
+    // A `coarse` frame uses the interactive quality settings.
+    fn start_render(&mut self, coarse: bool) {
+        // A frame that finished since the last poll can still seed a pan.
+        if let Some(frame) = self.render_job.as_ref().and_then(|job| job.take_frame()) {
+            self.stored_orbits = frame.orbits;
+        }
+        self.cancel_render();
+        let mut renderer = self.renderer();
+        renderer.perf = self.perf.begin();
+        if coarse {
+            renderer.quality = self.quality.interactive();
+        }
+        self.coarse_frame = coarse;
+        let keeps_orbits = self.keeps_orbits();
+        let panned = if keeps_orbits { self.panned_orbits(&mut renderer) } else { None };
+        self.stored_orbits = None;
+        self.traced_view = Some((renderer.state.clone(), renderer.quality, renderer.backend));
+        let (width, height) = renderer.quality.preview_size(renderer.state.width, renderer.state.height);
+        let job = Arc::new(RenderJob::new(width, height));
+        renderer.job = Some(job.clone());
+        renderer.cancel = job.token.clone();
+        let worker_job = job.clone();
+        thread::spawn(move || {
+            let frame = if keeps_orbits {
+                let panned = panned.as_ref().map(|(orbits, shift)| (orbits.as_slice(), *shift));
+                let (orbits, step) = renderer.preview_orbits(panned);
+                RenderedFrame { buffer: renderer.color_preview(&orbits, step, 0.0), orbits: Some((orbits, step)) }
+            } else {
+                RenderedFrame { buffer: renderer.generate_preview(), orbits: None }
+            };
+            worker_job.post_frame(frame);
+        });
+        self.render_job = Some(job);
+    }
+
+    // When the view has only moved since the stored orbits were traced, hands them
+    // over with the shift in preview pixels. The pan is snapped to whole pixels, in
+    // the renderer's state and the live view, so the copied orbits line up exactly.
+    fn panned_orbits(&mut self, renderer: &mut Renderer) -> Option<(Vec<Orbit>, (i64, i64))> {
+        let state = &mut renderer.state;
+        let (orbits, step) = self.stored_orbits.take()?;
+        let (view, quality, backend) = self.traced_view.as_ref()?;
+        if *quality != renderer.quality
+            || *backend != renderer.backend
+            || view.width != state.width
+            || view.height != state.height
+        {
+            return None;
+        }
+        let mut moved = view.clone();
+        moved.center_x = state.center_x;
+        moved.center_y = state.center_y;
+        if !recorder::same_view(&moved, state) {
+            return None;
+        }
+        let (width, height) = renderer.quality.preview_size(state.width, state.height);
+        let dx = ((state.center_x - view.center_x) / step.0).round();
+        let dy = ((state.center_y - view.center_y) / step.1).round();
+        if dx.abs() >= width as f64 || dy.abs() >= height as f64 || orbits.len() != (width * height) as usize {
+            return None;
+        }
+        state.center_x = view.center_x + dx * step.0;
+        state.center_y = view.center_y + dy * step.1;
+        let mut live = self.state.write();
+        live.center_x = state.center_x;
+        live.center_y = state.center_y;
+        Some((orbits, (dx as i64, dy as i64)))
+    }
+
+    fn cancel_render(&mut self) {
+        if let Some(job) = self.render_job.take() {
+            job.cancel();
+        }
+    }
+
+    // Pastes tiles finished since the last frame into the live view and returns the
+    // whole frame once the worker is done.
+    fn poll_render(&mut self, ctx: &egui::Context) -> Option<ColorBuffer> {
+        let job = self.render_job.clone()?;
+        if let Some(frame) = job.take_frame() {
+            self.render_job = None;
+            self.stored_orbits = frame.orbits;
+            self.perf.finish(frame.buffer.height);
+            return Some(frame.buffer);
+        }
+        // Tiles at another resolution than the frame on screen (a coarse frame and
+        // the full one after it) wait for the whole frame rather than blank it out.
+        let tiles = job.take_tiles();
+        let buffer = match &mut self.color_buffer {
+            Some(buffer) if buffer.width == job.width && buffer.height == job.height => Some(buffer),
+            Some(_) => None,
+            slot => Some(slot.insert(ColorBuffer::new(job.width, job.height))),
+        };
+        if let (Some(buffer), false) = (buffer, tiles.is_empty()) {
+            for (y, tile) in &tiles {
+                buffer.paste(tile, 0, *y);
+            }
+            self.regrade = true;
+        }
+        ctx.request_repaint_after(Duration::from_millis(30));
+        None
+    }
+
+    // Whether the view was dragged or zoomed within the settle time.
+    fn interacting(&self) -> bool {
+        self.drag_start.is_some() || self.last_interaction.is_some_and(|t| t.elapsed() < INTERACTION_SETTLE)
+    }
+
+    // Whether the live view is a single pass over the base fractal, so recoloring
+    // the stored orbits reproduces it exactly.
+    fn keeps_orbits(&self) -> bool {
+        self.state.read().fractal_type.colors_orbits()
+            && self.quality.antialias.samples <= 1
+            && !self.layers.iter().any(|layer| layer.visible)
+    }
+
+    // Applies the post-processing pipeline and quantizes for export.
+    fn finish(&self, buffer: &ColorBuffer) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+        postprocess::apply(buffer, &self.post).to_image_in(self.export_profile, self.post.dither)
+    }
+
+    // `finish` for the live view, which always shows sRGB.
+    fn finish_preview(&self, buffer: &ColorBuffer) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+        postprocess::apply(buffer, &self.post).to_image_in(ColorProfile::Srgb, self.post.dither)
+    }
+
+    fn handle_mouse_input(&mut self, ui: &mut egui::Ui, available_size: Vec2) {
+        let rect = ui.max_rect();
+        let response = ui.allocate_rect(rect, egui::Sense::click_and_drag());
+
+        let pick = self.seed_pick || ui.input(|i| i.modifiers.command);
+        if response.clicked()
+            && pick
+            && let Some(pointer) = response.interact_pointer_pos()
+        {
+            self.pick_julia_seed((pointer - self.image_origin).to_pos2());
+        }
+
+        if self.cross_section_mode {
+            self.drag_cross_section(&response);
+        } else if self.region.enabled {
+            self.drag_region(&response);
+        } else if self.drag_root(&response) {
+            // Moving a Newton root takes precedence over panning.
+        } else if self.state.read().fractal_type.is_3d() {
+            if response.dragged() {
+                let delta = response.drag_delta();
+                self.scene.camera.orbit(-delta.x as f64 * 0.01, delta.y as f64 * 0.01);
+                self.state.write().needs_update = true;
+                self.last_interaction = Some(Instant::now());
+            }
+        } else if response.dragged() {
+            if let Some(drag_start) = self.drag_start {
+                if let Some((start_x, start_y)) = self.drag_start_center {
+                    let delta = response.drag_delta();
+                    let mut state = self.state.write();
+                    let scale = 2.5 / state.zoom;
+                    let sensitivity = 0.5;
+                    let dx = (delta.x as f64) * scale * sensitivity / (available_size.x as f64);
+                    let dy = (delta.y as f64) * scale * sensitivity / (available_size.y as f64);
+                    let previous = (state.center_x, state.center_y);
+                    state.center_x = start_x - dx;
+                    state.center_y = start_y - dy;
+                    state.needs_update = true;
+                    self.navigator.track_drag(state.center_x - previous.0, state.center_y - previous.1);
+                    self.last_interaction = Some(Instant::now());
+                }
+            } else {
+                let state = self.state.read();
+                self.drag_start = Some(response.interact_pointer_pos().unwrap());
+                self.drag_start_center = Some((state.center_x, state.center_y));
+                self.navigator.stop();
+            }
+        } else {
+            // Letting go mid-drag hands the remaining momentum to the glide.
+            if self.drag_start.is_some() {
+                self.navigator.release();
+            }
+            self.drag_start = None;
+            self.drag_start_center = None;
+        }
+
+        if response.hovered() {
+            let scroll = ui.input(|i| i.raw_scroll_delta.y);
+            if scroll != 0.0 && self.state.read().fractal_type.is_3d() {
+                let camera = &mut self.scene.camera;
+                camera.distance = (camera.distance * if scroll > 0.0 { 0.95 } else { 1.05 }).clamp(1.1, 20.0);
+                self.state.write().needs_update = true;
+            } else if scroll != 0.0 {
+                let current = self.state.read().zoom;
+                let zoom_factor = if scroll > 0.0 { 1.05 } else { 0.95 };
+                let new_zoom = self.navigator.zoom_target().unwrap_or(current) * zoom_factor;
+                
+                // No upper bound: the finder and deep zooms go far past the slider.
+                if new_zoom >= 0.1 || scroll > 0.0 {
+                    self.navigator.zoom_to(current, new_zoom);
+                }
+            }
+        }
+    }
+
+    // Uses the clicked point of the parameter plane as the seed and switches to its Julia set.
+    fn pick_julia_seed(&mut self, pos: Pos2) {
+        let mut state = self.state.write();
+        if state.in_julia_plane() || state.fractal_type.is_3d() || matches!(state.fractal_type, FractalType::Ifs | FractalType::LSystem | FractalType::Attractor | FractalType::Kleinian | FractalType::Apollonian | FractalType::OrbitPlot) {
+            return;
+        }
+        self.parameter_view = Some((state.fractal_type, state.zoom, state.center_x, state.center_y));
+        state.julia_seed = state.pixel_to_complex(pos.x as f64, pos.y as f64);
+        // Types with their own Julia plane switch to it; everything else opens the
+        // quadratic Julia set.
+        if state.has_julia_plane() {
+            state.julia_plane = true;
+        } else {
+            state.fractal_type = FractalType::Julia;
+        }
+        (state.zoom, state.center_x, state.center_y) = state.fractal_type.julia_view();
+        state.needs_update = true;
+        self.seed_pick = false;
+        self.navigator.stop();
+    }
+
+    // Returns true while a polynomial root marker is being dragged.
+    fn drag_root(&mut self, response: &egui::Response) -> bool {
+        if response.drag_released() {
+            return self.root_drag.take().is_some();
+        }
+        let mut state = self.state.write();
+        if state.fractal_type != FractalType::Newton {
+            return false;
+        }
+        let Some(pointer) = response.interact_pointer_pos() else {
+            return self.root_drag.is_some();
+        };
+        let pos = (pointer - self.image_origin).to_pos2();
+        if response.drag_started() {
+            self.root_drag = state.polynomial.roots.iter()
+                .position(|&r| state.complex_to_pixel(r).distance(pos) < 8.0);
+        }
+        let Some(i) = self.root_drag else {
+            return false;
+        };
+        if response.dragged() {
+            state.polynomial.roots[i] = state.pixel_to_complex(pos.x as f64, pos.y as f64);
+            state.needs_update = true;
+        }
+        true
+    }
+
+    // Dragging near a handle moves it; dragging anywhere else draws a new line.
+    fn drag_cross_section(&mut self, response: &egui::Response) {
+        let Some(pointer) = response.interact_pointer_pos() else {
+            return;
+        };
+        let pos = (pointer - self.image_origin).to_pos2();
+
+        if response.drag_started() {
+            let hit = self.cross_section.as_ref().and_then(|s| s.endpoint_near(pos, 8.0));
+            if hit.is_none() {
+                self.cross_section = Some(CrossSection { start: pos, end: pos });
+            }
+            self.cross_section_handle = Some(hit.unwrap_or(1));
+        }
+
+        if response.dragged() {
+            if let (Some(section), Some(handle)) = (&mut self.cross_section, self.cross_section_handle) {
+                section.set_endpoint(handle, pos);
+            }
+        } else {
+            self.cross_section_handle = None;
+        }
+    }
+
+    // Dragging draws the region to boost, which renders once the drag ends.
+    fn drag_region(&mut self, response: &egui::Response) {
+        if let Some(pointer) = response.interact_pointer_pos() {
+            let pos = (pointer - self.image_origin).to_pos2();
+            if response.drag_started() {
+                self.region.start = Some(pos);
+            }
+            if let Some(start) = self.region.start {
+                self.region.rect = Some(egui::Rect::from_two_pos(start, pos));
+            }
+        }
+        if response.drag_released() && self.region.start.take().is_some() {
+            self.boost_region();
+        }
+    }
+
+    // Re-renders the selected rectangle at boosted quality and pastes it into the
+    // displayed frame; the next full render replaces it again.
+    fn boost_region(&mut self) {
+        let Some(buffer) = &self.color_buffer else {
+            return;
+        };
+        let state = self.state.read().clone();
+        let scale = buffer.width as f32 / state.width as f32;
+        let Some((x, y, width, height)) = self.region.pixel_bounds(scale, buffer.width, buffer.height) else {
+            return;
+        };
+        let (origin, step) = state.view_mapping(buffer.width, buffer.height);
+        let region_origin = (origin.0 + x as f64 * step.0, origin.1 + y as f64 * step.1);
+
+        let mut renderer = self.renderer();
+        renderer.quality = self.region.boosted(self.quality);
+        let patch = renderer.render_layers(&state, width, height, region_origin, step);
+
+        if let Some(buffer) = &mut self.color_buffer {
+            buffer.paste(&patch, x, y);
+        }
+        self.regrade = true;
+    }
+
+    // Escape time sampled along the cross-section, with x measured in complex-plane units.
+    fn cross_section_profile(&self, section: &CrossSection) -> Vec<[f64; 2]> {
+        let state = self.state.read();
+        let start = state.pixel_to_complex(section.start.x as f64, section.start.y as f64);
+        let end = state.pixel_to_complex(section.end.x as f64, section.end.y as f64);
+        let length = (end - start).norm();
+        let renderer = self.renderer_for(&state);
+        section
+            .samples(400)
+            .map(|(t, pos)| {
+                let c = state.pixel_to_complex(pos.x as f64, pos.y as f64);
+                [t * length, renderer.iterate_fractal(c, &state) as f64]
+            })
+            .collect()
+    }
+
+    fn randomize_params(&mut self) {
+        // ChaCha8 is portable across platforms and releases, so shared seeds stay valid.
+        // Only an empty field rolls a fresh seed; a typo must not pass for the seed typed.
+        let input = self.seed_input.trim();
+        let seed = if input.is_empty() {
+            rand::thread_rng().r#gen()
+        } else {
+            match input.parse() {
+                Ok(seed) => seed,
+                Err(_) => {
+                    self.seed_error = format!("\"{}\" is not a seed; seeds are whole numbers from 0 to {}", input, u64::MAX);
+                    return;
+                }
+            }
+        };
+        self.seed_error.clear();
+        self.last_seed = Some(seed);
+        let mut rng = ChaCha8Rng::seed_from_u64(seed);
+        let original = self.state.read().clone();
+        let mut candidate = original.clone();
+        let mut best: Option<(f64, FractalState)> = None;
+        // Layers, background, quality and the GPU would all shift the scores between setups.
+        let renderer = Renderer {
+            quality: QualitySettings::default(),
+            layers: Vec::new(),
+            background: Background::default(),
+            backend: Backend::Scalar,
+            ..self.renderer()
+        };
+
+        // Score a cheap thumbnail of each roll and keep going until one shows some structure.
+        // Thumbnails render only the rolled fields over the defaults, so a seed gives the
+        // same result whatever view and coloring settings it is rolled from.
+        for _ in 0..RANDOMIZE_ATTEMPTS {
+            roll_params(&mut candidate, &mut rng);
+            let preview = FractalState {
+                fractal_type: candidate.fractal_type,
+                power: candidate.power,
+                secondary_param: candidate.secondary_param,
+                hue_offset: candidate.hue_offset,
+                saturation: candidate.saturation,
+                value: candidate.value,
+                palette: candidate.palette.clone(),
+                max_iter: 250,
+                ..FractalState::default()
+            };
+            let score = structure_score(&renderer.render_view(&preview, 64, 48).to_image());
+            if self.lock_palette {
+                // Colors are still rolled and scored above, so a seed gives the same shape either way.
+                candidate.palette = original.palette.clone();
+                candidate.hue_offset = original.hue_offset;
+                candidate.saturation = original.saturation;
+                candidate.value = original.value;
+            }
+            if best.as_ref().is_none_or(|(best_score, _)| score > *best_score) {
+                best = Some((score, candidate.clone()));
+            }
+            if score >= RANDOMIZE_MIN_SCORE {
+                break;
+            }
+        }
+
+        if let Some((_, chosen)) = best {
+            self.apply_state(chosen);
+        }
+    }
+
+    // Renders one continuous image over the whole desktop and writes one file per monitor.
+    fn render_wallpaper(&self) -> image::ImageResult<usize> {
+        let Some((left, top, width, height)) = wallpaper::desktop_bounds(&self.monitors) else {
+            return Ok(0);
+        };
+        let state = self.state.read().clone();
+        let scale = 2.5 / state.zoom;
+        // Keep the on-screen vertical span across the desktop height with square pixels.
+        let step = 2.0 * scale / height as f64;
+        let mid_x = state.center_x - 0.75 * scale;
+        let origin = (
+            mid_x - width as f64 / 2.0 * step,
+            state.center_y - height as f64 / 2.0 * step,
+        );
+        let img = self.finish(&self.renderer().render_layers(&state, width, height, origin, (step, step)));
+
+        let stamp = Local::now().format("%Y%m%d_%H%M%S");
+        for (i, monitor) in self.monitors.iter().enumerate() {
+            let tile = wallpaper::crop(&img, monitor, left, top);
+            color::save_png(&tile, format!("wallpaper_{}_{}.png", stamp, i + 1), self.export_profile)?;
+        }
+        Ok(self.monitors.len())
+    }
+
+    fn render_morph_frame(&self, frame: &MorphFrame, width: u32, height: u32) -> ColorBuffer {
+        let renderer = self.renderer();
+        match frame {
+            MorphFrame::Single(state) => renderer.render_view(state, width, height),
+            MorphFrame::CrossFade(a, b, t) => {
+                let mut buffer = renderer.render_view(a, width, height);
+                let other = renderer.render_view(b, width, height);
+                let t = *t as f32;
+                for (dst, src) in buffer.pixels.iter_mut().zip(&other.pixels) {
+                    for (d, s) in dst.iter_mut().zip(src) {
+                        *d = *d * (1.0 - t) + s * t;
+                    }
+                }
+                buffer
+            }
+        }
+    }
+
+    // Writes a 30 fps PNG sequence of the morph at the current window size.
+    fn export_morph(&self, morph: &Morph) -> image::ImageResult<usize> {
+        let (width, height) = {
+            let state = self.state.read();
+            (state.width, state.height)
+        };
+        let frames = ((morph.duration * 30.0).round() as usize).max(2);
+        let stamp = Local::now().format("%Y%m%d_%H%M%S");
+        for i in 0..frames {
+            let t = i as f64 / (frames - 1) as f64;
+            let img = self.finish(&self.render_morph_frame(&morph.frame_at(t), width, height));
+            color::save_png(&img, format!("morph_{}_{:04}.png", stamp, i), self.export_profile)?;
+        }
+        Ok(frames)
+    }
+
+    fn export_timeline(&self, timeline: &Timeline, prefix: &str) -> image::ImageResult<usize> {
+        let (width, height) = {
+            let state = self.state.read();
+            (state.width, state.height)
+        };
+        let frames = ((timeline.duration() * 30.0).round() as usize).max(2);
+        let stamp = Local::now().format("%Y%m%d_%H%M%S");
+        let renderer = self.renderer();
+        for i in 0..frames {
+            let t = timeline.duration() * i as f64 / (frames - 1) as f64;
+            let Some(state) = timeline.sample(t) else {
+                break;
+            };
+            let img = self.finish(&renderer.render_view(&state, width, height));
+            color::save_png(&img, format!("{}_{}_{:04}.png", prefix, stamp, i), self.export_profile)?;
+        }
+        Ok(frames)
+    }
+
+    // Small sRGB PNG sent alongside shared parameters.
+    fn share_thumbnail(&self, state: &FractalState) -> Vec<u8> {
+        let img = postprocess::apply(&self.renderer_for(state).render_view(state, 256, 144), &self.post).to_image();
+        let mut bytes = Vec::new();
+        color::write_png(&img, &mut bytes, ColorProfile::Srgb).unwrap();
+        bytes
+    }
+
+    fn poll_gallery(&mut self, ctx: &egui::Context) {
+        if !self.gallery.is_busy() {
+            return;
+        }
+        match self.gallery.poll() {
+            None => ctx.request_repaint_after(std::time::Duration::from_millis(100)),
+            Some(GalleryReply::Shared(Ok(url))) => {
+                self.gallery_status = "Shared!".to_string();
+                self.gallery_link = Some(url);
+            }
+            Some(GalleryReply::Shared(Err(err))) => {
+                self.gallery_status = format!("Share failed: {}", err);
+            }
+            Some(GalleryReply::Listing(Ok(entries))) => {
+                self.gallery_status = format!("{} shared locations", entries.len());
+                self.gallery_entries = entries;
+                self.gallery_open = true;
+            }
+            Some(GalleryReply::Listing(Err(err))) => {
+                self.gallery_status = format!("Could not load gallery: {}", err);
+            }
+        }
+    }
+
+    // Renders the current view under both quality configurations, before grading, on
+    // a worker thread; poll_comparison picks up the result.
+    fn run_comparison(&mut self) {
+        let mut renderer = self.renderer();
+        let (a, b) = (self.compare.a, self.compare.b);
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            renderer.quality = a;
+            let first = renderer.generate_mandelbrot();
+            renderer.quality = b;
+            let second = renderer.generate_mandelbrot();
+            let _ = sender.send(Comparison::new(&first, &second));
+        });
+        self.compare_render = Some(receiver);
+    }
+
+    // Whether the comparison finished since the last frame.
+    fn poll_comparison(&mut self, ctx: &egui::Context) -> bool {
+        let Some(receiver) = &self.compare_render else {
+            return false;
+        };
+        match receiver.try_recv() {
+            Ok(result) => {
+                self.compare.result = Some(result);
+                self.compare_render = None;
+                true
+            }
+            Err(TryRecvError::Empty) => {
+                ctx.request_repaint_after(std::time::Duration::from_millis(100));
+                false
+            }
+            Err(TryRecvError::Disconnected) => {
+                self.compare_render = None;
+                false
+            }
+        }
+    }
+
+    // Renders, grades and saves the current view at full resolution on a worker
+    // thread; poll_export shows the outcome once it arrives.
+    fn start_export(&mut self) {
+        let renderer = self.renderer();
+        let (post, profile, transparent) = (self.post, self.export_profile, self.transparent_export);
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            let graded = postprocess::apply(&renderer.generate_mandelbrot(), &post);
+            let filename = format!("fractol_{}.png", Local::now().format("%Y%m%d_%H%M%S"));
+            // With an alpha channel that cuts the transparent region away, if one is set.
+            let saved = match transparent {
+                Some(region) => color::save_png(&graded.to_rgba_in(profile, post.dither, region), &filename, profile),
+                None => color::save_png(&graded.to_image_in(profile, post.dither), &filename, profile),
+            };
+            let _ = sender.send(match saved {
+                Ok(()) => format!("Saved {}", filename),
+                Err(err) => format!("Save failed: {}", err),
+            });
+        });
+        self.export_render = Some(receiver);
+        self.export_status = "Rendering…".to_string();
+    }
+
+    fn poll_export(&mut self, ctx: &egui::Context) {
+        let Some(receiver) = &self.export_render else {
+            return;
+        };
+        self.export_status = match receiver.try_recv() {
+            Ok(status) => status,
+            Err(TryRecvError::Empty) => {
+                ctx.request_repaint_after(std::time::Duration::from_millis(100));
+                return;
+            }
+            Err(TryRecvError::Disconnected) => "Render failed".to_string(),
+        };
+        self.export_render = None;
+    }
+
+    fn apply_state(&self, snapshot: FractalState) {
+        let mut state = self.state.write();
+        let (width, height) = (state.width, state.height);
+        *state = snapshot;
+        state.width = width;
+        state.height = height;
+        state.needs_update = true;
+    }
+
+    fn step_navigation(&mut self, ctx: &egui::Context) {
+        let mut state = self.state.write();
+        if self.navigator.step(&mut state) {
+            self.last_interaction = Some(Instant::now());
+            ctx.request_repaint();
+        }
+    }
+
+    fn step_playback(&mut self, ctx: &egui::Context) {
+        let Some(playback) = &self.playback else {
+            return;
+        };
+        if let Some(frame) = playback.current() {
+            self.apply_state(frame);
+        }
+        if playback.finished() {
+            self.playback = None;
+        } else {
+            ctx.request_repaint();
+        }
+    }
+}
+
+impl eframe::App for FractalApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.step_navigation(ctx);
+        self.step_playback(ctx);
+        self.poll_gallery(ctx);
+        self.poll_export(ctx);
+
+        egui::SidePanel::left("controls").show(ctx, |ui| {
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                ui.heading("Fractal Controls");
+            
+                let mut state = self.state.write();
+            
+                let previous_type = state.fractal_type;
+                ui.horizontal_wrapped(|ui| {
+                    ui.label("Fractal Type:");
+                    if ui.radio_value(&mut state.fractal_type, FractalType::Classic, "Classic").clicked() {
+                        state.needs_update = true;
+                    }
+                    if ui.radio_value(&mut state.fractal_type, FractalType::Spiral, "Spiral").clicked() {
+                        state.needs_update = true;
+                    }
+                    if ui.radio_value(&mut state.fractal_type, FractalType::Flower, "Flower").clicked() {
+                        state.needs_update = true;
+                    }
+                    if ui.radio_value(&mut state.fractal_type, FractalType::Phoenix, "Phoenix").clicked() {
+                        state.needs_update = true;
+                    }
+                    if ui.radio_value(&mut state.fractal_type, FractalType::Butterfly, "Butterfly").clicked() {
+                        state.needs_update = true;
+                    }
+                    if ui.radio_value(&mut state.fractal_type, FractalType::Newton, "Newton").clicked() {
+                        state.needs_update = true;
+                    }
+                    if ui.radio_value(&mut state.fractal_type, FractalType::Julia, "Julia").clicked() {
+                        state.needs_update = true;
+                    }
+                    if ui.radio_value(&mut state.fractal_type, FractalType::BurningShip, "Burning Ship").clicked() {
+                        state.needs_update = true;
+                    }
+                    if ui.radio_value(&mut state.fractal_type, FractalType::Tricorn, "Tricorn").clicked() {
+                        state.needs_update = true;
+                    }
+                    if ui.radio_value(&mut state.fractal_type, FractalType::Buddhabrot, "Buddhabrot").clicked() {
+                        state.needs_update = true;
+                    }
+                    if ui.radio_value(&mut state.fractal_type, FractalType::Lyapunov, "Lyapunov").clicked() {
+                        state.needs_update = true;
+                    }
+                    if ui.radio_value(&mut state.fractal_type, FractalType::MagnetI, "Magnet I").clicked() {
+                        state.needs_update = true;
+                    }
+                    if ui.radio_value(&mut state.fractal_type, FractalType::MagnetII, "Magnet II").clicked() {
+                        state.needs_update = true;
+                    }
+                    if ui.radio_value(&mut state.fractal_type, FractalType::Nova, "Nova").clicked() {
+                        state.needs_update = true;
+                    }
+                    if ui.radio_value(&mut state.fractal_type, FractalType::Custom, "Custom").clicked() {
+                        state.needs_update = true;
+                    }
+                    if ui.radio_value(&mut state.fractal_type, FractalType::Lambda, "Lambda").clicked() {
+                        state.needs_update = true;
+                    }
+                    if ui.radio_value(&mut state.fractal_type, FractalType::Mandelbulb, "Mandelbulb").clicked() {
+                        state.needs_update = true;
+                    }
+                    if ui.radio_value(&mut state.fractal_type, FractalType::QuaternionJulia, "Quaternion Julia").clicked() {
+                        state.needs_update = true;
+                    }
+                    if ui.radio_value(&mut state.fractal_type, FractalType::Ifs, "IFS").clicked() {
+                        state.needs_update = true;
+                    }
+                    if ui.radio_value(&mut state.fractal_type, FractalType::LSystem, "L-System").clicked() {
+                        state.needs_update = true;
+                    }
+                    if ui.radio_value(&mut state.fractal_type, FractalType::Attractor, "Strange Attractor").clicked() {
+                        state.needs_update = true;
+                    }
+                    if ui.radio_value(&mut state.fractal_type, FractalType::Kleinian, "Kleinian").clicked() {
+                        state.needs_update = true;
+                    }
+                    if ui.radio_value(&mut state.fractal_type, FractalType::Collatz, "Collatz").clicked() {
+                        state.needs_update = true;
+                    }
+                    if ui.radio_value(&mut state.fractal_type, FractalType::SinZ, "c·sin(z)").clicked() {
+                        state.needs_update = true;
+                    }
+                    if ui.radio_value(&mut state.fractal_type, FractalType::ExpZ, "c·exp(z)").clicked() {
+                        state.needs_update = true;
+                    }
+                    if ui.radio_value(&mut state.fractal_type, FractalType::CoshZ, "c·cosh(z)").clicked() {
+                        state.needs_update = true;
+                    }
+                    if ui.radio_value(&mut state.fractal_type, FractalType::Apollonian, "Apollonian Gasket").clicked() {
+                        state.needs_update = true;
+                    }
+                    if ui.radio_value(&mut state.fractal_type, FractalType::Ducks, "Ducks").clicked() {
+                        state.needs_update = true;
+                    }
+                    if ui.radio_value(&mut state.fractal_type, FractalType::Cubic, "Cubic Slice").clicked() {
+                        state.needs_update = true;
+                    }
+                    if ui.radio_value(&mut state.fractal_type, FractalType::InverseSquare, "1/z² + c").clicked() {
+                        state.needs_update = true;
+                    }
+                    if ui.radio_value(&mut state.fractal_type, FractalType::Quotient, "(z² + c)/(z² − c)").clicked() {
+                        state.needs_update = true;
+                    }
+                    if ui.radio_value(&mut state.fractal_type, FractalType::OrbitPlot, "Orbit Plot").clicked() {
+                        state.needs_update = true;
+                    }
+                    if ui.radio_value(&mut state.fractal_type, FractalType::Tetration, "Tetration").clicked() {
+                        state.needs_update = true;
+                    }
+                });
+                ui.checkbox(&mut self.type_defaults, "Apply type defaults (palette, framing, iterations)");
+                if state.fractal_type != previous_type {
+                    let view = match state.fractal_type {
+                        FractalType::Ifs => Some(self.ifs.preset.view()),
+                        FractalType::Attractor => Some(self.attractor.attractor.view()),
+                        FractalType::OrbitPlot => Some(self.orbit_plot.view()),
+                        other if self.type_defaults => Some(other.home_view()),
+                        other => other.default_view(),
+                    };
+                    if let Some((colors, max_iter)) = state.fractal_type.default_look().filter(|_| self.type_defaults) {
+                        state.palette = Palette::from_rgb(colors);
+                        state.max_iter = max_iter;
+                        self.palette_stop = 0;
+                    }
+                    if let Some((zoom, center_x, center_y)) = view {
+                        state.zoom = zoom;
+                        state.center_x = center_x;
+                        state.center_y = center_y;
+                        self.navigator.stop();
+                    }
+                }
+
+                if state.fractal_type == FractalType::Lyapunov {
+                    ui.horizontal(|ui| {
+                        ui.label("Sequence:");
+                        let edited = ui.add(egui::TextEdit::singleline(&mut self.sequence_input)
+                            .hint_text("AB")
+                            .desired_width(f32::INFINITY)).changed();
+                        if edited {
+                            match Sequence::parse(&self.sequence_input) {
+                                Ok(sequence) => {
+                                    state.lyapunov_sequence = sequence;
+                                    state.needs_update = true;
+                                    self.sequence_error = None;
+                                }
+                                Err(err) => self.sequence_error = Some(err),
+                            }
+                        }
+                    });
+                    if let Some(err) = &self.sequence_error {
+                        ui.colored_label(egui::Color32::from_rgb(230, 90, 80), err);
+                    }
+                    // Markus and Hess's image: a in [2.5, 3.4] across, b in [3.4, 4.0] down.
+                    if ui.button("Zircon Zity").clicked() {
+                        self.sequence_input = "BBBBBBAAAAAA".to_string();
+                        state.lyapunov_sequence = Sequence::parse(&self.sequence_input).expect("preset parses");
+                        self.sequence_error = None;
+                        state.zoom = 2.5 / 0.3;
+                        state.center_x = 3.175;
+                        state.center_y = 3.7;
+                        self.navigator.stop();
+                        state.needs_update = true;
+                    }
+                }
+
+                if state.fractal_type == FractalType::Custom {
+                    // The formula only replaces the live one once it parses.
+                    let edited = ui.add(egui::TextEdit::singleline(&mut self.formula_input)
+                        .hint_text("z = z^2 + c")
+                        .desired_width(f32::INFINITY)).changed();
+                    if edited {
+                        match Formula::parse(&self.formula_input) {
+                            Ok(formula) => {
+                                state.formula = formula;
+                                state.needs_update = true;
+                                self.formula_error = None;
+                            }
+                            Err(err) => self.formula_error = Some(err),
+                        }
+                    }
+                    match &self.formula_error {
+                        Some(err) => ui.colored_label(egui::Color32::from_rgb(230, 90, 80), err),
+                        None => ui.small("Use z, c, i, pi, e, + - * / ^ and sin cos tan exp log sqrt abs conj re im"),
+                    };
+                }
+                if state.fractal_type == FractalType::Phoenix {
+                    ui.horizontal(|ui| {
+                        ui.label("p:");
+                        let re = ui.add(egui::DragValue::new(&mut state.phoenix_p.re).speed(0.001).max_decimals(12));
+                        let im = ui.add(egui::DragValue::new(&mut state.phoenix_p.im).speed(0.001).max_decimals(12).suffix("i"));
+                        if re.changed() || im.changed() {
+                            state.needs_update = true;
+                        }
+                    });
+                    // Ushiki's original image: c = 0.5667, p = -0.5 in the Julia plane.
+                    if ui.button("Classic Phoenix").clicked() {
+                        state.phoenix_p = Complex64::new(-0.5, 0.0);
+                        state.julia_seed = Complex64::new(0.5667, 0.0);
+                        state.julia_plane = true;
+                        state.power = 2.0;
+                        // The view spans [x - 2.5s, x + s] with s = 2.5 / zoom, so this centers the origin.
+                        state.zoom = 1.5;
+                        state.center_x = 1.25;
+                        state.center_y = 0.0;
+                        state.needs_update = true;
+                    }
+                }
+                if state.has_julia_plane()
+                    && ui.checkbox(&mut state.julia_plane, "Julia plane (uses the seed below)").changed()
+                {
+                    state.needs_update = true;
+                }
+                if state.in_julia_plane() {
+                    ui.horizontal(|ui| {
+                        ui.label("Seed c:");
+                        let re = ui.add(egui::DragValue::new(&mut state.julia_seed.re).speed(0.001).max_decimals(12));
+                        let im = ui.add(egui::DragValue::new(&mut state.julia_seed.im).speed(0.001).max_decimals(12).suffix("i"));
+                        if re.changed() || im.changed() {
+                            state.needs_update = true;
+                        }
+                    });
+                    if let Some((fractal_type, zoom, center_x, center_y)) = self.parameter_view
+                        && ui.button("Back to Parameter Plane").clicked()
+                    {
+                        state.fractal_type = fractal_type;
+                        state.zoom = zoom;
+                        state.center_x = center_x;
+                        state.center_y = center_y;
+                        state.julia_plane = false;
+                        state.needs_update = true;
+                        self.parameter_view = None;
+                    }
+                } else {
+                    ui.checkbox(&mut self.seed_pick, "Pick Julia seed (or Ctrl+click)");
+                }
+
+                ui.add_space(10.0);
+            
+                ui.horizontal(|ui| {
+                    ui.label("Seed:");
+                    ui.add(egui::TextEdit::singleline(&mut self.seed_input)
+                        .hint_text("random")
+                        .desired_width(120.0));
+                });
+                let mut randomize = false;
+                ui.horizontal(|ui| {
+                    randomize = ui.button("🎲 Randomize").clicked();
+                    ui.checkbox(&mut self.lock_palette, "Lock palette");
+                });
+                if randomize {
+                    drop(state);  // Release the lock before calling randomize
+                    self.randomize_params();
+                    state = self.state.write();  // Reacquire the lock
+                }
+                if !self.seed_error.is_empty() {
+                    ui.colored_label(egui::Color32::from_rgb(230, 90, 80), &self.seed_error);
+                }
+                if let Some(seed) = self.last_seed {
+                    ui.label(format!("Last seed: {}", seed));
+                }
+
+                ui.add_space(5.0);
+            
+                let power_range = if state.has_complex_power() { -4.0..=8.0 } else { 2.0..=4.0 };
+                if ui.add(egui::Slider::new(&mut state.power, power_range)
+                    .step_by(0.1)
+                    .text("Power")).changed() {
+                    state.needs_update = true;
+                }
+                if state.has_complex_power() && ui.add(egui::Slider::new(&mut state.power_im, -4.0..=4.0)
+                    .step_by(0.05)
+                    .text("Power (imaginary)")).changed() {
+                    state.needs_update = true;
+                }
+            
+                if ui.add(egui::Slider::new(&mut state.secondary_param, 0.1..=0.9)
+                    .step_by(0.05)
+                    .text("Shape Parameter")).changed() {
+                    state.needs_update = true;
+                }
+                if ui.add(egui::Slider::new(&mut state.zoom, 0.1..=50.0)
+                    .step_by(0.1)
+                    .text("Zoom")).changed() {
+                    state.needs_update = true;
+                }
+                if ui.add(egui::Slider::new(&mut state.center_x, -2.0..=1.0)
+                    .step_by(0.01)
+                    .text("X Position")).changed() {
+                    state.needs_update = true;
+                }
+                if ui.add(egui::Slider::new(&mut state.center_y, -1.5..=1.5)
+                    .step_by(0.01)
+                    .text("Y Position")).changed() {
+                    state.needs_update = true;
+                }
+                if ui.add(egui::Slider::new(&mut state.max_iter, 100..=5000)
+                    .step_by(100.0)
+                    .text("Max Iterations")).changed() {
+                    state.needs_update = true;
+                }
+                if state.fractal_type.is_escape_time()
+                    && ui.checkbox(&mut state.biomorph, "Biomorph escape test").changed()
+                {
+                    state.needs_update = true;
+                }
+            
+                ui.separator();
+                ui.heading("Color Controls");
+                if coloring_combo(ui, "Coloring", &mut state.coloring) {
+                    state.needs_update = true;
+                }
+                if state.coloring == ColoringMode::Texture {
+                    ui.horizontal(|ui| {
+                        ui.add(egui::TextEdit::singleline(&mut self.texture_image.path)
+                            .hint_text("path/to/image.png")
+                            .desired_width(160.0));
+                        if ui.button("Load").clicked() {
+                            self.texture_status = match self.texture_image.load() {
+                                Ok(()) => String::new(),
+                                Err(err) => format!("Could not load image: {}", err),
+                            };
+                            self.recolor = true;
+                        }
+                    });
+                    if !self.texture_status.is_empty() {
+                        ui.label(&self.texture_status);
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label("Mapped by:");
+                        for source in TextureSource::ALL {
+                            if ui.radio_value(&mut state.texture.source, source, source.name()).changed() {
+                                state.needs_update = true;
+                            }
+                        }
+                    });
+                    if state.texture.source == TextureSource::Trap
+                        && ui.add(egui::Slider::new(&mut state.texture.size, 0.05..=4.0).logarithmic(true).text("Image size")).changed()
+                    {
+                        self.recolor = true;
+                    }
+                }
+                if state.coloring == ColoringMode::SineWaves && waves_editor(ui, &mut state.waves) {
+                    self.recolor = true;
+                }
+                if state.uses_trap() && trap_editor(ui, &mut state.trap) {
+                    state.needs_update = true;
+                }
+                if state.coloring == ColoringMode::StripeAverage
+                    && ui.add(egui::Slider::new(&mut state.stripe_density, 1.0..=20.0)
+                        // Whole numbers keep the stripes continuous across the branch cut of arg.
+                        .step_by(1.0)
+                        .text("Stripe density")).changed()
+                {
+                    state.needs_update = true;
+                }
+                if state.coloring == ColoringMode::BinaryDecomposition
+                    && ui.checkbox(&mut state.external_angle, "Shade by external angle").changed()
+                {
+                    self.recolor = true;
+                }
+                if state.coloring == ColoringMode::FinalAngle
+                    && ui.checkbox(&mut state.angle_by_escape, "Multiply by escape time").changed()
+                {
+                    self.recolor = true;
+                }
+                if state.fractal_type.has_interior()
+                    && ui.checkbox(&mut state.interior_period, "Color interior by period").changed()
+                {
+                    state.needs_update = true;
+                }
+                if state.coloring == ColoringMode::SlopeShading && state.tracks_derivative() {
+                    self.recolor |= lighting_editor(ui, &mut state.lighting);
+                }
+                if matches!(
+                    state.coloring,
+                    ColoringMode::DistanceEstimate | ColoringMode::SlopeShading | ColoringMode::DerivativeMagnitude
+                ) && !state.tracks_derivative()
+                {
+                    ui.label("Derivative-based coloring covers Classic and Julia; other types use escape time.");
+                }
+                egui::ComboBox::from_label("Color space")
+                    .selected_text(state.color_space.name())
+                    .show_ui(ui, |ui| {
+                        for space in ColorSpace::ALL {
+                            if ui.selectable_value(&mut state.color_space, space, space.name()).changed() {
+                                self.recolor = true;
+                            }
+                        }
+                    });
+                egui::ComboBox::from_label("Interpolation")
+                    .selected_text(state.interpolation.name())
+                    .show_ui(ui, |ui| {
+                        for interpolation in Interpolation::ALL {
+                            if ui.selectable_value(&mut state.interpolation, interpolation, interpolation.name()).changed() {
+                                self.recolor = true;
+                            }
+                        }
+                    });
+                let (space, interpolation) = (state.color_space, state.interpolation);
+                if gradient_editor(ui, &mut state.palette, space, interpolation, &mut self.palette_stop) {
+                    self.recolor = true;
+                }
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut self.palette_path)
+                        .hint_text("palette.map / .ugr / .ggr")
+                        .desired_width(160.0));
+                    if ui.button("Import").clicked() {
+                        self.palette_status = match palette::import(&self.palette_path) {
+                            Ok(palette) => {
+                                state.palette = palette;
+                                self.palette_stop = 0;
+                                self.recolor = true;
+                                String::new()
+                            }
+                            Err(err) => err,
+                        };
+                    }
+                    if ui.button("Export").clicked() {
+                        let name = std::path::Path::new(self.palette_path.trim())
+                            .file_stem()
+                            .and_then(|stem| stem.to_str())
+                            .unwrap_or("fractolrs")
+                            .to_owned();
+                        self.palette_status = palette::export(&self.palette_path, &name, &state.palette)
+                            .err()
+                            .unwrap_or_default();
+                    }
+                });
+                egui::CollapsingHeader::new("Saved Palettes").show(ui, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.add(egui::TextEdit::singleline(&mut self.palette_name)
+                            .hint_text("name")
+                            .desired_width(120.0));
+                        if ui.button("Save Palette").clicked() {
+                            let name = if self.palette_name.trim().is_empty() {
+                                format!("Palette {}", self.saved_palettes.len() + 1)
+                            } else {
+                                self.palette_name.trim().to_string()
+                            };
+                            self.palette_status = match palette::save_to_library(&name, &state.palette) {
+                                Ok(()) => {
+                                    self.saved_palettes = palette::load_library();
+                                    self.palette_name.clear();
+                                    String::new()
+                                }
+                                Err(err) => err,
+                            };
+                        }
+                    });
+                    let mut apply = None;
+                    let mut remove = None;
+                    for (i, (name, saved)) in self.saved_palettes.iter().enumerate() {
+                        ui.horizontal(|ui| {
+                            if ui.button("Use").clicked() {
+                                apply = Some(i);
+                            }
+                            if ui.small_button("✖").clicked() {
+                                remove = Some(i);
+                            }
+                            let (swatch, _) = ui.allocate_exact_size(Vec2::new(60.0, 14.0), egui::Sense::hover());
+                            paint_gradient(ui, swatch, saved, state.color_space, state.interpolation);
+                            ui.label(name);
+                        });
+                    }
+                    if let Some(i) = apply {
+                        state.palette = self.saved_palettes[i].1.clone();
+                        self.palette_stop = 0;
+                        self.recolor = true;
+                    }
+                    if let Some(i) = remove {
+                        let (name, _) = self.saved_palettes.remove(i);
+                        palette::delete_from_library(&name);
+                    }
+                });
+                if !self.palette_status.is_empty() {
+                    ui.colored_label(egui::Color32::from_rgb(230, 90, 80), &self.palette_status);
+                }
+                if ui.add(egui::Slider::new(&mut state.hue_offset, 0.0..=360.0).text("Hue Offset")).changed() {
+                    self.recolor = true;
+                }
+                egui::ComboBox::from_label("Transfer")
+                    .selected_text(state.transfer.curve.name())
+                    .show_ui(ui, |ui| {
+                        for curve in TransferCurve::ALL {
+                            if ui.selectable_value(&mut state.transfer.curve, curve, curve.name()).changed() {
+                                self.recolor = true;
+                            }
+                        }
+                    });
+                if state.transfer.curve == TransferCurve::Power
+                    && ui.add(egui::Slider::new(&mut state.transfer.exponent, 0.05..=4.0).logarithmic(true).text("Exponent")).changed()
+                {
+                    self.recolor = true;
+                }
+                ui.horizontal(|ui| {
+                    if ui.checkbox(&mut self.cycle.enabled, "Cycle colors").changed() && !self.cycle.enabled {
+                        // Keep the colors where the cycle left them.
+                        state.hue_offset = (state.hue_offset + self.cycle.stop()) % 360.0;
+                        self.recolor = true;
+                    }
+                    ui.add(egui::Slider::new(&mut self.cycle.speed, -180.0..=180.0).text("°/s"));
+                });
+                if self.cycle.enabled && !state.fractal_type.colors_orbits() {
+                    ui.label("Color cycling needs a type colored per pixel orbit.");
+                }
+                if ui.add(egui::Slider::new(&mut state.saturation, 0.0..=1.0).text("Saturation")).changed() {
+                    self.recolor = true;
+                }
+                if ui.add(egui::Slider::new(&mut state.value, 0.0..=1.0).text("Value")).changed() {
+                    self.recolor = true;
+                }
+            
+                if state.fractal_type == FractalType::Newton {
+                    ui.separator();
+                    ui.heading("Polynomial");
+                    let previous_method = state.root_method;
+                    egui::ComboBox::from_label("Method")
+                        .selected_text(state.root_method.name())
+                        .show_ui(ui, |ui| {
+                            for method in RootMethod::ALL {
+                                ui.selectable_value(&mut state.root_method, method, method.name());
+                            }
+                        });
+                    if state.root_method != previous_method {
+                        state.needs_update = true;
+                    }
+                    if polynomial_editor(ui, &mut state.polynomial) {
+                        state.needs_update = true;
+                    }
+                    if ui.add(egui::Slider::new(&mut state.convergence_shading, 0.0..=2.0).text("Convergence shading")).changed() {
+                        state.needs_update = true;
+                    }
+                }
+
+                if state.fractal_type == FractalType::Cubic {
+                    ui.separator();
+                    ui.heading("z³ + a·z + b");
+                    let cubic = &mut state.cubic;
+                    let mut changed = false;
+                    ui.horizontal(|ui| {
+                        ui.label("Screen scans:");
+                        changed |= ui.radio_value(&mut cubic.scan, CubicParameter::B, "b").clicked();
+                        changed |= ui.radio_value(&mut cubic.scan, CubicParameter::A, "a").clicked();
+                    });
+                    ui.horizontal(|ui| {
+                        ui.label(if cubic.scan == CubicParameter::B { "Fixed a:" } else { "Fixed b:" });
+                        changed |= ui.add(egui::DragValue::new(&mut cubic.fixed.re).speed(0.005).max_decimals(6)).changed();
+                        changed |= ui.add(egui::DragValue::new(&mut cubic.fixed.im).speed(0.005).max_decimals(6).suffix("i")).changed();
+                    });
+                    if changed {
+                        state.needs_update = true;
+                    }
+                }
+
+                if state.fractal_type == FractalType::Mandelbulb {
+                    ui.separator();
+                    ui.heading("Mandelbulb");
+                    let bulb = &mut self.bulb;
+                    let mut changed = ui.add(egui::Slider::new(&mut bulb.power, 2.0..=16.0).step_by(0.1).text("Power")).changed();
+                    changed |= ui.add(egui::Slider::new(&mut bulb.iterations, 2..=40).text("Iterations")).changed();
+                    if scene_editor(ui, &mut self.scene) || changed {
+                        state.needs_update = true;
+                    }
+                }
+
+                if state.fractal_type == FractalType::QuaternionJulia {
+                    ui.separator();
+                    ui.heading("Quaternion Julia");
+                    let julia = &mut self.quaternion;
+                    let mut changed = false;
+                    for (value, name) in [
+                        (&mut julia.c.r, "c (real)"),
+                        (&mut julia.c.i, "c (i)"),
+                        (&mut julia.c.j, "c (j)"),
+                        (&mut julia.c.k, "c (k)"),
+                    ] {
+                        changed |= ui.add(egui::Slider::new(value, -1.5..=1.5).text(name)).changed();
+                    }
+                    changed |= ui.add(egui::Slider::new(&mut julia.slice, -1.0..=1.0).text("k slice")).changed();
+                    changed |= ui.add(egui::Slider::new(&mut julia.iterations, 2..=40).text("Iterations")).changed();
+                    if scene_editor(ui, &mut self.scene) || changed {
+                        state.needs_update = true;
+                    }
+                }
+
+                if state.fractal_type == FractalType::Ifs {
+                    ui.separator();
+                    ui.heading("IFS");
+                    let settings = &mut self.ifs;
+                    let mut preset = None;
+                    egui::ComboBox::from_label("Preset")
+                        .selected_text(settings.preset.name())
+                        .show_ui(ui, |ui| {
+                            for option in IfsPreset::ALL {
+                                if ui.selectable_label(settings.preset == option, option.name()).clicked() {
+                                    preset = Some(option);
+                                }
+                            }
+                        });
+                    if let Some(option) = preset {
+                        settings.preset = option;
+                        settings.transforms = option.transforms();
+                        (state.zoom, state.center_x, state.center_y) = option.view();
+                        self.navigator.stop();
+                        state.needs_update = true;
+                    }
+                    let mut changed = ui.add(egui::Slider::new(&mut settings.density, 1.0..=200.0)
+                        .logarithmic(true)
+                        .text("Points per pixel")).changed();
+                    changed |= ifs_table(ui, &mut settings.transforms);
+                    if changed {
+                        state.needs_update = true;
+                    }
+                }
+
+                if state.fractal_type == FractalType::LSystem {
+                    ui.separator();
+                    ui.heading("L-System");
+                    let system = &mut self.lsystem;
+                    let mut preset = None;
+                    egui::ComboBox::from_label("Preset")
+                        .selected_text(system.preset.name())
+                        .show_ui(ui, |ui| {
+                            for option in LSystemPreset::ALL {
+                                if ui.selectable_label(system.preset == option, option.name()).clicked() {
+                                    preset = Some(option);
+                                }
+                            }
+                        });
+                    let mut changed = false;
+                    if let Some(option) = preset {
+                        system.load(option);
+                        changed = true;
+                    }
+                    ui.horizontal(|ui| {
+                        ui.label("Axiom");
+                        changed |= ui.text_edit_singleline(&mut system.axiom).changed();
+                    });
+                    ui.label("Rules (one X=... per line)");
+                    changed |= ui.add(egui::TextEdit::multiline(&mut system.rules)
+                        .code_editor()
+                        .desired_rows(3)).changed();
+                    changed |= ui.add(egui::Slider::new(&mut system.angle, 1.0..=180.0).text("Angle (°)")).changed();
+                    changed |= ui.add(egui::Slider::new(&mut system.iterations, 0..=16).text("Iterations")).changed();
+                    match &system.error {
+                        Some(error) => { ui.colored_label(egui::Color32::from_rgb(230, 90, 80), error); }
+                        None => { ui.label(format!("{} segments", system.segments.len())); }
+                    }
+                    if changed {
+                        system.rebuild();
+                        state.needs_update = true;
+                    }
+                }
+
+                if state.fractal_type == FractalType::Attractor {
+                    ui.separator();
+                    ui.heading("Strange Attractor");
+                    let settings = &mut self.attractor;
+                    let previous_kind = settings.attractor.kind;
+                    egui::ComboBox::from_label("Map")
+                        .selected_text(settings.attractor.kind.name())
+                        .show_ui(ui, |ui| {
+                            for kind in AttractorKind::ALL {
+                                ui.selectable_value(&mut settings.attractor.kind, kind, kind.name());
+                            }
+                        });
+                    let mut reframe = settings.attractor.kind != previous_kind;
+                    let mut changed = false;
+                    for (value, label) in [
+                        (&mut settings.attractor.a, "a"),
+                        (&mut settings.attractor.b, "b"),
+                        (&mut settings.attractor.c, "c"),
+                        (&mut settings.attractor.d, "d"),
+                    ] {
+                        changed |= ui.add(egui::Slider::new(value, -3.0..=3.0).text(label)).changed();
+                    }
+                    changed |= ui.add(egui::Slider::new(&mut settings.density, 1.0..=200.0)
+                        .logarithmic(true)
+                        .text("Points per pixel")).changed();
+                    if ui.button("Randomize").on_hover_text("Search for chaotic parameters").clicked() {
+                        settings.search_seed = settings.search_seed.wrapping_add(1);
+                        settings.search_failed = !settings.attractor.randomize(settings.search_seed);
+                        reframe = true;
+                    }
+                    if settings.search_failed {
+                        ui.label("No chaotic parameters found; try again.");
+                    }
+                    if reframe {
+                        (state.zoom, state.center_x, state.center_y) = settings.attractor.view();
+                        self.navigator.stop();
+                    }
+                    if changed || reframe {
+                        state.needs_update = true;
+                    }
+                }
+
+                if state.fractal_type == FractalType::Kleinian {
+                    ui.separator();
+                    ui.heading("Kleinian Group");
+                    let group = &mut self.kleinian;
+                    let mut changed = ui.add(egui::Slider::new(&mut group.a, 1.0..=2.0).text("μ imaginary (a)")).changed();
+                    changed |= ui.add(egui::Slider::new(&mut group.b, -1.0..=1.0).text("μ real (-b)")).changed();
+                    changed |= ui.add(egui::Slider::new(&mut group.iterations, 10..=500).text("Iterations")).changed();
+                    ui.horizontal(|ui| {
+                        if ui.button("Leys").clicked() {
+                            *group = Kleinian::default();
+                            changed = true;
+                        }
+                        // At μ = 2i the limit set is the Apollonian gasket.
+                        if ui.button("Apollonian").clicked() {
+                            group.a = 2.0;
+                            group.b = 0.0;
+                            changed = true;
+                        }
+                    });
+                    if changed {
+                        state.needs_update = true;
+                    }
+                }
+
+                if state.fractal_type == FractalType::Apollonian {
+                    ui.separator();
+                    ui.heading("Apollonian Gasket");
+                    let gasket = &mut self.apollonian;
+                    let mut changed = false;
+                    for (i, curvature) in gasket.curvatures.iter_mut().enumerate() {
+                        changed |= ui.add(egui::Slider::new(curvature, 0.1..=10.0)
+                            .logarithmic(true)
+                            .text(format!("Curvature {}", i + 1))).changed();
+                    }
+                    changed |= ui.add(egui::Slider::new(&mut gasket.iterations, 10..=500).text("Iterations")).changed();
+                    if !gasket.is_bounded() {
+                        ui.label("No circle encloses these three; make the smallest one larger.");
+                    }
+                    if changed {
+                        state.needs_update = true;
+                    }
+                }
+
+                if state.fractal_type == FractalType::OrbitPlot {
+                    ui.separator();
+                    ui.heading("Orbit Plot");
+                    let plot = &mut self.orbit_plot;
+                    let previous_map = plot.map;
+                    egui::ComboBox::from_label("Map")
+                        .selected_text(plot.map.name())
+                        .show_ui(ui, |ui| {
+                            for map in OrbitMap::ALL {
+                                ui.selectable_value(&mut plot.map, map, map.name());
+                            }
+                        });
+                    let mut reframe = plot.map != previous_map;
+                    if reframe {
+                        plot.set_map(plot.map);
+                    }
+                    let mut changed = false;
+                    let count = plot.map.parameter_count();
+                    for (value, label) in plot.params.iter_mut().zip(["a", "b", "c"]).take(count) {
+                        ui.horizontal(|ui| {
+                            ui.label(label);
+                            changed |= ui.add(egui::DragValue::new(value).speed(0.05).max_decimals(4)).changed();
+                        });
+                    }
+                    changed |= ui.add(egui::Slider::new(&mut plot.points, 100_000..=20_000_000)
+                        .logarithmic(true)
+                        .text("Points")).changed();
+                    changed |= ui.add(egui::Slider::new(&mut plot.exposure, 0.1..=10.0)
+                        .logarithmic(true)
+                        .text("Exposure")).changed();
+                    reframe |= ui.button("Fit to Orbit").clicked();
+                    if reframe {
+                        (state.zoom, state.center_x, state.center_y) = plot.view();
+                        self.navigator.stop();
+                    }
+                    if changed || reframe {
+                        state.needs_update = true;
+                    }
+                }
+
+                if state.fractal_type == FractalType::Buddhabrot {
+                    ui.separator();
+                    ui.heading("Buddhabrot");
+                    let settings = &mut self.buddhabrot;
+                    let mut changed = ui.add(egui::Slider::new(&mut settings.density, 0.5..=50.0)
+                        .logarithmic(true)
+                        .text("Samples per pixel")).changed();
+                    changed |= ui.add(egui::Slider::new(&mut settings.exposure, 0.1..=4.0).text("Exposure")).changed();
+                    changed |= ui.add(egui::Slider::new(&mut settings.gamma, 1.0..=4.0).text("Gamma")).changed();
+                    changed |= ui.checkbox(&mut settings.nebula, "Nebulabrot (RGB channels)").changed();
+                    if settings.nebula {
+                        for (limit, name) in settings.channel_iterations.iter_mut().zip(["Red", "Green", "Blue"]) {
+                            changed |= ui.add(egui::Slider::new(limit, 10..=20000)
+                                .logarithmic(true)
+                                .text(format!("{} iterations", name))).changed();
+                        }
+                    }
+                    if changed {
+                        state.needs_update = true;
+                    }
+                }
+
+                ui.separator();
+                ui.heading("Layers");
+                if ui.button("Add Layer").clicked() {
+                    let name = format!("Layer {}", self.layers.len() + 1);
+                    self.layers.push(Layer::new(name, (*state).clone()));
+                    state.needs_update = true;
+                }
+                let mut layer_action = None;
+                let layer_names: Vec<String> = self.layers.iter().map(|l| l.name.clone()).collect();
+                for (i, layer) in self.layers.iter_mut().enumerate() {
+                    ui.push_id(i, |ui| {
+                        let mut changed = false;
+                        ui.horizontal(|ui| {
+                            changed |= ui.checkbox(&mut layer.visible, "").changed();
+                            ui.label(&layer.name);
+                            if ui.small_button("▲").clicked() {
+                                layer_action = Some((i, LayerAction::MoveUp));
+                            }
+                            if ui.small_button("▼").clicked() {
+                                layer_action = Some((i, LayerAction::MoveDown));
+                            }
+                            if ui.small_button("✖").clicked() {
+                                layer_action = Some((i, LayerAction::Remove));
+                            }
+                        });
+                        egui::CollapsingHeader::new("Settings").show(ui, |ui| {
+                            changed |= layer_settings(ui, layer, i, &layer_names);
+                        });
+                        if changed {
+                            state.needs_update = true;
+                        }
+                    });
+                }
+                if let Some((i, action)) = layer_action {
+                    layers::apply_action(&mut self.layers, i, action);
+                    state.needs_update = true;
+                }
+
+                ui.separator();
+                ui.heading("Quality");
+                let quality = &mut self.quality;
+                let before = *quality;
+                egui::ComboBox::from_label("Preset")
+                    .selected_text(quality.preset().map_or("Custom", |p| p.name()))
+                    .show_ui(ui, |ui| {
+                        for preset in QualityPreset::ALL {
+                            if ui.selectable_label(quality.preset() == Some(preset), preset.name()).clicked() {
+                                *quality = preset.settings();
+                            }
+                        }
+                    });
+                egui::CollapsingHeader::new("Advanced").show(ui, |ui| {
+                    ui.add(egui::Slider::new(&mut quality.preview_scale, 0.25..=1.0).text("Preview Scale"));
+                    ui.add(egui::Slider::new(&mut quality.iteration_multiplier, 0.25..=4.0).text("Iteration Multiplier"));
+                    ui.horizontal(|ui| {
+                        ui.label("Precision:");
+                        for precision in Precision::ALL {
+                            ui.radio_value(&mut quality.precision, precision, precision.name());
+                        }
+                    });
+
+                    let aa = &mut quality.antialias;
+                    egui::ComboBox::from_label("Samples per pixel")
+                        .selected_text(aa.samples.to_string())
+                        .show_ui(ui, |ui| {
+                            for count in SAMPLE_COUNTS {
+                                ui.selectable_value(&mut aa.samples, count, count.to_string());
+                            }
+                        });
+                    if aa.samples > 1 {
+                        egui::ComboBox::from_label("Pattern")
+                            .selected_text(aa.pattern.name())
+                            .show_ui(ui, |ui| {
+                                for pattern in SamplePattern::ALL {
+                                    ui.selectable_value(&mut aa.pattern, pattern, pattern.name());
+                                }
+                            });
+                        ui.checkbox(&mut aa.adaptive, "Adaptive (refine only where needed)");
+                        if aa.adaptive {
+                            egui::ComboBox::from_label("Refine by")
+                                .selected_text(aa.refinement.name())
+                                .show_ui(ui, |ui| {
+                                    for refinement in Refinement::ALL {
+                                        ui.selectable_value(&mut aa.refinement, refinement, refinement.name());
+                                    }
+                                });
+                            match aa.refinement {
+                                Refinement::Variance if aa.samples <= ADAPTIVE_INITIAL_SAMPLES => {
+                                    ui.label(format!("Variance refinement needs more than {} samples.", ADAPTIVE_INITIAL_SAMPLES));
+                                }
+                                Refinement::Variance => {
+                                    ui.add(egui::Slider::new(&mut aa.variance_threshold, 0.0001..=0.05)
+                                        .logarithmic(true)
+                                        .text("Variance Threshold"));
+                                }
+                                Refinement::EscapeEdges => {
+                                    ui.add(egui::Slider::new(&mut aa.iteration_threshold, 1..=64)
+                                        .logarithmic(true)
+                                        .text("Iteration Difference"));
+                                }
+                            }
+                        }
+                    }
+                });
+                if *quality != before {
+                    state.needs_update = true;
+                }
+
+                ui.separator();
+                ui.heading("Performance");
+                let backends = &mut self.backends;
+                ui.label(format!("SIMD: {}", backends.simd.map_or("not detected", |level| level.name())));
+                ui.label(format!("GPU: {}", backends.adapter_name.as_deref().unwrap_or("no adapter found")));
+                let mut choice = backends.selected;
+                ui.horizontal(|ui| {
+                    for backend in Backend::ALL {
+                        ui.add_enabled_ui(backends.is_available(backend), |ui| {
+                            ui.radio_value(&mut choice, backend, backend.name());
+                        });
+                    }
+                });
+                if choice != backends.selected {
+                    backends.switch(choice);
+                    state.needs_update = true;
+                }
+                ui.label(format!("Active: {}", backends.active.name()));
+                if let Some(notice) = &backends.notice {
+                    ui.colored_label(egui::Color32::from_rgb(230, 160, 60), notice);
+                }
+                if backends.active != Backend::Scalar && state.escape_params().is_none() {
+                    ui.label("This view uses the scalar path; SIMD and GPU cover Classic, Julia, Burning Ship and Tricorn at integer powers with escape-time style coloring.");
+                }
+                if let Some(gpu) = &backends.gpu
+                    && let Some(failure) = gpu.failure.lock().as_ref()
+                {
+                    ui.colored_label(egui::Color32::from_rgb(230, 160, 60), format!("GPU render failed ({}); using the CPU", failure));
+                }
+
+                ui.separator();
+                ui.heading("Background");
+                let background = &mut self.background;
+                let mut background_changed = false;
+                egui::ComboBox::from_label("Underlay")
+                    .selected_text(background.kind.name())
+                    .show_ui(ui, |ui| {
+                        for kind in BackgroundKind::ALL {
+                            background_changed |= ui.selectable_value(&mut background.kind, kind, kind.name()).changed();
+                        }
+                    });
+                if background.kind != BackgroundKind::None {
+                    ui.horizontal(|ui| {
+                        ui.label("Show through:");
+                        background_changed |= ui.radio_value(&mut background.region, TransparentRegion::Interior, "Interior").changed();
+                        background_changed |= ui.radio_value(&mut background.region, TransparentRegion::Exterior, "Exterior").changed();
+                    });
+                }
+                match background.kind {
+                    BackgroundKind::Solid => {
+                        background_changed |= ui.color_edit_button_rgb(&mut background.top).changed();
+                    }
+                    BackgroundKind::Gradient => {
+                        ui.horizontal(|ui| {
+                            ui.label("Top");
+                            background_changed |= ui.color_edit_button_rgb(&mut background.top).changed();
+                            ui.label("Bottom");
+                            background_changed |= ui.color_edit_button_rgb(&mut background.bottom).changed();
+                        });
+                    }
+                    BackgroundKind::Image => {
+                        ui.horizontal(|ui| {
+                            ui.add(egui::TextEdit::singleline(&mut background.path)
+                                .hint_text("path/to/image.png")
+                                .desired_width(160.0));
+                            if ui.button("Load").clicked() {
+                                self.background_status = match background.load_image() {
+                                    Ok(()) => String::new(),
+                                    Err(err) => format!("Could not load image: {}", err),
+                                };
+                                background_changed = true;
+                            }
+                        });
+                        if !self.background_status.is_empty() {
+                            ui.label(&self.background_status);
+                        }
+                    }
+                    BackgroundKind::None => {}
+                }
+                if background_changed {
+                    state.needs_update = true;
+                }
+
+                ui.separator();
+                ui.heading("Post-Processing");
+                let post = &mut self.post;
+                let mut regrade = false;
+                regrade |= ui.add(egui::Slider::new(&mut post.exposure, -3.0..=3.0).text("Exposure")).changed();
+                regrade |= ui.add(egui::Slider::new(&mut post.temperature, -1.0..=1.0).text("Temperature")).changed();
+                regrade |= ui.add(egui::Slider::new(&mut post.tint, -1.0..=1.0).text("Tint")).changed();
+                regrade |= ui.add(egui::Slider::new(&mut post.saturation, 0.0..=2.0).text("Saturation")).changed();
+                regrade |= ui.add(egui::Slider::new(&mut post.vibrance, -1.0..=1.0).text("Vibrance")).changed();
+                regrade |= ui.add(egui::Slider::new(&mut post.contrast, 0.5..=2.0).text("Contrast")).changed();
+                regrade |= ui.add(egui::Slider::new(&mut post.brightness, -0.5..=0.5).text("Brightness")).changed();
+                regrade |= ui.add(egui::Slider::new(&mut post.gamma, 0.2..=5.0).logarithmic(true).text("Gamma")).changed();
+                regrade |= ui.add(egui::Slider::new(&mut post.vignette, 0.0..=1.0).text("Vignette")).changed();
+                regrade |= ui.add(egui::Slider::new(&mut post.bloom_intensity, 0.0..=2.0).text("Bloom Intensity")).changed();
+                regrade |= ui.add(egui::Slider::new(&mut post.bloom_threshold, 0.0..=2.0).text("Bloom Threshold")).changed();
+                regrade |= ui.add(egui::Slider::new(&mut post.bloom_radius, 0.1..=5.0)
+                    .suffix("%")
+                    .text("Bloom Radius")).changed();
+                regrade |= ui.add(egui::Slider::new(&mut post.sharpen_amount, 0.0..=2.0).text("Sharpen")).changed();
+                regrade |= ui.add(egui::Slider::new(&mut post.denoise, 0.0..=1.0).text("Denoise")).changed();
+                if ui.button("Reset Grading").clicked() {
+                    *post = PostSettings { dither: post.dither, ..PostSettings::default() };
+                    regrade = true;
+                }
+                egui::ComboBox::from_label("Dither")
+                    .selected_text(post.dither.name())
+                    .show_ui(ui, |ui| {
+                        for dither in Dither::ALL {
+                            regrade |= ui.selectable_value(&mut post.dither, dither, dither.name()).changed();
+                        }
+                    });
+                self.regrade |= regrade;
+
+                egui::ComboBox::from_label("Export Profile")
+                    .selected_text(self.export_profile.name())
+                    .show_ui(ui, |ui| {
+                        for profile in ColorProfile::ALL {
+                            ui.selectable_value(&mut self.export_profile, profile, profile.name());
+                        }
+                    });
+                let transparency_name = |region: Option<TransparentRegion>| match region {
+                    None => "None",
+                    Some(TransparentRegion::Interior) => "Interior",
+                    Some(TransparentRegion::Exterior) => "Exterior",
+                };
+                egui::ComboBox::from_label("Transparency")
+                    .selected_text(transparency_name(self.transparent_export))
+                    .show_ui(ui, |ui| {
+                        for region in [None, Some(TransparentRegion::Interior), Some(TransparentRegion::Exterior)] {
+                            ui.selectable_value(&mut self.transparent_export, region, transparency_name(region));
+                        }
+                    });
+                if self.transparent_export.is_some() && self.background.kind != BackgroundKind::None {
+                    ui.label("The background underlay fills the transparent region.");
+                }
+                if ui.add_enabled(self.export_render.is_none(), egui::Button::new("Save Image")).clicked() {
+                    drop(state);
+                    self.start_export();
+                    state = self.state.write();
+                }
+                if !self.export_status.is_empty() {
+                    ui.label(&self.export_status);
+                }
+            
+                ui.separator();
+                ui.heading("Wallpaper");
+                if ui.button("Detect Monitors").clicked() {
+                    self.monitors = wallpaper::detect_monitors();
+                    self.wallpaper_status = format!("Found {} monitors", self.monitors.len());
+                }
+                for (i, monitor) in self.monitors.iter_mut().enumerate() {
+                    ui.horizontal(|ui| {
+                        ui.label(if monitor.primary { format!("#{} ★", i + 1) } else { format!("#{}", i + 1) });
+                        ui.add(egui::DragValue::new(&mut monitor.x).prefix("x "));
+                        ui.add(egui::DragValue::new(&mut monitor.y).prefix("y "));
+                        ui.add(egui::DragValue::new(&mut monitor.width).clamp_range(1..=16384).prefix("w "));
+                        ui.add(egui::DragValue::new(&mut monitor.height).clamp_range(1..=16384).prefix("h "));
+                    });
+                }
+                if ui.add_enabled(!self.monitors.is_empty(), egui::Button::new("Render Spanning Wallpaper")).clicked() {
+                    drop(state);
+                    self.wallpaper_status = match self.render_wallpaper() {
+                        Ok(count) => format!("Saved {} wallpaper images", count),
+                        Err(err) => format!("Wallpaper export failed: {}", err),
+                    };
+                    state = self.state.write();
+                }
+                if !self.wallpaper_status.is_empty() {
+                    ui.label(&self.wallpaper_status);
+                }
+
+                ui.separator();
+                ui.heading("Bookmarks");
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut self.bookmark_name)
+                        .hint_text("name")
+                        .desired_width(120.0));
+                    if ui.button("Add Bookmark").clicked() {
+                        let name = if self.bookmark_name.trim().is_empty() {
+                            format!("Bookmark {}", self.bookmarks.len() + 1)
+                        } else {
+                            self.bookmark_name.trim().to_string()
+                        };
+                        self.bookmarks.push(Bookmark { name, state: (*state).clone() });
+                        self.bookmark_name.clear();
+                    }
+                });
+                let mut go_to = None;
+                let mut remove = None;
+                for (i, bookmark) in self.bookmarks.iter().enumerate() {
+                    ui.horizontal(|ui| {
+                        if ui.button("Go").clicked() {
+                            go_to = Some(i);
+                        }
+                        if ui.small_button("✖").clicked() {
+                            remove = Some(i);
+                        }
+                        ui.label(&bookmark.name);
+                    });
+                }
+                if let Some(i) = go_to {
+                    drop(state);
+                    self.apply_state(self.bookmarks[i].state.clone());
+                    state = self.state.write();
+                }
+                if let Some(i) = remove {
+                    self.bookmarks.remove(i);
+                }
+
+                if self.bookmarks.len() >= 2 {
+                    ui.label("Animate between…");
+                    let names: Vec<String> = self.bookmarks.iter().map(|b| b.name.clone()).collect();
+                    self.morph_from = self.morph_from.min(names.len() - 1);
+                    self.morph_to = self.morph_to.min(names.len() - 1);
+                    egui::ComboBox::from_label("From")
+                        .selected_text(names[self.morph_from].as_str())
+                        .show_ui(ui, |ui| {
+                            for (i, name) in names.iter().enumerate() {
+                                ui.selectable_value(&mut self.morph_from, i, name);
+                            }
+                        });
+                    egui::ComboBox::from_label("To")
+                        .selected_text(names[self.morph_to].as_str())
+                        .show_ui(ui, |ui| {
+                            for (i, name) in names.iter().enumerate() {
+                                ui.selectable_value(&mut self.morph_to, i, name);
+                            }
+                        });
+                    ui.add(egui::Slider::new(&mut self.morph_duration, 1.0..=30.0).text("Seconds"));
+                    let (preview, export) = ui.horizontal(|ui| {
+                        (ui.button("▶ Preview").clicked(), ui.button("Export Frames").clicked())
+                    }).inner;
+                    if preview || export {
+                        let morph = Morph::new(
+                            self.bookmarks[self.morph_from].state.clone(),
+                            self.bookmarks[self.morph_to].state.clone(),
+                            self.morph_duration,
+                        );
+                        if export {
+                            drop(state);
+                            self.morph_status = match self.export_morph(&morph) {
+                                Ok(frames) => format!("Saved {} frames", frames),
+                                Err(err) => format!("Export failed: {}", err),
+                            };
+                            state = self.state.write();
+                        } else {
+                            self.morph = Some(morph);
+                        }
+                    }
+                    if !self.morph_status.is_empty() {
+                        ui.label(&self.morph_status);
+                    }
+                }
+
+                ui.separator();
+                ui.heading("Gallery");
+                ui.add(egui::TextEdit::singleline(&mut self.gallery_endpoint)
+                    .hint_text("https://gallery.example.com/api"));
+                let ready = !self.gallery_endpoint.trim().is_empty() && !self.gallery.is_busy();
+                let (share, browse) = ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut self.gallery_name)
+                        .hint_text("title")
+                        .desired_width(120.0));
+                    (
+                        ui.add_enabled(ready, egui::Button::new("Share")).clicked(),
+                        ui.add_enabled(ready, egui::Button::new("Browse")).clicked(),
+                    )
+                }).inner;
+                if share {
+                    let name = if self.gallery_name.trim().is_empty() {
+                        format!("{} at {:.3e}x", state.fractal_type.name(), state.zoom)
+                    } else {
+                        self.gallery_name.trim().to_string()
+                    };
+                    let thumbnail = self.share_thumbnail(&state);
+                    self.gallery.share(self.gallery_endpoint.clone(), name, (*state).clone(), thumbnail);
+                    self.gallery_status = "Uploading…".to_string();
+                    self.gallery_link = None;
+                }
+                if browse {
+                    self.gallery.browse(self.gallery_endpoint.clone(), (*state).clone());
+                    self.gallery_status = "Loading…".to_string();
+                }
+                if !self.gallery_status.is_empty() {
+                    ui.label(&self.gallery_status);
+                }
+                if let Some(link) = &self.gallery_link {
+                    ui.hyperlink(link);
+                }
+
+                ui.separator();
+                ui.heading("Animation");
+                ui.horizontal(|ui| {
+                    if self.recorder.is_recording() {
+                        if ui.button("⏹ Stop Recording").clicked() {
+                            self.recorder.stop();
+                        }
+                    } else if ui.button("⏺ Record Path").clicked() {
+                        self.recorder.start();
+                    }
+                    if ui.add_enabled(self.recorder.sample_count() > 1, egui::Button::new("▶ Replay")).clicked() {
+                        self.playback = Some(Playback::new(self.recorder.to_timeline()));
+                    }
+                });
+                ui.label(format!("Recorded {} steps over {:.1}s",
+                    self.recorder.sample_count(), self.recorder.duration()));
+                if ui.add_enabled(self.recorder.sample_count() > 1, egui::Button::new("Export to Timeline")).clicked() {
+                    self.timeline = self.recorder.to_keyframes(0.5);
+                }
+
+                ui.horizontal(|ui| {
+                    if ui.button("Add Keyframe").clicked() {
+                        let time = if self.timeline.keyframes.is_empty() {
+                            0.0
+                        } else {
+                            self.timeline.duration() + 2.0
+                        };
+                        self.timeline.push(time, (*state).clone());
+                    }
+                    if ui.add_enabled(self.timeline.keyframes.len() > 1, egui::Button::new("▶ Play Timeline")).clicked() {
+                        self.playback = Some(Playback::new(self.timeline.clone()));
+                    }
+                    if ui.button("Clear").clicked() {
+                        self.timeline.keyframes.clear();
+                    }
+                });
+                ui.label(format!("{} keyframes, {:.1}s",
+                    self.timeline.keyframes.len(), self.timeline.duration()));
+                egui::CollapsingHeader::new("Julia Tour").show(ui, |ui| {
+                    let tour = &mut self.julia_tour;
+                    egui::ComboBox::from_label("Path")
+                        .selected_text(tour.path.name())
+                        .show_ui(ui, |ui| {
+                            for path in TourPath::ALL {
+                                ui.selectable_value(&mut tour.path, path, path.name());
+                            }
+                        });
+                    ui.add(egui::Slider::new(&mut tour.offset, 0.9..=1.2).text("Boundary Offset"));
+                    ui.add(egui::Slider::new(&mut tour.seconds, 5.0..=120.0).text("Seconds per Loop"));
+                });
+                let (play, to_timeline, export) = ui.horizontal(|ui| {
+                    (
+                        ui.button("▶ Play Tour").clicked(),
+                        ui.button("Tour to Timeline").clicked(),
+                        ui.button("Export Tour").clicked(),
+                    )
+                }).inner;
+                if play || to_timeline || export {
+                    let tour_timeline = self.julia_tour.to_timeline(&state);
+                    if play {
+                        self.playback = Some(Playback::new(tour_timeline.clone()));
+                    }
+                    if to_timeline {
+                        self.timeline = tour_timeline.clone();
+                    }
+                    if export {
+                        drop(state);
+                        self.tour_status = match self.export_timeline(&tour_timeline, "julia_tour") {
+                            Ok(frames) => format!("Saved {} frames", frames),
+                            Err(err) => format!("Export failed: {}", err),
+                        };
+                        state = self.state.write();
+                    }
+                }
+                if !self.tour_status.is_empty() {
+                    ui.label(&self.tour_status);
+                }
+
+                egui::CollapsingHeader::new("Keyframes").show(ui, |ui| {
+                    let mut resort = false;
+                    for (i, key) in self.timeline.keyframes.iter_mut().enumerate() {
+                        ui.push_id(i, |ui| {
+                            ui.horizontal(|ui| {
+                                ui.label(format!("#{}", i + 1));
+                                resort |= ui.add(egui::DragValue::new(&mut key.time)
+                                    .speed(0.05)
+                                    .clamp_range(0.0..=3600.0)
+                                    .suffix(" s")).changed();
+                            });
+                            easing_editor(ui, "Camera", &mut key.camera_easing);
+                            easing_editor(ui, "Parameters", &mut key.param_easing);
+                        });
+                        ui.separator();
+                    }
+                    if resort {
+                        self.timeline.sort();
+                    }
+                });
+                if self.playback.is_some() && ui.button("⏹ Stop Playback").clicked() {
+                    self.playback = None;
+                }
+
+                ui.separator();
+                ui.heading("Tools");
+                ui.checkbox(&mut self.cross_section_mode, "Cross-section (drag a line)");
+                ui.checkbox(&mut self.region.enabled, "Region boost (drag a rectangle)");
+                if self.region.enabled {
+                    ui.add(egui::Slider::new(&mut self.region.iteration_boost, 1.0..=16.0)
+                        .logarithmic(true)
+                        .text("Iteration Boost ×"));
+                    egui::ComboBox::from_label("Boost Samples")
+                        .selected_text(self.region.samples.to_string())
+                        .show_ui(ui, |ui| {
+                            for count in SAMPLE_COUNTS {
+                                ui.selectable_value(&mut self.region.samples, count, count.to_string());
+                            }
+                        });
+                }
+                let center = Complex64::new(state.center_x, state.center_y);
+                let mut guess = false;
+                egui::CollapsingHeader::new("Point Finder").show(ui, |ui| {
+                    let finder = &mut self.finder;
+                    ui.horizontal(|ui| {
+                        ui.radio_value(&mut finder.target, FinderTarget::Nucleus, "Minibrot");
+                        ui.radio_value(&mut finder.target, FinderTarget::Misiurewicz, "Misiurewicz");
+                    });
+                    ui.horizontal(|ui| {
+                        if finder.target == FinderTarget::Misiurewicz {
+                            ui.add(egui::DragValue::new(&mut finder.preperiod).clamp_range(1..=1000).prefix("preperiod "));
+                        }
+                        ui.add(egui::DragValue::new(&mut finder.period).clamp_range(1..=10000).prefix("period "));
+                    });
+                    let find = ui.horizontal(|ui| {
+                        guess = ui.button("Guess Period").clicked();
+                        ui.button("Find").clicked()
+                    }).inner;
+                    let supported = state.fractal_type == FractalType::Classic && state.power_im == 0.0;
+                    if find {
+                        finder.result = if !supported {
+                            None
+                        } else if finder.target == FinderTarget::Nucleus {
+                            finder::nucleus(center, finder.period, state.power)
+                        } else {
+                            finder::misiurewicz(center, finder.preperiod, finder.period, state.power)
+                        };
+                        finder.status = match &finder.result {
+                            _ if !supported => "Only the Classic type with a real power is supported".to_string(),
+                            Some(found) => format!("Found {:.12} {:+.12}i ({:.2e} away)",
+                                found.c.re, found.c.im, (found.c - center).norm()),
+                            None => "Newton's method did not converge".to_string(),
+                        };
+                    }
+                    if !finder.status.is_empty() {
+                        ui.label(&finder.status);
+                    }
+                    if let Some(found) = &finder.result {
+                        ui.horizontal(|ui| {
+                            if ui.button("Center").clicked() {
+                                state.center_x = found.c.re;
+                                state.center_y = found.c.im;
+                                state.needs_update = true;
+                            }
+                            if ui.button("Center & Zoom").clicked() {
+                                state.center_x = found.c.re;
+                                state.center_y = found.c.im;
+                                // Frame a minibrot at about half the view height; otherwise step in 10x.
+                                state.zoom = match found.size {
+                                    Some(size) if size > 0.0 => 1.25 / size,
+                                    _ => state.zoom * 10.0,
+                                };
+                                state.needs_update = true;
+                            }
+                        });
+                    }
+                });
+                // The atom domain under the view center names the period of the nearest minibrot.
+                if guess {
+                    self.finder.period = self.renderer_for(&state).trace_orbit(center, &state).min_iteration.max(1);
+                }
+                egui::CollapsingHeader::new("External Rays").show(ui, |ui| {
+                    let overlay = &mut self.rays;
+                    ui.checkbox(&mut overlay.enabled, "Show rays and equipotentials");
+                    ui.horizontal(|ui| {
+                        ui.label("Angles:");
+                        overlay.dirty |= ui.text_edit_singleline(&mut overlay.angles).changed();
+                    });
+                    overlay.dirty |= ui.add(egui::Slider::new(&mut overlay.depth, 4..=50).text("Ray depth")).changed();
+                    overlay.dirty |= ui.add(egui::Slider::new(&mut overlay.equipotentials, 0..=8).text("Equipotentials")).changed();
+                    if state.fractal_type != FractalType::Classic || state.power != 2.0 || state.power_im != 0.0 {
+                        ui.label("Drawn for the quadratic Classic set only");
+                    }
+                });
+                if ui.checkbox(&mut self.perf.enabled, "Tile timing overlay").changed() {
+                    state.needs_update = true;
+                }
+                if self.perf.enabled && !self.perf.tiles.is_empty() {
+                    ui.label(format!("{} tiles, {:.1} ms total, slowest {:.1} ms",
+                        self.perf.tiles.len(), self.perf.total_millis(), self.perf.slowest_millis()));
+                }
+                if ui.button("Compare Renders…").clicked() {
+                    self.compare.open = true;
+                }
+
+                ui.separator();
+                ui.heading("Controls");
+                ui.label("• Drag to pan");
+                ui.label("• Scroll to zoom");
+                ui.label("• Use sliders for fine control");
+                ui.label(format!("Using {} threads", self.thread_count));
+            });
+        });
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            let available_size = ui.available_size();
+            let needs_update = {
+                let mut state = self.state.write();
+                let size_changed = state.width != available_size.x as u32 || 
+                                 state.height != available_size.y as u32;
+                
+                if size_changed {
+                    state.width = available_size.x as u32;
+                    state.height = available_size.y as u32;
+                    state.needs_update = true;
+                }
                 
                 let needs_update = state.needs_update;
                 state.needs_update = false;
                 needs_update
             };
 
-            if needs_update {
-                let img = self.generate_mandelbrot();
+            let morph_frame = self.morph.as_ref().map(|m| m.frame_at(m.progress()));
+            // Morphs and color cycling render every frame in place; a changed view is
+            // rendered on a worker thread, cancelling whatever frame it replaces.
+            let fresh = match &morph_frame {
+                Some(frame) => {
+                    self.cancel_render();
+                    let (width, height) = {
+                        let state = self.state.read();
+                        self.quality.preview_size(state.width, state.height)
+                    };
+                    Some(self.render_morph_frame(frame, width, height))
+                }
+                None if self.cycle.enabled && self.state.read().fractal_type.colors_orbits() => {
+                    self.cancel_render();
+                    let shift = self.cycle.advance();
+                    Some(self.recolor_preview(needs_update, shift))
+                }
+                None if self.recolor && !needs_update && self.render_job.is_none() && self.keeps_orbits() => {
+                    Some(self.recolor_preview(false, 0.0))
+                }
+                None if needs_update || self.recolor => {
+                    self.start_render(self.interacting());
+                    None
+                }
+                // Input settled on a coarse frame: render it again at full resolution.
+                None if self.coarse_frame && !self.interacting() => {
+                    self.start_render(false);
+                    None
+                }
+                None => self.poll_render(ui.ctx()),
+            };
+            if self.coarse_frame {
+                ui.ctx().request_repaint_after(INTERACTION_SETTLE);
+            }
+            self.recolor = false;
+            if self.cycle.enabled {
+                ui.ctx().request_repaint();
+            }
+            if let Some(buffer) = fresh {
+                self.color_buffer = Some(buffer);
+                self.region.rect = None;
+                self.regrade = true;
+            }
+
+            // Grading changes only re-run the post stage over the stored float buffer.
+            let img = match &self.color_buffer {
+                Some(buffer) if self.regrade => Some(self.finish_preview(buffer)),
+                _ => None,
+            };
+            self.regrade = false;
+
+            if let Some(img) = img {
                 let color_image = egui::ColorImage::from_rgb(
-                    [self.state.read().width as usize, self.state.read().height as usize],
+                    [img.width() as usize, img.height() as usize],
                     img.as_raw()
                 );
                 
@@ -401,7 +4192,86 @@ impl eframe::App for FractalApp {
             }
             
             if let Some(texture) = &self.image_texture {
-                ui.add(egui::Image::new(&*texture).fit_to_original_size(1.0));
+                // Scaled previews are stretched back up to the window resolution.
+                let size = {
+                    let state = self.state.read();
+                    Vec2::new(state.width as f32, state.height as f32)
+                };
+                let image_rect = ui.add(egui::Image::new(&*texture).fit_to_exact_size(size)).rect;
+                self.image_origin = image_rect.min;
+            }
+
+            if let (true, Some(section)) = (self.cross_section_mode, &self.cross_section) {
+                let painter = ui.painter();
+                let start = self.image_origin + section.start.to_vec2();
+                let end = self.image_origin + section.end.to_vec2();
+                painter.line_segment([start, end], egui::Stroke::new(2.0, egui::Color32::YELLOW));
+                painter.circle_filled(start, 5.0, egui::Color32::YELLOW);
+                painter.circle_filled(end, 5.0, egui::Color32::YELLOW);
+            }
+
+            if let (true, Some(rect)) = (self.region.enabled, self.region.rect) {
+                let rect = rect.translate(self.image_origin.to_vec2());
+                ui.painter().rect_stroke(rect, 0.0, egui::Stroke::new(1.5, egui::Color32::LIGHT_BLUE));
+            }
+
+            if self.perf.enabled && self.perf.buffer_height > 0 {
+                let (width, height) = {
+                    let state = self.state.read();
+                    (state.width as f32, state.height as f32)
+                };
+                let scale = height / self.perf.buffer_height as f32;
+                let slowest = self.perf.slowest_millis().max(1e-6);
+                let painter = ui.painter();
+                for tile in &self.perf.tiles {
+                    let rect = egui::Rect::from_min_size(
+                        self.image_origin + Vec2::new(0.0, tile.y as f32 * scale),
+                        Vec2::new(width, tile.rows as f32 * scale),
+                    );
+                    painter.rect_filled(rect, 0.0, perf::heat_color((tile.millis / slowest) as f32));
+                    painter.rect_stroke(rect, 0.0, egui::Stroke::new(1.0, egui::Color32::from_black_alpha(120)));
+                    // Only label tiles tall enough to fit the text.
+                    if rect.height() >= 16.0 {
+                        painter.text(
+                            rect.left_top() + Vec2::new(4.0, 2.0),
+                            egui::Align2::LEFT_TOP,
+                            format!("{:.1} ms", tile.millis),
+                            egui::FontId::monospace(12.0),
+                            egui::Color32::WHITE,
+                        );
+                    }
+                }
+            }
+
+            if self.rays.enabled {
+                if self.rays.dirty {
+                    self.rays.rebuild();
+                }
+                let state = self.state.read();
+                if state.fractal_type == FractalType::Classic && state.power == 2.0 && state.power_im == 0.0 {
+                    let painter = ui.painter();
+                    let to_screen = |points: &[Complex64]| -> Vec<Pos2> {
+                        points.iter().map(|&c| self.image_origin + state.complex_to_pixel(c).to_vec2()).collect()
+                    };
+                    for curve in &self.rays.curves {
+                        painter.add(egui::Shape::line(to_screen(curve), egui::Stroke::new(1.0, egui::Color32::from_rgb(120, 200, 255))));
+                    }
+                    for ray in &self.rays.rays {
+                        painter.add(egui::Shape::line(to_screen(ray), egui::Stroke::new(1.5, egui::Color32::from_rgb(255, 220, 90))));
+                    }
+                }
+            }
+
+            {
+                let state = self.state.read();
+                if state.fractal_type == FractalType::Newton {
+                    let painter = ui.painter();
+                    for &root in &state.polynomial.roots {
+                        let pos = self.image_origin + state.complex_to_pixel(root).to_vec2();
+                        painter.circle_filled(pos, 5.0, egui::Color32::BLACK);
+                        painter.circle_stroke(pos, 5.0, egui::Stroke::new(2.0, egui::Color32::WHITE));
+                    }
+                }
             }
             
             self.handle_mouse_input(ui, available_size);
@@ -411,7 +4281,600 @@ impl eframe::App for FractalApp {
         if self.drag_start.is_some() {
             ctx.request_repaint();
         }
+
+        if self.cross_section_mode {
+            let profile = self.cross_section.as_ref().map(|s| self.cross_section_profile(s));
+            let mut open = self.cross_section_mode;
+            egui::Window::new("Cross Section")
+                .open(&mut open)
+                .default_width(360.0)
+                .show(ctx, |ui| match profile {
+                    Some(points) => {
+                        egui_plot::Plot::new("cross_section_plot")
+                            .height(200.0)
+                            .x_axis_label("distance")
+                            .y_axis_label("iterations")
+                            .show(ui, |plot_ui| plot_ui.line(egui_plot::Line::new(points)));
+                    }
+                    None => {
+                        ui.label("Drag across the image to draw a line.");
+                    }
+                });
+            self.cross_section_mode = open;
+        }
+
+        if self.compare.open {
+            let mut open = self.compare.open;
+            let mut run = false;
+            let mut regain = false;
+            egui::Window::new("Compare Renders")
+                .open(&mut open)
+                .default_width(420.0)
+                .show(ctx, |ui| {
+                    ui.columns(2, |columns| {
+                        quality_editor(&mut columns[0], "A", &mut self.compare.a);
+                        quality_editor(&mut columns[1], "B", &mut self.compare.b);
+                    });
+                    regain = ui.add(egui::Slider::new(&mut self.compare.gain, 1.0..=1000.0)
+                        .logarithmic(true)
+                        .text("Difference Gain")).changed();
+                    run = ui.add_enabled(self.compare_render.is_none(), egui::Button::new("Run Comparison")).clicked();
+                    if self.compare_render.is_some() {
+                        ui.label("Rendering…");
+                    }
+                    if let Some(result) = &self.compare.result {
+                        ui.label(format!("Max error: {:.4}", result.max_error));
+                        ui.label(format!("Mean error: {:.6}", result.mean_error));
+                        ui.label(format!("Pixels differing: {:.2}%", result.differing * 100.0));
+                    }
+                    if let Some(texture) = &self.compare_texture {
+                        ui.add(egui::Image::new(texture).shrink_to_fit());
+                    }
+                });
+            if run {
+                self.run_comparison();
+            }
+            let finished = self.poll_comparison(ctx);
+            if (finished || regain)
+                && let Some(result) = &self.compare.result
+            {
+                let img = result.difference_image(self.compare.gain);
+                let color_image = egui::ColorImage::from_rgb(
+                    [img.width() as usize, img.height() as usize],
+                    img.as_raw()
+                );
+                self.compare_texture = Some(ctx.load_texture("difference", color_image, Default::default()));
+            }
+            self.compare.open = open;
+        }
+
+        if self.gallery_open {
+            let mut open = self.gallery_open;
+            let mut load = None;
+            egui::Window::new("Gallery")
+                .open(&mut open)
+                .default_width(320.0)
+                .show(ctx, |ui| {
+                    egui::ScrollArea::vertical().show(ui, |ui| {
+                        for (i, entry) in self.gallery_entries.iter().enumerate() {
+                            ui.horizontal(|ui| {
+                                if ui.button("Load").clicked() {
+                                    load = Some(i);
+                                }
+                                ui.label(&entry.name);
+                                if !entry.url.is_empty() {
+                                    ui.hyperlink_to("↗", &entry.url);
+                                }
+                            });
+                        }
+                    });
+                });
+            if let Some(i) = load {
+                self.apply_state(self.gallery_entries[i].state.clone());
+            }
+            self.gallery_open = open;
+        }
+
+        if let Some(morph) = &self.morph {
+            if morph.finished() {
+                self.morph = None;
+                self.state.write().needs_update = true;
+            }
+            ctx.request_repaint();
+        }
+
+        // Playback replays a recording; capturing it would record the recording.
+        if self.playback.is_none() {
+            self.recorder.record(&self.state.read());
+        }
+    }
+}
+
+// Whether c lies inside the main cardioid or the period-2 bulb of the power-2
+// Mandelbrot set, where every orbit stays bounded.
+fn in_main_components(c: Complex64) -> bool {
+    let x = c.re - 0.25;
+    let y2 = c.im * c.im;
+    let q = x * x + y2;
+    q * (q + x) < 0.25 * y2 || (c.re + 1.0) * (c.re + 1.0) + y2 < 0.0625
+}
+
+fn round_to_f32(z: Complex64) -> Complex64 {
+    Complex64::new(z.re as f32 as f64, z.im as f32 as f64)
+}
+
+// Escape test for one iterate. The transcendental families grow through one
+// component only; Collatz and the reciprocal families can overflow to infinity
+// or NaN.
+fn diverged(fractal_type: FractalType, z: Complex64, bailout: f64, biomorph: bool) -> bool {
+    match fractal_type {
+        _ if biomorph => z.re.abs() > BIOMORPH_BAILOUT || z.im.abs() > BIOMORPH_BAILOUT || z.is_nan(),
+        // Ducks orbits stay bounded and are colored by their average; only a hit on
+        // the logarithm's pole ends one early.
+        FractalType::Ducks => !z.is_finite(),
+        FractalType::SinZ | FractalType::CoshZ => z.im.abs() > TRANSCENDENTAL_BAILOUT || z.is_nan(),
+        FractalType::ExpZ => z.re > TRANSCENDENTAL_BAILOUT || z.is_nan(),
+        FractalType::Collatz | FractalType::InverseSquare | FractalType::Quotient => {
+            z.norm_sqr() > bailout || !z.is_finite()
+        }
+        _ => z.norm_sqr() > bailout,
+    }
+}
+
+// z^p for a complex exponent; the real case keeps the cheaper powf.
+fn complex_pow(z: Complex64, exponent: Complex64) -> Complex64 {
+    if exponent.im == 0.0 { z.powf(exponent.re) } else { z.powc(exponent) }
+}
+
+// Fractional step count for a converged root-finding orbit. With convergence of
+// order k, ln|z - root| grows roughly k-fold per step and its ratio to the
+// tolerance's log gives how far past the threshold the last step landed.
+fn newton_smooth_steps(orbit: &Orbit, root: Complex64, order: f64) -> f32 {
+    let distance = (orbit.final_z - root).norm().max(f64::MIN_POSITIVE);
+    let overshoot = (distance.ln() / NEWTON_TOLERANCE.ln()).log(order).clamp(0.0, 1.0);
+    (orbit.iterations as f64 - overshoot).max(0.0) as f32
+}
+
+fn roll_params<R: Rng>(state: &mut FractalState, rng: &mut R) {
+    state.hue_offset = rng.gen_range(0.0..360.0);
+    state.saturation = rng.gen_range(0.7..1.0);
+    state.value = rng.gen_range(0.7..1.0);
+    state.palette = palette::random(rng);
+    state.power = rng.gen_range(2.0..4.0);
+    state.secondary_param = rng.gen_range(0.1..0.9);
+    state.fractal_type = match rng.gen_range(0..5) {
+        0 => FractalType::Classic,
+        1 => FractalType::Spiral,
+        2 => FractalType::Flower,
+        3 => FractalType::Phoenix,
+        _ => FractalType::Butterfly,
+    };
+}
+
+// Edge density plus brightness spread; flat fills and solid black both score near zero.
+fn structure_score(img: &ImageBuffer<Rgb<u8>, Vec<u8>>) -> f64 {
+    let (width, height) = img.dimensions();
+    let mut edges = 0usize;
+    let mut sum = 0.0;
+    let mut sum_sq = 0.0;
+
+    for y in 0..height {
+        for x in 0..width {
+            let pixel = img.get_pixel(x, y);
+            let [r, g, b] = pixel.0;
+            let luma = (0.299 * r as f64 + 0.587 * g as f64 + 0.114 * b as f64) / 255.0;
+            sum += luma;
+            sum_sq += luma * luma;
+
+            let right_differs = x + 1 < width && img.get_pixel(x + 1, y) != pixel;
+            let below_differs = y + 1 < height && img.get_pixel(x, y + 1) != pixel;
+            if right_differs || below_differs {
+                edges += 1;
+            }
+        }
+    }
+
+    let count = (width * height).max(1) as f64;
+    let mean = sum / count;
+    let deviation = (sum_sq / count - mean * mean).max(0.0).sqrt();
+    // Pure noise is as dull as a flat fill, so cap what edges alone can contribute.
+    (edges as f64 / count).min(0.3) + deviation
+}
+
+// One row per affine map: x' = a x + b y + e, y' = c x + d y + f, chosen by weight.
+fn ifs_table(ui: &mut egui::Ui, transforms: &mut Vec<ifs::Affine>) -> bool {
+    let mut changed = false;
+    let mut remove = None;
+    egui::Grid::new("ifs_table").striped(true).num_columns(8).show(ui, |ui| {
+        for header in ["a", "b", "c", "d", "e", "f", "weight", ""] {
+            ui.label(header);
+        }
+        ui.end_row();
+        for (i, t) in transforms.iter_mut().enumerate() {
+            for value in [&mut t.a, &mut t.b, &mut t.c, &mut t.d, &mut t.e, &mut t.f] {
+                changed |= ui.add(egui::DragValue::new(value).speed(0.01).max_decimals(3)).changed();
+            }
+            changed |= ui.add(egui::DragValue::new(&mut t.weight).speed(0.01).clamp_range(0.0..=100.0).max_decimals(3)).changed();
+            if ui.small_button("✕").clicked() {
+                remove = Some(i);
+            }
+            ui.end_row();
+        }
+    });
+    if let Some(i) = remove {
+        transforms.remove(i);
+        changed = true;
+    }
+    if ui.button("Add Transform").clicked() {
+        transforms.push(ifs::Affine { a: 0.5, b: 0.0, c: 0.0, d: 0.5, e: 0.0, f: 0.0, weight: 1.0 });
+        changed = true;
+    }
+    changed
+}
+
+// Camera, light and marching controls shared by the 3D types; returns whether anything changed.
+fn scene_editor(ui: &mut egui::Ui, scene: &mut Scene3d) -> bool {
+    use std::f64::consts::PI;
+    let mut changed = false;
+    egui::CollapsingHeader::new("Camera & Lighting").show(ui, |ui| {
+        let camera = &mut scene.camera;
+        changed |= ui.add(egui::Slider::new(&mut camera.yaw, -PI..=PI).text("Yaw")).changed();
+        changed |= ui.add(egui::Slider::new(&mut camera.pitch, -1.5..=1.5).text("Pitch")).changed();
+        changed |= ui.add(egui::Slider::new(&mut camera.distance, 1.1..=20.0).logarithmic(true).text("Distance")).changed();
+        changed |= ui.add(egui::Slider::new(&mut camera.fov_degrees, 10.0..=120.0).text("Field of view")).changed();
+        changed |= ui.add(egui::Slider::new(&mut scene.light_yaw, -PI..=PI).text("Light yaw")).changed();
+        changed |= ui.add(egui::Slider::new(&mut scene.light_pitch, -1.5..=1.5).text("Light pitch")).changed();
+        changed |= ui.add(egui::Slider::new(&mut scene.max_steps, 32..=512).text("Max steps")).changed();
+        changed |= ui.add(egui::Slider::new(&mut scene.detail, 0.05..=2.0).logarithmic(true).text("Detail threshold")).changed();
+        if ui.button("Reset Camera").clicked() {
+            *camera = raymarch::Camera::default();
+            changed = true;
+        }
+    });
+    ui.small("Drag the view to orbit, scroll to move closer");
+    changed
+}
+
+// Per-layer fractal and coloring controls; returns whether anything changed.
+fn layer_settings(ui: &mut egui::Ui, layer: &mut Layer, index: usize, names: &[String]) -> bool {
+    let state = &mut layer.state;
+    let mut changed = false;
+    changed |= ui.checkbox(&mut layer.recolors_base, "Recolor base fractal")
+        .on_hover_text("Follow the base fractal and only color it differently")
+        .changed();
+    if !layer.recolors_base {
+        egui::ComboBox::from_label("Type")
+            .selected_text(state.fractal_type.name())
+            .show_ui(ui, |ui| {
+                for fractal_type in FractalType::ALL {
+                    changed |= ui.selectable_value(&mut state.fractal_type, fractal_type, fractal_type.name()).changed();
+                }
+            });
+    }
+    egui::ComboBox::from_label("Blend")
+        .selected_text(layer.blend_mode.name())
+        .show_ui(ui, |ui| {
+            for mode in BlendMode::ALL {
+                changed |= ui.selectable_value(&mut layer.blend_mode, mode, mode.name()).changed();
+            }
+        });
+    changed |= coloring_combo(ui, "Coloring", &mut state.coloring);
+    if state.uses_trap() {
+        changed |= trap_editor(ui, &mut state.trap);
+    }
+    changed |= ui.add(egui::Slider::new(&mut layer.opacity, 0.0..=1.0).text("Opacity")).changed();
+    changed |= ui.checkbox(&mut layer.fill_interior, "Opaque interior").changed();
+
+    let mut masked = layer.mask.is_some();
+    if ui.checkbox(&mut masked, "Mask by orbit metric").changed() {
+        layer.mask = masked.then_some(LayerMask {
+            source: None,
+            metric: OrbitMetric::TrapDistance,
+            threshold: 0.1,
+            invert: false,
+        });
+        changed = true;
+    }
+    if let Some(mask) = &mut layer.mask {
+        let source_name = |source: Option<usize>| match source {
+            None => "Base".to_string(),
+            Some(j) => names.get(j).cloned().unwrap_or_else(|| "(missing)".to_string()),
+        };
+        egui::ComboBox::from_label("Source")
+            .selected_text(source_name(mask.source))
+            .show_ui(ui, |ui| {
+                changed |= ui.selectable_value(&mut mask.source, None, "Base").changed();
+                for (j, name) in names.iter().enumerate().filter(|(j, _)| *j != index) {
+                    changed |= ui.selectable_value(&mut mask.source, Some(j), name).changed();
+                }
+            });
+        egui::ComboBox::from_label("Metric")
+            .selected_text(mask.metric.name())
+            .show_ui(ui, |ui| {
+                for metric in OrbitMetric::ALL {
+                    changed |= ui.selectable_value(&mut mask.metric, metric, metric.name()).changed();
+                }
+            });
+        ui.horizontal(|ui| {
+            ui.label("Show where below");
+            changed |= ui.add(egui::DragValue::new(&mut mask.threshold).speed(0.005)).changed();
+        });
+        changed |= ui.checkbox(&mut mask.invert, "Invert mask").changed();
+    }
+    if !layer.recolors_base {
+        changed |= ui.add(egui::Slider::new(&mut state.power, 2.0..=4.0).step_by(0.1).text("Power")).changed();
+        changed |= ui.add(egui::Slider::new(&mut state.secondary_param, 0.1..=0.9).step_by(0.05).text("Shape Parameter")).changed();
+        changed |= ui.add(egui::Slider::new(&mut state.max_iter, 100..=5000).step_by(100.0).text("Max Iterations")).changed();
+    }
+    changed |= ui.add(egui::Slider::new(&mut state.hue_offset, 0.0..=360.0).text("Hue Offset")).changed();
+    changed |= ui.add(egui::Slider::new(&mut state.saturation, 0.0..=1.0).text("Saturation")).changed();
+    changed |= ui.add(egui::Slider::new(&mut state.value, 0.0..=1.0).text("Value")).changed();
+    changed
+}
+
+// Roots are edited directly; coefficient edits are solved back into roots.
+fn polynomial_editor(ui: &mut egui::Ui, polynomial: &mut Polynomial) -> bool {
+    let mut changed = false;
+    ui.label("Roots (drag the markers on the canvas):");
+    let mut remove = None;
+    for (i, root) in polynomial.roots.iter_mut().enumerate() {
+        ui.horizontal(|ui| {
+            changed |= ui.add(egui::DragValue::new(&mut root.re).speed(0.01).prefix("re ")).changed();
+            changed |= ui.add(egui::DragValue::new(&mut root.im).speed(0.01).prefix("im ")).changed();
+            if ui.small_button("✖").clicked() {
+                remove = Some(i);
+            }
+        });
+    }
+    if let Some(i) = remove.filter(|_| polynomial.degree() > 1) {
+        polynomial.roots.remove(i);
+        changed = true;
+    }
+    ui.horizontal(|ui| {
+        if ui.button("Add Root").clicked() {
+            polynomial.roots.push(Complex64::new(0.5, 0.5));
+            changed = true;
+        }
+        if ui.button("Reset to zⁿ − 1").clicked() {
+            *polynomial = Polynomial::roots_of_unity(polynomial.degree());
+            changed = true;
+        }
+    });
+
+    egui::CollapsingHeader::new("Coefficients (monic)").show(ui, |ui| {
+        let mut coeffs = polynomial.coefficients();
+        let degree = polynomial.degree();
+        let mut edited = false;
+        for (k, coeff) in coeffs.iter_mut().enumerate().take(degree) {
+            ui.horizontal(|ui| {
+                ui.label(format!("z^{}", k));
+                edited |= ui.add(egui::DragValue::new(&mut coeff.re).speed(0.01)).changed();
+                edited |= ui.add(egui::DragValue::new(&mut coeff.im).speed(0.01).suffix("i")).changed();
+            });
+        }
+        if edited {
+            *polynomial = Polynomial::from_coefficients(&coeffs);
+            changed = true;
+        }
+    });
+    changed
+}
+
+fn coloring_combo(ui: &mut egui::Ui, label: &str, coloring: &mut ColoringMode) -> bool {
+    let mut changed = false;
+    egui::ComboBox::from_label(label)
+        .selected_text(coloring.name())
+        .show_ui(ui, |ui| {
+            for mode in ColoringMode::ALL {
+                changed |= ui.selectable_value(coloring, mode, mode.name()).changed();
+            }
+        });
+    changed
+}
+
+fn trap_editor(ui: &mut egui::Ui, trap: &mut OrbitTrap) -> bool {
+    let mut changed = false;
+    egui::ComboBox::from_label("Trap")
+        .selected_text(trap.shape.name())
+        .show_ui(ui, |ui| {
+            for shape in TrapShape::ALL {
+                changed |= ui.selectable_value(&mut trap.shape, shape, shape.name()).changed();
+            }
+        });
+    ui.horizontal(|ui| {
+        ui.label("Center:");
+        changed |= ui.add(egui::DragValue::new(&mut trap.center.re).speed(0.01).max_decimals(6)).changed();
+        changed |= ui.add(egui::DragValue::new(&mut trap.center.im).speed(0.01).max_decimals(6).suffix("i")).changed();
+    });
+    match trap.shape {
+        TrapShape::Circle => {
+            changed |= ui.add(egui::Slider::new(&mut trap.radius, 0.01..=2.0).text("Radius")).changed();
+        }
+        TrapShape::Line | TrapShape::Cross => {
+            changed |= ui.add(egui::Slider::new(&mut trap.angle, -std::f64::consts::PI..=std::f64::consts::PI).text("Angle")).changed();
+        }
+        TrapShape::Point => {}
+    }
+    changed
+}
+
+fn srgb_color32(color: [f32; 3]) -> egui::Color32 {
+    let [r, g, b] = color.map(|v| (v.clamp(0.0, 1.0) * 255.0).round() as u8);
+    egui::Color32::from_rgb(r, g, b)
+}
+
+// Preview of the palette across `rect`, drawn as narrow slices.
+fn paint_gradient(ui: &egui::Ui, rect: egui::Rect, palette: &Palette, space: ColorSpace, interpolation: Interpolation) {
+    const SLICES: usize = 64;
+    for i in 0..SLICES {
+        let left = rect.left() + rect.width() * i as f32 / SLICES as f32;
+        let right = rect.left() + rect.width() * (i + 1) as f32 / SLICES as f32;
+        let slice = egui::Rect::from_min_max(Pos2::new(left, rect.top()), Pos2::new(right, rect.bottom()));
+        ui.painter().rect_filled(slice, 0.0, srgb_color32(palette.sample((i as f32 + 0.5) / SLICES as f32, space, interpolation)));
+    }
+}
+
+// Gradient bar with a handle per stop: drag a handle to move its stop, click it to
+// edit its color, double-click the bar to add a stop.
+fn gradient_editor(
+    ui: &mut egui::Ui,
+    palette: &mut Palette,
+    space: ColorSpace,
+    interpolation: Interpolation,
+    selected: &mut usize,
+) -> bool {
+    let mut changed = false;
+    ui.label("Palette");
+    let width = ui.available_width().min(260.0);
+    let (bar, bar_response) = ui.allocate_exact_size(Vec2::new(width, 20.0), egui::Sense::click());
+    let bar_response = bar_response.on_hover_text("Double-click to add a stop");
+    paint_gradient(ui, bar, palette, space, interpolation);
+    if bar_response.double_clicked()
+        && let Some(pointer) = bar_response.interact_pointer_pos()
+    {
+        *selected = palette.insert(((pointer.x - bar.left()) / bar.width()).clamp(0.0, 1.0), space, interpolation);
+        changed = true;
+    }
+
+    let (track, _) = ui.allocate_exact_size(Vec2::new(width, 12.0), egui::Sense::hover());
+    for i in 0..palette.stops.len() {
+        let x = track.left() + track.width() * palette.stops[i].position;
+        let handle = egui::Rect::from_center_size(Pos2::new(x, track.center().y), Vec2::new(8.0, 12.0));
+        let response = ui.interact(handle, ui.id().with(("palette_stop", i)), egui::Sense::click_and_drag());
+        if response.clicked() || response.drag_started() {
+            *selected = i;
+        }
+        if response.dragged() {
+            palette.move_stop(i, palette.stops[i].position + response.drag_delta().x / track.width());
+            changed = true;
+        }
+        let stroke = if i == *selected {
+            egui::Stroke::new(2.0, ui.visuals().strong_text_color())
+        } else {
+            egui::Stroke::new(1.0, ui.visuals().weak_text_color())
+        };
+        ui.painter().rect(handle, 2.0, srgb_color32(palette.stops[i].color), stroke);
+    }
+
+    *selected = (*selected).min(palette.stops.len().saturating_sub(1));
+    ui.horizontal(|ui| {
+        if let Some(stop) = palette.stops.get_mut(*selected) {
+            changed |= ui.color_edit_button_rgb(&mut stop.color).changed();
+            ui.label(format!("at {:.2}", stop.position));
+        }
+        if ui.add_enabled(palette.stops.len() > MIN_STOPS, egui::Button::new("Remove")).clicked() {
+            palette.remove(*selected);
+            *selected = (*selected).min(palette.stops.len() - 1);
+            changed = true;
+        }
+        if ui.button("Reset").clicked() {
+            *palette = Palette::default();
+            changed = true;
+        }
+    });
+    changed
+}
+
+fn waves_editor(ui: &mut egui::Ui, waves: &mut SineWaves) -> bool {
+    let mut changed = false;
+    egui::Grid::new("sine_waves").show(ui, |ui| {
+        ui.label("");
+        ui.label("Frequency");
+        ui.label("Phase");
+        ui.end_row();
+        for (c, name) in ["Red", "Green", "Blue"].into_iter().enumerate() {
+            ui.label(name);
+            changed |= ui.add(egui::DragValue::new(&mut waves.frequency[c]).speed(0.001).clamp_range(0.0..=2.0)).changed();
+            changed |= ui.add(egui::DragValue::new(&mut waves.phase[c]).speed(0.02).clamp_range(0.0..=std::f32::consts::TAU)).changed();
+            ui.end_row();
+        }
+    });
+    changed
+}
+
+// Changes only need the stored orbits recolored, not iterated again.
+fn lighting_editor(ui: &mut egui::Ui, lighting: &mut Lighting) -> bool {
+    let mut changed = false;
+    egui::CollapsingHeader::new("Lighting").default_open(true).show(ui, |ui| {
+        changed |= ui.add(egui::Slider::new(&mut lighting.azimuth, -180.0..=180.0).text("Azimuth")).changed();
+        changed |= ui.add(egui::Slider::new(&mut lighting.elevation, 0.0..=90.0).text("Elevation")).changed();
+        changed |= ui.add(egui::Slider::new(&mut lighting.ambient, 0.0..=1.0).text("Ambient")).changed();
+        changed |= ui.add(egui::Slider::new(&mut lighting.diffuse, 0.0..=1.5).text("Diffuse")).changed();
+        changed |= ui.add(egui::Slider::new(&mut lighting.specular, 0.0..=1.0).text("Specular")).changed();
+        changed |= ui.add(egui::Slider::new(&mut lighting.shininess, 1.0..=200.0).logarithmic(true).text("Shininess")).changed();
+        if ui.button("Reset Lighting").clicked() {
+            *lighting = Lighting::default();
+            changed = true;
+        }
+    });
+    changed
+}
+
+fn quality_editor(ui: &mut egui::Ui, label: &str, quality: &mut QualitySettings) {
+    ui.strong(label);
+    egui::ComboBox::from_id_source(format!("compare_preset_{}", label))
+        .selected_text(quality.preset().map_or("Custom", |p| p.name()))
+        .show_ui(ui, |ui| {
+            for preset in QualityPreset::ALL {
+                if ui.selectable_label(quality.preset() == Some(preset), preset.name()).clicked() {
+                    *quality = preset.settings();
+                }
+            }
+        });
+    for precision in Precision::ALL {
+        ui.radio_value(&mut quality.precision, precision, precision.name());
+    }
+    egui::ComboBox::from_id_source(format!("compare_samples_{}", label))
+        .selected_text(format!("{} spp", quality.antialias.samples))
+        .show_ui(ui, |ui| {
+            for count in SAMPLE_COUNTS {
+                ui.selectable_value(&mut quality.antialias.samples, count, format!("{} spp", count));
+            }
+        });
+    egui::ComboBox::from_id_source(format!("compare_pattern_{}", label))
+        .selected_text(quality.antialias.pattern.name())
+        .show_ui(ui, |ui| {
+            for pattern in SamplePattern::ALL {
+                ui.selectable_value(&mut quality.antialias.pattern, pattern, pattern.name());
+            }
+        });
+    ui.add(egui::Slider::new(&mut quality.iteration_multiplier, 0.25..=4.0).text("Iter ×"));
+}
+
+fn easing_editor(ui: &mut egui::Ui, label: &str, easing: &mut Easing) {
+    egui::ComboBox::from_label(label)
+        .selected_text(easing.name())
+        .show_ui(ui, |ui| {
+            for preset in Easing::PRESETS {
+                let selected = std::mem::discriminant(easing) == std::mem::discriminant(&preset);
+                if ui.selectable_label(selected, preset.name()).clicked() && !selected {
+                    *easing = preset;
+                }
+            }
+        });
+
+    if let Easing::CubicBezier(x1, y1, x2, y2) = easing {
+        ui.horizontal(|ui| {
+            ui.add(egui::DragValue::new(x1).speed(0.01).clamp_range(0.0..=1.0).prefix("x1 "));
+            ui.add(egui::DragValue::new(y1).speed(0.01).clamp_range(-1.0..=2.0).prefix("y1 "));
+            ui.add(egui::DragValue::new(x2).speed(0.01).clamp_range(0.0..=1.0).prefix("x2 "));
+            ui.add(egui::DragValue::new(y2).speed(0.01).clamp_range(-1.0..=2.0).prefix("y2 "));
+        });
     }
+
+    let (response, painter) = ui.allocate_painter(Vec2::new(120.0, 48.0), egui::Sense::hover());
+    let rect = response.rect;
+    let points: Vec<Pos2> = (0..=32)
+        .map(|i| {
+            let t = i as f64 / 32.0;
+            Pos2::new(
+                rect.left() + t as f32 * rect.width(),
+                rect.bottom() - easing.apply(t) as f32 * rect.height(),
+            )
+        })
+        .collect();
+    painter.rect_stroke(rect, 0.0, egui::Stroke::new(1.0, egui::Color32::GRAY));
+    painter.add(egui::Shape::line(points, egui::Stroke::new(1.5, egui::Color32::WHITE)));
 }
 
 fn main() {
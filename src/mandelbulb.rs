@@ -0,0 +1,40 @@
+use crate::raymarch::{DistanceEstimator, V3};
+
+// Points farther out than this always escape.
+const BAILOUT: f64 = 2.0;
+
+// White and Nylander's power-n bulb: z -> z^n + c in spherical coordinates.
+#[derive(Clone)]
+pub struct Mandelbulb {
+    pub power: f64,
+    pub iterations: u32,
+}
+
+impl Default for Mandelbulb {
+    fn default() -> Self {
+        Self { power: 8.0, iterations: 12 }
+    }
+}
+
+impl DistanceEstimator for Mandelbulb {
+    fn estimate(&self, c: V3) -> (f64, f64) {
+        let mut z = c;
+        let mut dr = 1.0;
+        let mut r = z.length();
+        let mut trap = r;
+        for _ in 0..self.iterations {
+            if r > BAILOUT || r == 0.0 {
+                break;
+            }
+            let theta = (z.z / r).acos() * self.power;
+            let phi = z.y.atan2(z.x) * self.power;
+            dr = r.powf(self.power - 1.0) * self.power * dr + 1.0;
+            let zr = r.powf(self.power);
+            z = V3::new(theta.sin() * phi.cos(), theta.sin() * phi.sin(), theta.cos()) * zr + c;
+            r = z.length();
+            trap = trap.min(r);
+        }
+        // Hubbard-Douady style estimate from the running derivative.
+        (0.5 * r.ln() * r / dr, trap)
+    }
+}
@@ -0,0 +1,91 @@
+use crate::animation::Easing;
+use crate::FractalState;
+use std::time::Instant;
+
+// Glide velocity decays by e^-FRICTION per second after a drag is released.
+const FRICTION: f64 = 4.0;
+// Gliding stops below this speed, in view widths per second.
+const MIN_SPEED: f64 = 0.01;
+const ZOOM_DURATION: f64 = 0.15;
+
+struct ZoomAnimation {
+    from: f64,
+    to: f64,
+    started: Instant,
+}
+
+#[derive(Default)]
+pub struct Navigator {
+    // Complex-plane units per second.
+    velocity: (f64, f64),
+    last_tick: Option<Instant>,
+    gliding: bool,
+    zoom: Option<ZoomAnimation>,
+}
+
+impl Navigator {
+    // Called every frame of a drag with how far the view center moved.
+    pub fn track_drag(&mut self, dx: f64, dy: f64) {
+        let now = Instant::now();
+        if let Some(last) = self.last_tick {
+            let dt = now.duration_since(last).as_secs_f64().max(1e-3);
+            // Smoothed so a single jittery frame doesn't decide the throw.
+            self.velocity.0 = self.velocity.0 * 0.6 + dx / dt * 0.4;
+            self.velocity.1 = self.velocity.1 * 0.6 + dy / dt * 0.4;
+        }
+        self.last_tick = Some(now);
+        self.gliding = false;
+    }
+
+    pub fn release(&mut self) {
+        self.gliding = true;
+        self.last_tick = Some(Instant::now());
+    }
+
+    pub fn stop(&mut self) {
+        self.velocity = (0.0, 0.0);
+        self.last_tick = None;
+        self.gliding = false;
+    }
+
+    // Where the running zoom animation ends, so repeated scrolls accumulate.
+    pub fn zoom_target(&self) -> Option<f64> {
+        self.zoom.as_ref().map(|z| z.to)
+    }
+
+    pub fn zoom_to(&mut self, current: f64, target: f64) {
+        self.zoom = Some(ZoomAnimation { from: current, to: target, started: Instant::now() });
+    }
+
+    // Advances the glide and zoom animation; true while either is still running.
+    pub fn step(&mut self, state: &mut FractalState) -> bool {
+        let now = Instant::now();
+        if self.gliding {
+            let dt = self.last_tick.map_or(0.0, |t| now.duration_since(t).as_secs_f64());
+            self.last_tick = Some(now);
+            state.center_x += self.velocity.0 * dt;
+            state.center_y += self.velocity.1 * dt;
+            state.needs_update = true;
+
+            let decay = (-FRICTION * dt).exp();
+            self.velocity = (self.velocity.0 * decay, self.velocity.1 * decay);
+            let view_width = 2.5 / state.zoom;
+            if self.velocity.0.hypot(self.velocity.1) < MIN_SPEED * view_width {
+                self.stop();
+            }
+        }
+
+        if let Some(anim) = &self.zoom {
+            // Interpolated in log space so zooming in and out feel symmetric.
+            let t = now.duration_since(anim.started).as_secs_f64() / ZOOM_DURATION;
+            let eased = Easing::EaseOut.apply(t);
+            state.zoom = (anim.from.ln() + (anim.to.ln() - anim.from.ln()) * eased).exp();
+            state.needs_update = true;
+            if t >= 1.0 {
+                self.zoom = None;
+            }
+        }
+
+        self.gliding || self.zoom.is_some()
+    }
+}
@@ -0,0 +1,165 @@
+use crate::coloring::OrbitTrap;
+use num_complex::Complex64;
+
+// Per-pixel summary of an orbit, kept alongside the escape count so coloring
+// and layer masks can use more than just the iteration number.
+#[derive(Clone, Copy)]
+pub struct Orbit {
+    pub iterations: u32,
+    pub escaped: bool,
+    // Closest approach of the orbit to the origin (a point trap at 0), and the
+    // iteration it happened at; the latter partitions the plane into atom domains.
+    pub min_distance: f64,
+    pub min_iteration: u32,
+    pub final_z: Complex64,
+    // Index of the root a root-finding orbit settled on.
+    pub root: Option<usize>,
+    // Lyapunov exponent, for kernels that measure stability instead of escape.
+    pub exponent: f64,
+    // Running sum of |z| over the tracked iterations, for average-based coloring.
+    pub magnitude_sum: f64,
+    // Length of the cycle a bounded orbit settled on, or 0 if none was found.
+    pub period: u32,
+    // Closest approach to the orbit trap, when trap coloring is on, and the iterate
+    // that made it.
+    pub trap_distance: f64,
+    pub trap_point: Complex64,
+    // Exterior distance estimate to the set, in plane units, for escaped orbits
+    // that tracked their derivative; infinite otherwise.
+    pub distance: f64,
+    // Unit direction of z / dz at escape, the outward normal of the potential's level curves.
+    pub normal: Complex64,
+    // ln |dz| at the last iterate, escaped or not, for orbits that tracked their
+    // derivative; infinite if it overflowed.
+    pub log_derivative: f64,
+    // Running sum of an averaging or summing coloring's per-iteration term, the sum one
+    // iteration earlier, and the number of terms.
+    pub average_sum: f64,
+    pub average_previous: f64,
+    pub average_count: u32,
+}
+
+impl Orbit {
+    pub fn new() -> Self {
+        Self {
+            iterations: 0,
+            escaped: false,
+            min_distance: f64::INFINITY,
+            min_iteration: 0,
+            final_z: Complex64::new(0.0, 0.0),
+            root: None,
+            exponent: 0.0,
+            magnitude_sum: 0.0,
+            period: 0,
+            trap_distance: f64::INFINITY,
+            trap_point: Complex64::new(0.0, 0.0),
+            distance: f64::INFINITY,
+            normal: Complex64::new(0.0, 0.0),
+            log_derivative: f64::NEG_INFINITY,
+            average_sum: 0.0,
+            average_previous: 0.0,
+            average_count: 0,
+        }
+    }
+
+    #[inline(always)]
+    pub fn track(&mut self, z: Complex64, iteration: u32) {
+        let distance = z.norm();
+        self.magnitude_sum += distance;
+        if distance < self.min_distance {
+            self.min_distance = distance;
+            self.min_iteration = iteration;
+        }
+    }
+
+    #[inline(always)]
+    pub fn track_trap(&mut self, z: Complex64, trap: &OrbitTrap) {
+        let distance = trap.distance(z);
+        if distance < self.trap_distance {
+            self.trap_distance = distance;
+            self.trap_point = z;
+        }
+    }
+
+    #[inline(always)]
+    pub fn track_average(&mut self, term: f64) {
+        self.average_previous = self.average_sum;
+        self.average_sum += term;
+        self.average_count += 1;
+    }
+
+    // Mean of the averaged terms, blended between the means with and without the
+    // last term by how far past the bailout radius the final iterate landed, which
+    // hides the bands a plain mean shows at each iteration count.
+    pub fn smooth_average(&self, bailout: f64) -> f64 {
+        if self.average_count < 2 {
+            return self.average_sum;
+        }
+        let last = self.average_sum / self.average_count as f64;
+        let previous = self.average_previous / (self.average_count - 1) as f64;
+        let radius = self.final_z.norm().max(1.0 + 1e-9);
+        let blend = (1.0 + (0.5 * bailout.ln() / radius.ln()).log2()).clamp(0.0, 1.0);
+        blend * last + (1.0 - blend) * previous
+    }
+}
+
+// Longest cycle period detection looks for.
+const MAX_PERIOD: usize = 64;
+// Squared distance within which an iterate counts as back where it was.
+const PERIOD_TOLERANCE: f64 = 1e-9;
+
+// The last few iterates of a bounded orbit, kept to find the cycle it settled on.
+pub struct RecentIterates {
+    values: [Complex64; MAX_PERIOD],
+    count: usize,
+}
+
+impl RecentIterates {
+    pub fn new() -> Self {
+        Self { values: [Complex64::new(0.0, 0.0); MAX_PERIOD], count: 0 }
+    }
+
+    #[inline(always)]
+    pub fn push(&mut self, z: Complex64) {
+        self.values[self.count % MAX_PERIOD] = z;
+        self.count += 1;
+    }
+
+    // The smallest p with z close to the iterate p steps back, or 0 if there is none.
+    pub fn period(&self, z: Complex64) -> u32 {
+        (1..=self.count.min(MAX_PERIOD))
+            .find(|&p| (z - self.values[(self.count - p) % MAX_PERIOD]).norm_sqr() < PERIOD_TOLERANCE)
+            .map_or(0, |p| p as u32)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum OrbitMetric {
+    Iterations,
+    TrapDistance,
+    FinalMagnitude,
+}
+
+impl OrbitMetric {
+    pub const ALL: [OrbitMetric; 3] = [
+        OrbitMetric::Iterations,
+        OrbitMetric::TrapDistance,
+        OrbitMetric::FinalMagnitude,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            OrbitMetric::Iterations => "Iterations (0-1)",
+            OrbitMetric::TrapDistance => "Trap distance",
+            OrbitMetric::FinalMagnitude => "Final |z|",
+        }
+    }
+
+    pub fn value(&self, orbit: &Orbit, max_iter: u32) -> f64 {
+        match self {
+            OrbitMetric::Iterations => orbit.iterations as f64 / max_iter as f64,
+            OrbitMetric::TrapDistance => orbit.min_distance,
+            OrbitMetric::FinalMagnitude => orbit.final_z.norm(),
+        }
+    }
+}
@@ -0,0 +1,117 @@
+// Points sampled to find the orbit's extent when framing it.
+const FRAMING_POINTS: usize = 100_000;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum OrbitMap {
+    Hopalong,
+    Gingerbreadman,
+    Martin,
+}
+
+impl OrbitMap {
+    pub const ALL: [OrbitMap; 3] = [OrbitMap::Hopalong, OrbitMap::Gingerbreadman, OrbitMap::Martin];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            OrbitMap::Hopalong => "Hopalong",
+            OrbitMap::Gingerbreadman => "Gingerbreadman",
+            OrbitMap::Martin => "Martin",
+        }
+    }
+
+    // How many of a, b, c the map reads.
+    pub fn parameter_count(&self) -> usize {
+        match self {
+            OrbitMap::Hopalong => 3,
+            OrbitMap::Gingerbreadman => 0,
+            OrbitMap::Martin => 1,
+        }
+    }
+
+    // Barry Martin's and Dewdney's published parameters.
+    pub fn defaults(&self) -> [f64; 3] {
+        match self {
+            OrbitMap::Hopalong => [-55.0, -1.0, -42.0],
+            OrbitMap::Gingerbreadman => [0.0; 3],
+            OrbitMap::Martin => [std::f64::consts::PI, 0.0, 0.0],
+        }
+    }
+
+    // Hopalong:        x' = y - sign(x) sqrt|b x - c|,  y' = a - x
+    // Gingerbreadman:  x' = 1 - y + |x|,                y' = x
+    // Martin:          x' = y - sin(x),                 y' = a - x
+    #[inline]
+    fn step(&self, [a, b, c]: [f64; 3], (x, y): (f64, f64)) -> (f64, f64) {
+        match self {
+            OrbitMap::Hopalong => (y - x.signum() * (b * x - c).abs().sqrt(), a - x),
+            OrbitMap::Gingerbreadman => (1.0 - y + x.abs(), x),
+            OrbitMap::Martin => (y - x.sin(), a - x),
+        }
+    }
+
+    fn start(&self) -> (f64, f64) {
+        match self {
+            // The origin lies on a 6-cycle, so the classic picture starts just beside it.
+            OrbitMap::Gingerbreadman => (-0.1, 0.0),
+            _ => (0.0, 0.0),
+        }
+    }
+}
+
+#[derive(Clone)]
+pub struct OrbitPlot {
+    pub map: OrbitMap,
+    pub params: [f64; 3],
+    // Length of the single orbit.
+    pub points: usize,
+    pub exposure: f32,
+}
+
+impl Default for OrbitPlot {
+    fn default() -> Self {
+        let map = OrbitMap::Hopalong;
+        Self { map, params: map.defaults(), points: 2_000_000, exposure: 1.0 }
+    }
+}
+
+impl OrbitPlot {
+    pub fn set_map(&mut self, map: OrbitMap) {
+        self.map = map;
+        self.params = map.defaults();
+    }
+
+    // Zoom and center (x, y) fitting the start of the orbit, which these maps
+    // spread over a range that depends strongly on the parameters.
+    pub fn view(&self) -> (f64, f64, f64) {
+        let mut point = self.map.start();
+        let (mut min, mut max) = (point, point);
+        for _ in 0..FRAMING_POINTS.min(self.points) {
+            point = self.map.step(self.params, point);
+            if !point.0.is_finite() || !point.1.is_finite() {
+                break;
+            }
+            min = (min.0.min(point.0), min.1.min(point.1));
+            max = (max.0.max(point.0), max.1.max(point.1));
+        }
+        let center = ((min.0 + max.0) / 2.0, (min.1 + max.1) / 2.0);
+        // The view is 3.5 s wide and 2 s tall.
+        let scale = ((max.0 - min.0) / 3.5).max((max.1 - min.1) / 2.0).max(1e-6) * 1.05;
+        (2.5 / scale, center.0 + 0.75 * scale, center.1)
+    }
+}
+
+// Hit counts of one long orbit. Each point depends on the last, so unlike the
+// attractor plots this runs on a single thread.
+pub fn accumulate(plot: &OrbitPlot, width: u32, height: u32, origin: (f64, f64), step: (f64, f64)) -> Vec<u32> {
+    let mut hits = vec![0u32; (width * height) as usize];
+    let mut point = plot.map.start();
+    for _ in 0..plot.points {
+        point = plot.map.step(plot.params, point);
+        let x = (point.0 - origin.0) / step.0;
+        let y = (point.1 - origin.1) / step.1;
+        if x >= 0.0 && y >= 0.0 && x < width as f64 && y < height as f64 {
+            hits[y as usize * width as usize + x as usize] += 1;
+        }
+    }
+    hits
+}
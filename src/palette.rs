@@ -0,0 +1,387 @@
+use crate::backend::config_dir;
+use crate::color::ColorSpace;
+use rand::Rng;
+use std::path::PathBuf;
+
+// Fewest stops a palette keeps; the editor will not remove past this.
+pub const MIN_STOPS: usize = 2;
+
+// How the gradient runs between stops, in whichever color space it blends in.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Interpolation {
+    // Straight from one stop to the next, with a visible kink at each stop.
+    Linear,
+    // A monotone cubic through the stops: smooth at each stop, but never
+    // overshooting the colors on either side.
+    Spline,
+}
+
+impl Interpolation {
+    pub const ALL: [Interpolation; 2] = [Interpolation::Linear, Interpolation::Spline];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Interpolation::Linear => "Linear",
+            Interpolation::Spline => "Monotone spline",
+        }
+    }
+}
+
+// A color at a position along the gradient, picked in sRGB.
+#[derive(Clone, Copy, PartialEq)]
+pub struct ColorStop {
+    pub position: f32,
+    pub color: [f32; 3],
+}
+
+// A cyclic gradient over [0, 1): past the last stop it blends back into the first,
+// so iteration counts can wrap around it any number of times.
+#[derive(Clone, PartialEq)]
+pub struct Palette {
+    // Sorted by position.
+    pub stops: Vec<ColorStop>,
+}
+
+impl Default for Palette {
+    // The six HSV primaries and secondaries, which blend into exactly the hue sweep
+    // escape-time coloring used before palettes.
+    fn default() -> Self {
+        let colors = [
+            [1.0, 0.0, 0.0],
+            [1.0, 1.0, 0.0],
+            [0.0, 1.0, 0.0],
+            [0.0, 1.0, 1.0],
+            [0.0, 0.0, 1.0],
+            [1.0, 0.0, 1.0],
+        ];
+        let stops = colors
+            .iter()
+            .enumerate()
+            .map(|(i, &color)| ColorStop { position: i as f32 / 6.0, color })
+            .collect();
+        Self { stops }
+    }
+}
+
+impl Palette {
+    // Evenly spaced stops from 8-bit sRGB colors.
+    pub fn from_rgb(colors: &[[u8; 3]]) -> Self {
+        let count = colors.len() as f32;
+        let stops = colors
+            .iter()
+            .enumerate()
+            .map(|(i, color)| ColorStop { position: i as f32 / count, color: color.map(|c| c as f32 / 255.0) })
+            .collect();
+        Self { stops }
+    }
+
+    // sRGB color at `t`, which wraps to [0, 1), blending stops in `space`.
+    pub fn sample(&self, t: f32, space: ColorSpace, interpolation: Interpolation) -> [f32; 3] {
+        let count = self.stops.len() as isize;
+        if count == 0 {
+            return [0.0; 3];
+        }
+        let t = t.rem_euclid(1.0);
+        // The stop at or before t is `from`, which is -1 (the last stop, a cycle
+        // back) when t comes before the first.
+        let from = self.stops.partition_point(|stop| stop.position <= t) as isize - 1;
+        // The stop `k` places on from `from`, with its position unwrapped so
+        // positions keep increasing across the wrap.
+        let stop = |k: isize| {
+            let i = from + k;
+            let stop = self.stops[i.rem_euclid(count) as usize];
+            ColorStop { position: stop.position + i.div_euclid(count) as f32, ..stop }
+        };
+        match interpolation {
+            Interpolation::Linear => {
+                let (a, b) = (stop(0), stop(1));
+                let span = b.position - a.position;
+                let f = if span > 0.0 { ((t - a.position) / span).clamp(0.0, 1.0) } else { 0.0 };
+                space.mix(a.color, b.color, f)
+            }
+            Interpolation::Spline => {
+                let stops = [stop(-1), stop(0), stop(1), stop(2)];
+                let mut coords = stops.map(|stop| space.to_coords(stop.color));
+                space.unwrap_hues(&mut coords);
+                let x = stops.map(|stop| stop.position);
+                space.to_srgb(std::array::from_fn(|c| monotone_cubic(x, coords.map(|p| p[c]), t)))
+            }
+        }
+    }
+
+    // Adds a stop at `position` in the color the gradient already has there, so the
+    // picture does not change until it is edited. Returns its index.
+    pub fn insert(&mut self, position: f32, space: ColorSpace, interpolation: Interpolation) -> usize {
+        let color = self.sample(position, space, interpolation);
+        let index = self.stops.partition_point(|stop| stop.position <= position);
+        self.stops.insert(index, ColorStop { position, color });
+        index
+    }
+
+    pub fn remove(&mut self, index: usize) {
+        if self.stops.len() > MIN_STOPS && index < self.stops.len() {
+            self.stops.remove(index);
+        }
+    }
+
+    // Moves a stop without letting it pass its neighbours, which keeps the stops sorted.
+    pub fn move_stop(&mut self, index: usize, position: f32) {
+        let low = if index > 0 { self.stops[index - 1].position } else { 0.0 };
+        let high = self.stops.get(index + 1).map_or(1.0, |stop| stop.position);
+        self.stops[index].position = position.clamp(low, high);
+    }
+
+    // GIMP gradient with a linear RGB segment between each pair of stops. The
+    // format cannot wrap, so the blend across 1.0 is split at both ends.
+    pub fn to_ggr(&self, name: &str) -> String {
+        let mut stops = self.stops.clone();
+        // GIMP blends segments in sRGB, so the wrap color is taken the same way.
+        let wrap = self.sample(0.0, ColorSpace::Rgb, Interpolation::Linear);
+        if stops.first().is_some_and(|stop| stop.position > 0.0) {
+            stops.insert(0, ColorStop { position: 0.0, color: wrap });
+        }
+        if stops.last().is_some_and(|stop| stop.position < 1.0) {
+            stops.push(ColorStop { position: 1.0, color: wrap });
+        }
+        let mut out = format!("GIMP Gradient\nName: {}\n{}\n", name, stops.len().saturating_sub(1));
+        for pair in stops.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+            let [r0, g0, b0] = from.color;
+            let [r1, g1, b1] = to.color;
+            out += &format!(
+                "{:.6} {:.6} {:.6} {:.6} {:.6} {:.6} 1.000000 {:.6} {:.6} {:.6} 1.000000 0 0\n",
+                from.position,
+                (from.position + to.position) / 2.0,
+                to.position,
+                r0, g0, b0, r1, g1, b1,
+            );
+        }
+        out
+    }
+}
+
+// Fritsch-Carlson: the cubic Hermite between the middle two of four points at `t`,
+// with tangents limited so it stays between them wherever the data is monotone.
+fn monotone_cubic(x: [f32; 4], y: [f32; 4], t: f32) -> f32 {
+    let span = x[2] - x[1];
+    if span <= 0.0 {
+        return y[1];
+    }
+    let secant = |i: usize| {
+        let h = x[i + 1] - x[i];
+        if h > 0.0 { (y[i + 1] - y[i]) / h } else { 0.0 }
+    };
+    let (before, middle, after) = (secant(0), secant(1), secant(2));
+    let tangent = |a: f32, b: f32| {
+        if a * b <= 0.0 {
+            0.0
+        } else {
+            ((a + b) / 2.0).clamp(-3.0 * a.abs().min(b.abs()), 3.0 * a.abs().min(b.abs()))
+        }
+    };
+    let (m1, m2) = (tangent(before, middle), tangent(middle, after));
+    let s = ((t - x[1]) / span).clamp(0.0, 1.0);
+    let (s2, s3) = (s * s, s * s * s);
+    (2.0 * s3 - 3.0 * s2 + 1.0) * y[1]
+        + (s3 - 2.0 * s2 + s) * span * m1
+        + (-2.0 * s3 + 3.0 * s2) * y[2]
+        + (s3 - s2) * span * m2
+}
+
+// A random palette that hangs together: hues from one color-wheel scheme around a
+// random base, picked in OKLCh so equal steps look equal, with lightness rising from
+// dark to light and back once per cycle so the wrap stays seamless.
+pub fn random<R: Rng>(rng: &mut R) -> Palette {
+    let base: f32 = rng.gen_range(0.0..360.0);
+    // Scheme hues as offsets from the base: analogous, complementary, triadic and
+    // split complementary.
+    let scheme: &[f32] = match rng.gen_range(0..4) {
+        0 => &[0.0, 30.0, 60.0],
+        1 => &[0.0, 180.0],
+        2 => &[0.0, 120.0, 240.0],
+        _ => &[0.0, 150.0, 210.0],
+    };
+    let count = rng.gen_range(4..=7);
+    let dark: f32 = rng.gen_range(0.1..0.35);
+    let light: f32 = rng.gen_range(0.85..1.0);
+    let saturation = rng.gen_range(0.5..1.0);
+    let stops = (0..count)
+        .map(|i| {
+            let position = i as f32 / count as f32;
+            let lightness = dark + (light - dark) * (0.5 - 0.5 * (std::f32::consts::TAU * position).cos());
+            let jitter: f32 = rng.gen_range(-10.0..10.0);
+            let hue = (base + scheme[i % scheme.len()] + jitter).rem_euclid(360.0);
+            ColorStop { position, color: ColorSpace::Oklch.hue(hue, saturation, lightness) }
+        })
+        .collect();
+    Palette { stops }
+}
+
+// Writes the palette as a GIMP gradient, which the importer and GIMP both read.
+pub fn export(path: &str, name: &str, palette: &Palette) -> Result<(), String> {
+    let path = path.trim();
+    std::fs::write(path, palette.to_ggr(name)).map_err(|err| format!("Could not write {}: {}", path, err))
+}
+
+// Saved palettes are .ggr files in the settings directory, named after the file.
+fn library_dir() -> Option<PathBuf> {
+    Some(config_dir()?.join("palettes"))
+}
+
+// Keeps names usable as file names on every platform. The library lists palettes by
+// this stem, so None for names with nothing usable left.
+fn file_stem(name: &str) -> Option<String> {
+    let name: String = name
+        .chars()
+        .filter(|c| c.is_alphanumeric() || matches!(c, ' ' | '-' | '_'))
+        .collect();
+    let name = name.trim();
+    (!name.is_empty()).then(|| name.to_owned())
+}
+
+// Every readable palette in the library, sorted by name. Unreadable files are skipped.
+pub fn load_library() -> Vec<(String, Palette)> {
+    let Some(entries) = library_dir().and_then(|dir| std::fs::read_dir(dir).ok()) else {
+        return Vec::new();
+    };
+    let mut palettes: Vec<(String, Palette)> = entries
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let name = path.file_stem()?.to_str()?.to_owned();
+            let palette = import(path.to_str()?).ok()?;
+            Some((name, palette))
+        })
+        .collect();
+    palettes.sort_by(|a, b| a.0.cmp(&b.0));
+    palettes
+}
+
+pub fn save_to_library(name: &str, palette: &Palette) -> Result<(), String> {
+    let dir = library_dir().ok_or("No settings directory to save palettes in")?;
+    std::fs::create_dir_all(&dir).map_err(|err| format!("Could not create {}: {}", dir.display(), err))?;
+    let stem = file_stem(name).ok_or("Palette names need a letter or digit")?;
+    let path = dir.join(format!("{}.ggr", stem));
+    // Saving under the same name replaces the palette; a different name that comes
+    // down to the same file would replace it without the user asking.
+    if stem != name.trim() && path.exists() {
+        return Err(format!("\"{}\" would replace the saved palette \"{}\"", name.trim(), stem));
+    }
+    std::fs::write(&path, palette.to_ggr(name)).map_err(|err| format!("Could not write {}: {}", path.display(), err))
+}
+
+pub fn delete_from_library(name: &str) {
+    if let (Some(dir), Some(stem)) = (library_dir(), file_stem(name)) {
+        let _ = std::fs::remove_file(dir.join(format!("{}.ggr", stem)));
+    }
+}
+
+// Reads a palette file, picking the format by extension.
+pub fn import(path: &str) -> Result<Palette, String> {
+    let path = path.trim();
+    let text = std::fs::read_to_string(path).map_err(|err| format!("Could not read {}: {}", path, err))?;
+    let extension = std::path::Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_ascii_lowercase();
+    let stops = match extension.as_str() {
+        "map" => parse_map(&text)?,
+        "ugr" => parse_ugr(&text)?,
+        "ggr" => parse_ggr(&text)?,
+        _ => return Err("Expected a .map, .ugr or .ggr file".to_owned()),
+    };
+    if stops.len() < MIN_STOPS {
+        return Err("Palette has fewer than two colors".to_owned());
+    }
+    Ok(Palette { stops })
+}
+
+// Fractint: one "r g b" line per entry, 0-255, evenly spaced; anything after the
+// third number is a comment.
+fn parse_map(text: &str) -> Result<Vec<ColorStop>, String> {
+    let mut colors = Vec::new();
+    for (n, line) in text.lines().enumerate() {
+        let fields: Vec<&str> = line.split_whitespace().take(3).collect();
+        if fields.is_empty() {
+            continue;
+        }
+        let channel = |s: &str| s.parse::<u8>();
+        match fields.as_slice() {
+            [r, g, b] => match (channel(r), channel(g), channel(b)) {
+                (Ok(r), Ok(g), Ok(b)) => colors.push([r, g, b]),
+                _ => return Err(format!("Line {}: expected three values from 0 to 255", n + 1)),
+            },
+            _ => return Err(format!("Line {}: expected three values from 0 to 255", n + 1)),
+        }
+    }
+    Ok(Palette::from_rgb(&colors).stops)
+}
+
+// Ultra Fractal: a file of gradients, each a run of "index=i color=c" pairs with
+// indices over 0-399 and colors packed as 0xBBGGRR. Only the first gradient is read.
+fn parse_ugr(text: &str) -> Result<Vec<ColorStop>, String> {
+    let start = text.find("gradient:").ok_or("No gradient found in the file")?;
+    let body = &text[start..];
+    let body = &body[..body.find('}').unwrap_or(body.len())];
+    let mut stops = Vec::new();
+    let mut index = None;
+    for token in body.split_whitespace() {
+        if let Some(value) = token.strip_prefix("index=") {
+            index = Some(value.parse::<i32>().map_err(|_| format!("Bad gradient index \"{}\"", value))?);
+        } else if let Some(value) = token.strip_prefix("color=") {
+            let packed = value.parse::<u32>().map_err(|_| format!("Bad gradient color \"{}\"", value))?;
+            let i = index.take().ok_or("Gradient color without an index")?;
+            let channel = |shift: u32| ((packed >> shift) & 0xff) as f32 / 255.0;
+            stops.push(ColorStop {
+                position: i.rem_euclid(400) as f32 / 400.0,
+                color: [channel(0), channel(8), channel(16)],
+            });
+        }
+    }
+    stops.sort_by(|a, b| a.position.total_cmp(&b.position));
+    Ok(stops)
+}
+
+// GIMP: a header, then one line per segment: "left middle right" positions, the
+// RGBA colors at either end and the blending and coloring types. Each segment
+// becomes stops at its ends and off-center midpoint, blended linearly in RGB.
+fn parse_ggr(text: &str) -> Result<Vec<ColorStop>, String> {
+    let mut lines = text.lines();
+    if lines.next().map(str::trim) != Some("GIMP Gradient") {
+        return Err("Missing the \"GIMP Gradient\" header".to_owned());
+    }
+    let mut lines = lines.skip_while(|line| line.starts_with("Name:"));
+    let count: usize = lines
+        .next()
+        .and_then(|line| line.trim().parse().ok())
+        .ok_or("Missing the segment count")?;
+    let mut stops: Vec<ColorStop> = Vec::new();
+    for n in 0..count {
+        let line = lines.next().ok_or(format!("Expected {} segments, found {}", count, n))?;
+        let values: Vec<f32> = line
+            .split_whitespace()
+            .take(11)
+            .map(|s| s.parse::<f32>())
+            .collect::<Result<_, _>>()
+            .map_err(|_| format!("Segment {}: expected numbers", n + 1))?;
+        let [left, middle, right, r0, g0, b0, _, r1, g1, b1, _] = values[..] else {
+            return Err(format!("Segment {}: expected at least 11 values", n + 1));
+        };
+        let (from, to) = ([r0, g0, b0], [r1, g1, b1]);
+        let mid = std::array::from_fn(|c| (from[c] + to[c]) / 2.0);
+        // A centered midpoint is already a straight blend and needs no stop of its own.
+        let skewed = (middle - (left + right) / 2.0).abs() > 1e-4;
+        let segment = [
+            Some(ColorStop { position: left, color: from }),
+            skewed.then_some(ColorStop { position: middle, color: mid }),
+            Some(ColorStop { position: right, color: to }),
+        ];
+        for stop in segment.into_iter().flatten() {
+            // Neighbouring segments usually share their boundary color.
+            if stops.last() != Some(&stop) {
+                stops.push(stop);
+            }
+        }
+    }
+    Ok(stops)
+}
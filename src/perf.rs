@@ -0,0 +1,76 @@
+use egui::Color32;
+use parking_lot::Mutex;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+#[derive(Clone, Copy)]
+pub struct TileTiming {
+    // First buffer row of the tile and how many rows it covers.
+    pub y: u32,
+    pub rows: u32,
+    pub millis: f64,
+}
+
+// Shared with the renderer of one preview, which may run on another thread; records
+// nothing unless it was handed out by PerfOverlay::begin.
+#[derive(Clone, Default)]
+pub struct TileRecorder(Arc<Mutex<Option<Vec<TileTiming>>>>);
+
+impl TileRecorder {
+    pub fn record(&self, timing: TileTiming) {
+        if let Some(tiles) = self.0.lock().as_mut() {
+            tiles.push(timing);
+        }
+    }
+}
+
+// Timings are collected from the render workers while a preview renders. A tile
+// rendered several times (antialiasing passes, layers) accumulates its total. Each
+// preview gets a recorder of its own, so cancelled previews and other renders still
+// finishing tiles do not add to it.
+#[derive(Default)]
+pub struct PerfOverlay {
+    pub enabled: bool,
+    pub recorder: TileRecorder,
+    pub tiles: Vec<TileTiming>,
+    pub buffer_height: u32,
+}
+
+impl PerfOverlay {
+    // The recorder to give the renderer of the preview being started.
+    pub fn begin(&mut self) -> TileRecorder {
+        let tiles = self.enabled.then(Vec::new);
+        self.recorder = TileRecorder(Arc::new(Mutex::new(tiles)));
+        self.recorder.clone()
+    }
+
+    pub fn finish(&mut self, buffer_height: u32) {
+        let Some(recorded) = self.recorder.0.lock().take() else {
+            return;
+        };
+        let mut merged: BTreeMap<u32, TileTiming> = BTreeMap::new();
+        for timing in recorded {
+            merged
+                .entry(timing.y)
+                .and_modify(|t| t.millis += timing.millis)
+                .or_insert(timing);
+        }
+        self.tiles = merged.into_values().collect();
+        self.buffer_height = buffer_height;
+    }
+
+    pub fn total_millis(&self) -> f64 {
+        self.tiles.iter().map(|t| t.millis).sum()
+    }
+
+    pub fn slowest_millis(&self) -> f64 {
+        self.tiles.iter().map(|t| t.millis).fold(0.0, f64::max)
+    }
+}
+
+// Green for the fastest tiles through yellow to red for the slowest.
+pub fn heat_color(t: f32) -> Color32 {
+    let t = t.clamp(0.0, 1.0);
+    let (r, g) = if t < 0.5 { (t * 2.0, 1.0) } else { (1.0, 2.0 - t * 2.0) };
+    Color32::from_rgba_unmultiplied((r * 255.0) as u8, (g * 255.0) as u8, 0, 90)
+}
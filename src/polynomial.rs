@@ -0,0 +1,142 @@
+use num_complex::Complex64;
+
+// Root-finding iterations, by order of convergence: secant ~1.6, Newton 2,
+// Halley 3, Householder (third order in the family) 4.
+#[derive(Clone, Copy, PartialEq)]
+pub enum RootMethod {
+    Newton,
+    Halley,
+    Householder,
+    Secant,
+}
+
+impl RootMethod {
+    pub const ALL: [RootMethod; 4] = [RootMethod::Newton, RootMethod::Halley, RootMethod::Householder, RootMethod::Secant];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RootMethod::Newton => "Newton",
+            RootMethod::Halley => "Halley",
+            RootMethod::Householder => "Householder",
+            RootMethod::Secant => "Secant",
+        }
+    }
+
+    pub fn order(&self) -> f64 {
+        match self {
+            RootMethod::Newton => 2.0,
+            RootMethod::Halley => 3.0,
+            RootMethod::Householder => 4.0,
+            RootMethod::Secant => 1.618,
+        }
+    }
+}
+
+// Monic polynomial kept in factored form; Newton's method and basin coloring both
+// need the roots, and coefficient edits are converted back with Durand-Kerner.
+#[derive(Clone, PartialEq)]
+pub struct Polynomial {
+    pub roots: Vec<Complex64>,
+}
+
+impl Default for Polynomial {
+    fn default() -> Self {
+        Self::roots_of_unity(3)
+    }
+}
+
+impl Polynomial {
+    // z^n - 1
+    pub fn roots_of_unity(n: usize) -> Self {
+        let roots = (0..n)
+            .map(|k| Complex64::from_polar(1.0, std::f64::consts::TAU * k as f64 / n as f64))
+            .collect();
+        Self { roots }
+    }
+
+    pub fn degree(&self) -> usize {
+        self.roots.len()
+    }
+
+    // Lowest degree first, ending with the leading 1.
+    pub fn coefficients(&self) -> Vec<Complex64> {
+        let mut coeffs = vec![Complex64::new(1.0, 0.0)];
+        for &root in &self.roots {
+            let mut next = vec![Complex64::new(0.0, 0.0); coeffs.len() + 1];
+            for (i, &a) in coeffs.iter().enumerate() {
+                next[i + 1] += a;
+                next[i] -= a * root;
+            }
+            coeffs = next;
+        }
+        coeffs
+    }
+
+    // `coeffs` is lowest degree first; the last entry is treated as the leading 1.
+    pub fn from_coefficients(coeffs: &[Complex64]) -> Self {
+        let degree = coeffs.len().saturating_sub(1);
+        let eval = |z: Complex64| {
+            coeffs[..degree]
+                .iter()
+                .rev()
+                .fold(Complex64::new(1.0, 0.0), |acc, &a| acc * z + a)
+        };
+
+        let seed = Complex64::new(0.4, 0.9);
+        let mut roots: Vec<Complex64> = (0..degree).map(|k| seed.powu(k as u32)).collect();
+        for _ in 0..500 {
+            let mut moved = 0.0f64;
+            for i in 0..degree {
+                let denom = roots
+                    .iter()
+                    .enumerate()
+                    .filter(|&(j, _)| j != i)
+                    .fold(Complex64::new(1.0, 0.0), |acc, (_, &r)| acc * (roots[i] - r));
+                let step = eval(roots[i]) / denom;
+                roots[i] -= step;
+                moved = moved.max(step.norm());
+            }
+            if moved < 1e-14 {
+                break;
+            }
+        }
+        Self { roots }
+    }
+
+    // z - p(z)/p'(z), using p'/p = sum of 1/(z - r).
+    pub fn newton_step(&self, z: Complex64) -> Complex64 {
+        let sum: Complex64 = self.roots.iter().map(|&r| (z - r).inv()).sum();
+        z - sum.inv()
+    }
+
+    pub fn eval(&self, z: Complex64) -> Complex64 {
+        self.roots.iter().fold(Complex64::new(1.0, 0.0), |acc, &r| acc * (z - r))
+    }
+
+    // One step of `method` from z; `prev` is the secant's second point. With the
+    // power sums s_k = sum of 1/(z - r)^k, p'/p = s1 and p''/p = s1^2 - s2.
+    pub fn root_step(&self, method: RootMethod, z: Complex64, prev: Complex64) -> Complex64 {
+        let power_sums = |k: i32| -> Complex64 { self.roots.iter().map(|&r| (z - r).powi(-k)).sum() };
+        match method {
+            RootMethod::Newton => self.newton_step(z),
+            RootMethod::Halley => {
+                let (s1, s2) = (power_sums(1), power_sums(2));
+                z - s1 * 2.0 / (s1 * s1 + s2)
+            }
+            RootMethod::Householder => {
+                let (s1, s2, s3) = (power_sums(1), power_sums(2), power_sums(3));
+                z - (s1 * s1 + s2) * 3.0 / (s1 * s1 * s1 + s1 * s2 * 3.0 + s3 * 2.0)
+            }
+            // The first step has no second point and falls back to Newton.
+            RootMethod::Secant if z == prev => self.newton_step(z),
+            RootMethod::Secant => {
+                let (p, q) = (self.eval(z), self.eval(prev));
+                z - p * (z - prev) / (p - q)
+            }
+        }
+    }
+
+    pub fn root_near(&self, z: Complex64, tolerance: f64) -> Option<usize> {
+        self.roots.iter().position(|&r| (z - r).norm_sqr() < tolerance * tolerance)
+    }
+}
@@ -0,0 +1,346 @@
+use crate::background::TransparentRegion;
+use crate::color::ColorProfile;
+use image::{ImageBuffer, Rgb, Rgba};
+use rayon::prelude::*;
+
+// Unclamped linear-light colors straight out of the coloring stage, before grading and quantization.
+// Alpha is coverage: interior points render black with zero alpha so layers can see through.
+#[derive(Clone)]
+pub struct ColorBuffer {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<[f32; 3]>,
+    pub alpha: Vec<f32>,
+}
+
+impl ColorBuffer {
+    pub fn new(width: u32, height: u32) -> Self {
+        let len = (width * height) as usize;
+        Self { width, height, pixels: vec![[0.0; 3]; len], alpha: vec![1.0; len] }
+    }
+
+    pub fn to_image(&self) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+        self.to_image_in(ColorProfile::Srgb, Dither::None)
+    }
+
+    pub fn to_image_in(&self, profile: ColorProfile, dither: Dither) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+        let raw = (0..self.pixels.len()).flat_map(|i| self.quantize(i, profile, dither)).collect();
+        ImageBuffer::from_raw(self.width, self.height, raw).unwrap()
+    }
+
+    // With `transparent` cut away, going by the coverage the coloring stage left in alpha.
+    pub fn to_rgba_in(
+        &self,
+        profile: ColorProfile,
+        dither: Dither,
+        transparent: TransparentRegion,
+    ) -> ImageBuffer<Rgba<u8>, Vec<u8>> {
+        let raw = (0..self.pixels.len())
+            .flat_map(|i| {
+                let [r, g, b] = self.quantize(i, profile, dither);
+                let coverage = self.alpha[i].clamp(0.0, 1.0);
+                let alpha = match transparent {
+                    TransparentRegion::Interior => coverage,
+                    TransparentRegion::Exterior => 1.0 - coverage,
+                };
+                [r, g, b, (alpha * 255.0).round() as u8]
+            })
+            .collect();
+        ImageBuffer::from_raw(self.width, self.height, raw).unwrap()
+    }
+
+    // 8-bit color of pixel `i`, encoded for `profile`.
+    fn quantize(&self, i: usize, profile: ColorProfile, dither: Dither) -> [u8; 3] {
+        let width = self.width.max(1) as usize;
+        let offset = dither.offset((i % width) as u32, (i / width) as u32);
+        profile.encode(self.pixels[i]).map(|c| (c * 255.0 + offset).round().clamp(0.0, 255.0) as u8)
+    }
+
+    // Copies `patch` into this buffer with its top-left corner at (x, y).
+    pub fn paste(&mut self, patch: &ColorBuffer, x: u32, y: u32) {
+        let width = patch.width.min(self.width.saturating_sub(x)) as usize;
+        for row in 0..patch.height.min(self.height.saturating_sub(y)) {
+            let src = (row * patch.width) as usize;
+            let dst = ((y + row) * self.width + x) as usize;
+            self.pixels[dst..dst + width].copy_from_slice(&patch.pixels[src..src + width]);
+            self.alpha[dst..dst + width].copy_from_slice(&patch.alpha[src..src + width]);
+        }
+    }
+}
+
+// Threshold noise added before rounding to 8 bits, which trades the flat steps of
+// a slow gradient for fine grain the eye averages back into a smooth ramp.
+#[derive(Clone, Copy, PartialEq)]
+pub enum Dither {
+    None,
+    // 8x8 Bayer matrix: cheap and regular, with a faint crosshatch.
+    Ordered,
+    // Interleaved gradient noise, whose energy sits at high frequencies like blue noise.
+    BlueNoise,
+}
+
+impl Dither {
+    pub const ALL: [Dither; 3] = [Dither::None, Dither::Ordered, Dither::BlueNoise];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Dither::None => "None",
+            Dither::Ordered => "Ordered",
+            Dither::BlueNoise => "Blue noise",
+        }
+    }
+
+    // Offset in 8-bit steps, in [-0.5, 0.5), for the pixel at (x, y).
+    fn offset(&self, x: u32, y: u32) -> f32 {
+        match self {
+            Dither::None => 0.0,
+            Dither::Ordered => {
+                // Each bit pair of (x ^ y, y) picks a quadrant; low bits are the coarsest level.
+                let rank = (0..3).fold(0, |rank, bit| {
+                    (rank << 2) | (((x ^ y) >> bit & 1) << 1) | (y >> bit & 1)
+                });
+                (rank as f32 + 0.5) / 64.0 - 0.5
+            }
+            Dither::BlueNoise => {
+                (52.982_92 * (0.067_110_56 * x as f32 + 0.005_837_15 * y as f32).fract()).fract() - 0.5
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct PostSettings {
+    pub exposure: f32,
+    pub temperature: f32,
+    pub tint: f32,
+    pub saturation: f32,
+    // Saturation boost that favors muted colors, so already vivid ones do not clip.
+    pub vibrance: f32,
+    // Slope around linear middle gray; above 1 darkens shadows and lifts highlights.
+    pub contrast: f32,
+    pub brightness: f32,
+    pub gamma: f32,
+    pub vignette: f32,
+    pub bloom_intensity: f32,
+    // Luminance above which pixels glow; lower values spread the glow to dimmer filaments.
+    pub bloom_threshold: f32,
+    // Blur radius as a percentage of image height, so exports match the preview.
+    pub bloom_radius: f32,
+    pub sharpen_amount: f32,
+    pub denoise: f32,
+    // Applied only when quantizing, so it does not affect the graded buffer.
+    pub dither: Dither,
+}
+
+impl Default for PostSettings {
+    fn default() -> Self {
+        Self {
+            exposure: 0.0,
+            temperature: 0.0,
+            tint: 0.0,
+            saturation: 1.0,
+            vibrance: 0.0,
+            contrast: 1.0,
+            brightness: 0.0,
+            gamma: 1.0,
+            vignette: 0.0,
+            bloom_intensity: 0.0,
+            bloom_threshold: 0.8,
+            bloom_radius: 1.0,
+            sharpen_amount: 0.0,
+            denoise: 0.0,
+            dither: Dither::None,
+        }
+    }
+}
+
+// Linear value of 18% gray, the pivot contrast turns around.
+const MIDDLE_GRAY: f32 = 0.18;
+
+pub fn luma(c: [f32; 3]) -> f32 {
+    0.2126 * c[0] + 0.7152 * c[1] + 0.0722 * c[2]
+}
+
+// Denoise and sharpen spreads as fractions of image height, so exports match the preview.
+const DENOISE_SIGMA: f32 = 0.001;
+const SHARPEN_SIGMA: f32 = 0.001;
+
+pub fn apply(buffer: &ColorBuffer, settings: &PostSettings) -> ColorBuffer {
+    let grading = PostSettings { dither: Dither::None, ..*settings };
+    if grading == PostSettings::default() || buffer.pixels.is_empty() {
+        return buffer.clone();
+    }
+
+    let mut out = if settings.denoise > 0.0 {
+        let sigma = DENOISE_SIGMA * buffer.height as f32;
+        denoise(buffer, settings.denoise, sigma.max(0.5))
+    } else {
+        buffer.clone()
+    };
+
+    let gain = 2f32.powf(settings.exposure);
+    // Warm shifts red up and blue down; positive tint pushes toward magenta.
+    let balance = [
+        gain * (1.0 + 0.2 * settings.temperature),
+        gain * (1.0 - 0.2 * settings.tint),
+        gain * (1.0 - 0.2 * settings.temperature),
+    ];
+
+    out.pixels.par_iter_mut().for_each(|pixel| {
+        let mut c: [f32; 3] = std::array::from_fn(|ch| pixel[ch] * balance[ch]);
+        let l = luma(c);
+        let (low, high) = (c[0].min(c[1]).min(c[2]), c[0].max(c[1]).max(c[2]));
+        let chroma = if high > 0.0 { (high - low) / high } else { 0.0 };
+        let saturation = settings.saturation * (1.0 + settings.vibrance * (1.0 - chroma));
+        for value in &mut c {
+            *value = l + (*value - l) * saturation;
+            *value = MIDDLE_GRAY * (value.max(0.0) / MIDDLE_GRAY).powf(settings.contrast);
+            *value = (*value + settings.brightness).max(0.0).powf(1.0 / settings.gamma);
+        }
+        *pixel = c;
+    });
+
+    if settings.bloom_intensity > 0.0 {
+        let sigma = settings.bloom_radius / 100.0 * out.height as f32;
+        bloom(&mut out, settings.bloom_threshold, sigma, settings.bloom_intensity);
+    }
+
+    if settings.sharpen_amount > 0.0 {
+        let sigma = SHARPEN_SIGMA * out.height as f32;
+        sharpen(&mut out, settings.sharpen_amount, sigma.max(0.5));
+    }
+
+    if settings.vignette > 0.0 {
+        vignette(&mut out, settings.vignette);
+    }
+    out
+}
+
+// Bright-pass, separable Gaussian blur, then additive composite back onto the image.
+fn bloom(buffer: &mut ColorBuffer, threshold: f32, sigma: f32, intensity: f32) {
+    let bright: Vec<[f32; 3]> = buffer
+        .pixels
+        .par_iter()
+        .map(|&c| {
+            let l = luma(c);
+            if l > threshold {
+                c.map(|v| v * (l - threshold) / l)
+            } else {
+                [0.0; 3]
+            }
+        })
+        .collect();
+
+    let kernel = gaussian_kernel(sigma.max(0.5));
+    let width = buffer.width as usize;
+    let height = buffer.height as usize;
+    let horizontal = blur_pass(&bright, width, height, &kernel, true);
+    let blurred = blur_pass(&horizontal, width, height, &kernel, false);
+
+    buffer.pixels.par_iter_mut().zip(blurred).for_each(|(pixel, glow)| {
+        for (v, g) in pixel.iter_mut().zip(glow) {
+            *v += g * intensity;
+        }
+    });
+}
+
+fn gaussian_kernel(sigma: f32) -> Vec<f32> {
+    let radius = (sigma * 3.0).ceil() as i32;
+    let weights: Vec<f32> = (-radius..=radius)
+        .map(|k| (-(k * k) as f32 / (2.0 * sigma * sigma)).exp())
+        .collect();
+    let total: f32 = weights.iter().sum();
+    weights.into_iter().map(|w| w / total).collect()
+}
+
+fn blur_pass(src: &[[f32; 3]], width: usize, height: usize, kernel: &[f32], horizontal: bool) -> Vec<[f32; 3]> {
+    let radius = (kernel.len() / 2) as isize;
+    let mut out = vec![[0.0; 3]; src.len()];
+    out.par_chunks_mut(width).enumerate().for_each(|(y, row)| {
+        for (x, pixel) in row.iter_mut().enumerate() {
+            let mut sum = [0.0; 3];
+            for (k, weight) in kernel.iter().enumerate() {
+                let offset = k as isize - radius;
+                let (sx, sy) = if horizontal {
+                    ((x as isize + offset).clamp(0, width as isize - 1) as usize, y)
+                } else {
+                    (x, (y as isize + offset).clamp(0, height as isize - 1) as usize)
+                };
+                let sample = src[sy * width + sx];
+                for (s, v) in sum.iter_mut().zip(sample) {
+                    *s += v * weight;
+                }
+            }
+            *pixel = sum;
+        }
+    });
+    out
+}
+
+// Unsharp mask: push each pixel away from a slightly blurred copy of itself.
+fn sharpen(buffer: &mut ColorBuffer, amount: f32, sigma: f32) {
+    let kernel = gaussian_kernel(sigma);
+    let width = buffer.width as usize;
+    let height = buffer.height as usize;
+    let horizontal = blur_pass(&buffer.pixels, width, height, &kernel, true);
+    let blurred = blur_pass(&horizontal, width, height, &kernel, false);
+
+    buffer.pixels.par_iter_mut().zip(blurred).for_each(|(pixel, soft)| {
+        for (v, s) in pixel.iter_mut().zip(soft) {
+            *v += (*v - s) * amount;
+        }
+    });
+}
+
+// Bilateral filter: averages neighbours weighted by both distance and color similarity,
+// which smooths sample noise without washing out filament edges.
+fn denoise(buffer: &ColorBuffer, strength: f32, spatial_sigma: f32) -> ColorBuffer {
+    let width = buffer.width as i32;
+    let height = buffer.height as i32;
+    let range_sigma = 0.02 + 0.2 * strength;
+    // Weights past two sigma are too small to change the result.
+    let radius = (spatial_sigma * 2.0).ceil() as i32;
+    let src = &buffer.pixels;
+
+    let mut out = buffer.clone();
+    out.pixels.par_chunks_mut(width as usize).enumerate().for_each(|(y, row)| {
+        let y = y as i32;
+        for (x, pixel) in row.iter_mut().enumerate() {
+            let x = x as i32;
+            let center = src[(y * width + x) as usize];
+            let mut sum = [0.0; 3];
+            let mut total = 0.0;
+            for dy in -radius..=radius {
+                for dx in -radius..=radius {
+                    let sx = (x + dx).clamp(0, width - 1);
+                    let sy = (y + dy).clamp(0, height - 1);
+                    let sample = src[(sy * width + sx) as usize];
+                    let diff: f32 = sample.iter().zip(&center).map(|(a, b)| (a - b) * (a - b)).sum();
+                    let spatial = ((dx * dx + dy * dy) as f32) / (2.0 * spatial_sigma * spatial_sigma);
+                    let weight = (-spatial - diff / (2.0 * range_sigma * range_sigma)).exp();
+                    for (s, v) in sum.iter_mut().zip(sample) {
+                        *s += v * weight;
+                    }
+                    total += weight;
+                }
+            }
+            *pixel = sum.map(|s| s / total);
+        }
+    });
+    out
+}
+
+fn vignette(buffer: &mut ColorBuffer, strength: f32) {
+    let width = buffer.width;
+    let (cx, cy) = (buffer.width as f32 / 2.0, buffer.height as f32 / 2.0);
+    let max_radius = (cx * cx + cy * cy).sqrt().max(1.0);
+    buffer.pixels.par_iter_mut().enumerate().for_each(|(i, pixel)| {
+        let x = (i as u32 % width) as f32 + 0.5 - cx;
+        let y = (i as u32 / width) as f32 + 0.5 - cy;
+        let r = (x * x + y * y).sqrt() / max_radius;
+        let falloff = (1.0 - strength * r * r).max(0.0);
+        for value in pixel.iter_mut() {
+            *value *= falloff;
+        }
+    });
+}
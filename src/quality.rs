@@ -0,0 +1,127 @@
+use crate::antialias::{AntialiasSettings, SamplePattern};
+
+// Fraction of the preview resolution, each way, frames render at while the view is
+// being dragged or zoomed.
+const INTERACTIVE_SCALE: f32 = 0.5;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Precision {
+    // Orbits are rounded to f32 every step, as a 32-bit float kernel would compute them.
+    Single,
+    Double,
+}
+
+impl Precision {
+    pub const ALL: [Precision; 2] = [Precision::Single, Precision::Double];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Precision::Single => "Single (f32)",
+            Precision::Double => "Double (f64)",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum QualityPreset {
+    Draft,
+    Normal,
+    High,
+    Ultra,
+}
+
+impl QualityPreset {
+    pub const ALL: [QualityPreset; 4] = [
+        QualityPreset::Draft,
+        QualityPreset::Normal,
+        QualityPreset::High,
+        QualityPreset::Ultra,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            QualityPreset::Draft => "Draft",
+            QualityPreset::Normal => "Normal",
+            QualityPreset::High => "High",
+            QualityPreset::Ultra => "Ultra",
+        }
+    }
+
+    pub fn settings(&self) -> QualitySettings {
+        match self {
+            QualityPreset::Draft => QualitySettings {
+                preview_scale: 0.5,
+                antialias: AntialiasSettings::default(),
+                iteration_multiplier: 0.5,
+                precision: Precision::Single,
+            },
+            QualityPreset::Normal => QualitySettings {
+                preview_scale: 1.0,
+                antialias: AntialiasSettings::default(),
+                iteration_multiplier: 1.0,
+                precision: Precision::Double,
+            },
+            QualityPreset::High => QualitySettings {
+                preview_scale: 1.0,
+                antialias: AntialiasSettings {
+                    pattern: SamplePattern::RotatedGrid,
+                    samples: 4,
+                    ..AntialiasSettings::default()
+                },
+                iteration_multiplier: 1.5,
+                precision: Precision::Double,
+            },
+            QualityPreset::Ultra => QualitySettings {
+                preview_scale: 1.0,
+                antialias: AntialiasSettings {
+                    pattern: SamplePattern::BlueNoise,
+                    samples: 16,
+                    adaptive: true,
+                    ..AntialiasSettings::default()
+                },
+                iteration_multiplier: 2.0,
+                precision: Precision::Double,
+            },
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct QualitySettings {
+    // Fraction of the window resolution the live view renders at; exports ignore it.
+    pub preview_scale: f32,
+    pub antialias: AntialiasSettings,
+    pub iteration_multiplier: f64,
+    pub precision: Precision,
+}
+
+impl Default for QualitySettings {
+    fn default() -> Self {
+        QualityPreset::Normal.settings()
+    }
+}
+
+impl QualitySettings {
+    // The preset these settings match exactly, or None once an advanced knob was changed.
+    pub fn preset(&self) -> Option<QualityPreset> {
+        QualityPreset::ALL.into_iter().find(|p| p.settings() == *self)
+    }
+
+    pub fn scale_iterations(&self, max_iter: u32) -> u32 {
+        ((max_iter as f64 * self.iteration_multiplier).round() as u32).max(1)
+    }
+
+    // Quarter-resolution, single-sample settings for frames rendered mid-interaction.
+    pub fn interactive(&self) -> Self {
+        Self {
+            preview_scale: self.preview_scale * INTERACTIVE_SCALE,
+            antialias: AntialiasSettings { samples: 1, ..self.antialias },
+            ..*self
+        }
+    }
+
+    pub fn preview_size(&self, width: u32, height: u32) -> (u32, u32) {
+        let scale = |v: u32| ((v as f32 * self.preview_scale).round() as u32).max(1);
+        (scale(width), scale(height))
+    }
+}
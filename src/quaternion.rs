@@ -0,0 +1,82 @@
+use crate::raymarch::{DistanceEstimator, V3};
+use std::ops::{Add, Mul};
+
+// A larger bailout than the 2D kernels keeps the distance estimate accurate.
+const BAILOUT: f64 = 4.0;
+
+#[derive(Clone, Copy)]
+pub struct Quat {
+    pub r: f64,
+    pub i: f64,
+    pub j: f64,
+    pub k: f64,
+}
+
+impl Quat {
+    pub const fn new(r: f64, i: f64, j: f64, k: f64) -> Self {
+        Self { r, i, j, k }
+    }
+
+    pub fn norm(self) -> f64 {
+        (self.r * self.r + self.i * self.i + self.j * self.j + self.k * self.k).sqrt()
+    }
+}
+
+impl Add for Quat {
+    type Output = Quat;
+    fn add(self, o: Quat) -> Quat {
+        Quat::new(self.r + o.r, self.i + o.i, self.j + o.j, self.k + o.k)
+    }
+}
+
+impl Mul for Quat {
+    type Output = Quat;
+    fn mul(self, o: Quat) -> Quat {
+        Quat::new(
+            self.r * o.r - self.i * o.i - self.j * o.j - self.k * o.k,
+            self.r * o.i + self.i * o.r + self.j * o.k - self.k * o.j,
+            self.r * o.j - self.i * o.k + self.j * o.r + self.k * o.i,
+            self.r * o.k + self.i * o.j - self.j * o.i + self.k * o.r,
+        )
+    }
+}
+
+impl Mul<f64> for Quat {
+    type Output = Quat;
+    fn mul(self, s: f64) -> Quat {
+        Quat::new(self.r * s, self.i * s, self.j * s, self.k * s)
+    }
+}
+
+// Julia set of q -> q^2 + c over the quaternions, cut to 3D at a fixed k.
+#[derive(Clone)]
+pub struct QuaternionJulia {
+    pub c: Quat,
+    pub slice: f64,
+    pub iterations: u32,
+}
+
+impl Default for QuaternionJulia {
+    fn default() -> Self {
+        Self { c: Quat::new(-0.2, 0.8, 0.0, 0.0), slice: 0.0, iterations: 12 }
+    }
+}
+
+impl DistanceEstimator for QuaternionJulia {
+    fn estimate(&self, p: V3) -> (f64, f64) {
+        let mut q = Quat::new(p.x, p.y, p.z, self.slice);
+        let mut dq = Quat::new(1.0, 0.0, 0.0, 0.0);
+        let mut r = q.norm();
+        let mut trap = r;
+        for _ in 0..self.iterations {
+            if r > BAILOUT {
+                break;
+            }
+            dq = q * dq * 2.0;
+            q = q * q + self.c;
+            r = q.norm();
+            trap = trap.min(r);
+        }
+        (0.5 * r * r.ln() / dq.norm(), trap)
+    }
+}
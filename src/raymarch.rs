@@ -0,0 +1,188 @@
+use crate::tiles::CancelToken;
+use rayon::prelude::*;
+use std::f64::consts::{PI, TAU};
+use std::ops::{Add, Mul, Sub};
+
+#[derive(Clone, Copy)]
+pub struct V3 {
+    pub x: f64,
+    pub y: f64,
+    pub z: f64,
+}
+
+impl V3 {
+    pub const fn new(x: f64, y: f64, z: f64) -> Self {
+        Self { x, y, z }
+    }
+
+    pub fn dot(self, other: V3) -> f64 {
+        self.x * other.x + self.y * other.y + self.z * other.z
+    }
+
+    pub fn cross(self, other: V3) -> V3 {
+        V3::new(
+            self.y * other.z - self.z * other.y,
+            self.z * other.x - self.x * other.z,
+            self.x * other.y - self.y * other.x,
+        )
+    }
+
+    pub fn length(self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    pub fn normalized(self) -> V3 {
+        self * (1.0 / self.length())
+    }
+}
+
+impl Add for V3 {
+    type Output = V3;
+    fn add(self, o: V3) -> V3 {
+        V3::new(self.x + o.x, self.y + o.y, self.z + o.z)
+    }
+}
+
+impl Sub for V3 {
+    type Output = V3;
+    fn sub(self, o: V3) -> V3 {
+        V3::new(self.x - o.x, self.y - o.y, self.z - o.z)
+    }
+}
+
+impl Mul<f64> for V3 {
+    type Output = V3;
+    fn mul(self, s: f64) -> V3 {
+        V3::new(self.x * s, self.y * s, self.z * s)
+    }
+}
+
+// A lower bound on the distance from `p` to the surface, plus an orbit-trap value
+// the shading can color by.
+pub trait DistanceEstimator: Sync {
+    fn estimate(&self, p: V3) -> (f64, f64);
+}
+
+// Orbits the origin; yaw and pitch in radians.
+#[derive(Clone, Copy)]
+pub struct Camera {
+    pub yaw: f64,
+    pub pitch: f64,
+    pub distance: f64,
+    pub fov_degrees: f64,
+}
+
+impl Default for Camera {
+    fn default() -> Self {
+        Self { yaw: 0.6, pitch: 0.4, distance: 3.0, fov_degrees: 45.0 }
+    }
+}
+
+impl Camera {
+    pub fn position(&self) -> V3 {
+        V3::new(
+            self.pitch.cos() * self.yaw.sin(),
+            self.pitch.sin(),
+            self.pitch.cos() * self.yaw.cos(),
+        ) * self.distance
+    }
+
+    // Yaw wraps to [-π, π); pitch stops short of the poles, where the view's up
+    // vector is undefined.
+    pub fn orbit(&mut self, d_yaw: f64, d_pitch: f64) {
+        self.yaw = (self.yaw + d_yaw + PI).rem_euclid(TAU) - PI;
+        self.pitch = (self.pitch + d_pitch).clamp(-1.5, 1.5);
+    }
+}
+
+#[derive(Clone)]
+pub struct Scene3d {
+    pub camera: Camera,
+    pub light_yaw: f64,
+    pub light_pitch: f64,
+    pub max_steps: u32,
+    // Hit threshold as a fraction of a pixel's footprint; lower traces finer detail.
+    pub detail: f64,
+}
+
+impl Default for Scene3d {
+    fn default() -> Self {
+        Self { camera: Camera::default(), light_yaw: 0.8, light_pitch: 0.9, max_steps: 160, detail: 0.5 }
+    }
+}
+
+// What a primary ray saw, for the caller to color.
+#[derive(Clone, Copy)]
+pub struct Surface {
+    pub brightness: f32,
+    pub trap: f64,
+}
+
+impl Scene3d {
+    fn light_direction(&self) -> V3 {
+        V3::new(
+            self.light_pitch.cos() * self.light_yaw.sin(),
+            self.light_pitch.sin(),
+            self.light_pitch.cos() * self.light_yaw.cos(),
+        )
+    }
+
+    // Sphere-traces one ray per pixel; None where the ray leaves the scene. Rows
+    // started after `cancel` is set are left empty.
+    pub fn render(&self, field: &impl DistanceEstimator, width: u32, height: u32, cancel: &CancelToken) -> Vec<Option<Surface>> {
+        let eye = self.camera.position();
+        let forward = (V3::new(0.0, 0.0, 0.0) - eye).normalized();
+        let right = forward.cross(V3::new(0.0, 1.0, 0.0)).normalized();
+        let up = right.cross(forward);
+        let half = (self.camera.fov_degrees.to_radians() / 2.0).tan();
+        let aspect = width as f64 / height as f64;
+        // Angular size of one pixel, so the hit threshold grows with distance.
+        let pixel_angle = 2.0 * half / height as f64;
+        let far = self.camera.distance + 4.0;
+        let light = self.light_direction();
+
+        (0..height)
+            .into_par_iter()
+            .flat_map_iter(|y| {
+                let cancelled = cancel.is_cancelled();
+                (0..width).map(move |x| {
+                    if cancelled {
+                        return None;
+                    }
+                    let u = (2.0 * (x as f64 + 0.5) / width as f64 - 1.0) * half * aspect;
+                    let v = (1.0 - 2.0 * (y as f64 + 0.5) / height as f64) * half;
+                    let dir = (forward + right * u + up * v).normalized();
+                    self.march(field, eye, dir, pixel_angle, far, light)
+                })
+            })
+            .collect()
+    }
+
+    fn march(&self, field: &impl DistanceEstimator, eye: V3, dir: V3, pixel_angle: f64, far: f64, light: V3) -> Option<Surface> {
+        let mut t = 0.0;
+        for step in 0..self.max_steps {
+            let p = eye + dir * t;
+            let (distance, trap) = field.estimate(p);
+            let threshold = (t * pixel_angle * self.detail).max(1e-7);
+            if distance < threshold {
+                let normal = normal_at(field, p, threshold);
+                let diffuse = normal.dot(light).max(0.0);
+                // Rays that needed many steps grazed nearby surfaces: cheap ambient occlusion.
+                let occlusion = 1.0 - step as f64 / self.max_steps as f64;
+                let brightness = (0.15 + 0.85 * diffuse) * occlusion;
+                return Some(Surface { brightness: brightness as f32, trap });
+            }
+            t += distance;
+            if t > far {
+                return None;
+            }
+        }
+        None
+    }
+}
+
+// Gradient of the distance estimate by central differences.
+fn normal_at(field: &impl DistanceEstimator, p: V3, e: f64) -> V3 {
+    let d = |offset: V3| field.estimate(p + offset).0 - field.estimate(p - offset).0;
+    V3::new(d(V3::new(e, 0.0, 0.0)), d(V3::new(0.0, e, 0.0)), d(V3::new(0.0, 0.0, e))).normalized()
+}
@@ -0,0 +1,142 @@
+use num_complex::Complex64;
+use std::f64::consts::TAU;
+
+// Rays start on a circle of this radius, where the Böttcher map is close to identity.
+const ESCAPE_RADIUS: f64 = 65536.0;
+// Newton targets per halving of the potential; more keeps each step in its basin.
+const SHARPNESS: u32 = 8;
+const NEWTON_STEPS: usize = 16;
+// Equipotentials are swept with this many points per turn of the target phase.
+const POINTS_PER_TURN: usize = 32;
+const MAX_CURVE_POINTS: usize = 32768;
+
+pub struct RayOverlay {
+    pub enabled: bool,
+    // Angles in turns, comma separated; fractions like 1/3 are accepted.
+    pub angles: String,
+    pub depth: u32,
+    pub equipotentials: u32,
+    // Curves in the complex plane, rebuilt only when the settings change.
+    pub rays: Vec<Vec<Complex64>>,
+    pub curves: Vec<Vec<Complex64>>,
+    pub dirty: bool,
+}
+
+impl Default for RayOverlay {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            angles: "0, 1/3, 2/3, 1/7, 2/7, 4/7".to_string(),
+            depth: 32,
+            equipotentials: 6,
+            rays: Vec::new(),
+            curves: Vec::new(),
+            dirty: true,
+        }
+    }
+}
+
+impl RayOverlay {
+    pub fn rebuild(&mut self) {
+        self.rays = parse_angles(&self.angles)
+            .into_iter()
+            .map(|angle| external_ray(angle, self.depth))
+            .collect();
+        // Potentials 1, 1/2, 1/4, ... close in on the boundary.
+        self.curves = (0..self.equipotentials)
+            .filter_map(|k| equipotential(0.5f64.powi(k as i32)))
+            .collect();
+        self.dirty = false;
+    }
+}
+
+pub fn parse_angles(text: &str) -> Vec<f64> {
+    text.split(',')
+        .filter_map(|part| {
+            let part = part.trim();
+            match part.split_once('/') {
+                Some((num, den)) => {
+                    let num: f64 = num.trim().parse().ok()?;
+                    let den: f64 = den.trim().parse().ok()?;
+                    (den != 0.0).then(|| num / den)
+                }
+                None => part.parse().ok(),
+            }
+        })
+        .map(|angle| angle.rem_euclid(1.0))
+        .collect()
+}
+
+// Solves f_c^n(0) = target for c by Newton's method, starting from `c`.
+fn solve(mut c: Complex64, n: u32, target: Complex64) -> Option<Complex64> {
+    for _ in 0..NEWTON_STEPS {
+        let mut z = Complex64::new(0.0, 0.0);
+        let mut dz = Complex64::new(0.0, 0.0);
+        for _ in 0..n {
+            dz = z * dz * 2.0 + 1.0;
+            z = z * z + c;
+        }
+        let step = (z - target) / dz;
+        if !step.re.is_finite() || !step.im.is_finite() {
+            return None;
+        }
+        c -= step;
+        if step.norm() <= 1e-14 * c.norm().max(1.0) {
+            break;
+        }
+    }
+    Some(c)
+}
+
+// Radius of the Newton target at sub-step m of a depth, from R down to sqrt(R).
+fn target_radius(m: u32) -> f64 {
+    ESCAPE_RADIUS.powf(0.5f64.powf(m as f64 / SHARPNESS as f64))
+}
+
+// Walks the parameter ray of external angle `angle` inward. At depth n the point
+// c satisfies f_c^n(0) = r e^(2πi 2^(n-1) angle), which pins Φ(c) to the ray.
+pub fn external_ray(angle: f64, depth: u32) -> Vec<Complex64> {
+    let mut c = Complex64::from_polar(ESCAPE_RADIUS, TAU * angle);
+    let mut points = vec![c];
+    // Doubling mod 1 is exact in binary, so the angle survives ~50 depths.
+    let mut turn = angle;
+    for n in 1..=depth {
+        for m in 1..=SHARPNESS {
+            let target = Complex64::from_polar(target_radius(m), TAU * turn);
+            match solve(c, n, target) {
+                Some(next) => c = next,
+                None => return points,
+            }
+            points.push(c);
+        }
+        turn = (turn * 2.0).fract();
+    }
+    points
+}
+
+// The curve where the Green's function equals `potential`, i.e. |Φ(c)| = e^potential.
+pub fn equipotential(potential: f64) -> Option<Vec<Complex64>> {
+    // Pick the depth whose target radius e^(potential 2^(n-1)) lies in (sqrt(R), R].
+    let n = ((ESCAPE_RADIUS.ln() / potential).log2().floor().max(0.0) as u32) + 1;
+    let log_radius = potential * 2f64.powi(n as i32 - 1);
+
+    // Follow the zero ray to depth n, then ease the radius down to the curve.
+    let mut c = *external_ray(0.0, n - 1).last()?;
+    for m in 1..=SHARPNESS {
+        let t = m as f64 / SHARPNESS as f64;
+        let radius = (ESCAPE_RADIUS.ln() * (1.0 - t) + log_radius * t).exp();
+        c = solve(c, n, Complex64::new(radius, 0.0))?;
+    }
+
+    // The target phase winds 2^(n-1) times while the angle goes once around.
+    let windings = 1usize << (n - 1).min(20);
+    let count = (windings * POINTS_PER_TURN).min(MAX_CURVE_POINTS);
+    let mut points = Vec::with_capacity(count + 1);
+    points.push(c);
+    for i in 1..=count {
+        let phase = TAU * windings as f64 * i as f64 / count as f64;
+        c = solve(c, n, Complex64::from_polar(log_radius.exp(), phase))?;
+        points.push(c);
+    }
+    Some(points)
+}
@@ -0,0 +1,113 @@
+use crate::animation::Timeline;
+use crate::FractalState;
+use std::time::Instant;
+
+// A gap between changes longer than this is a pause, not a slow frame.
+const PAUSE: f64 = 0.1;
+
+#[derive(Default)]
+pub struct PathRecorder {
+    started: Option<Instant>,
+    samples: Vec<(f64, FractalState)>,
+}
+
+pub fn same_view(a: &FractalState, b: &FractalState) -> bool {
+    a.fractal_type == b.fractal_type
+        && a.zoom == b.zoom
+        && a.center_x == b.center_x
+        && a.center_y == b.center_y
+        && a.max_iter == b.max_iter
+        && a.power == b.power
+        && a.power_im == b.power_im
+        && a.secondary_param == b.secondary_param
+        && a.hue_offset == b.hue_offset
+        && a.saturation == b.saturation
+        && a.value == b.value
+        && a.palette == b.palette
+        && a.color_space == b.color_space
+        && a.interpolation == b.interpolation
+        && a.stripe_density == b.stripe_density
+        && a.external_angle == b.external_angle
+        && a.angle_by_escape == b.angle_by_escape
+        && a.interior_period == b.interior_period
+        && a.lighting == b.lighting
+        && a.texture == b.texture
+        && a.transfer == b.transfer
+        && a.waves == b.waves
+        && a.coloring == b.coloring
+        && a.trap == b.trap
+        && a.polynomial == b.polynomial
+        && a.root_method == b.root_method
+        && a.biomorph == b.biomorph
+        && a.cubic == b.cubic
+        && a.lyapunov_sequence == b.lyapunov_sequence
+        && a.julia_seed == b.julia_seed
+        && a.convergence_shading == b.convergence_shading
+        && a.julia_plane == b.julia_plane
+        && a.phoenix_p == b.phoenix_p
+        && a.formula == b.formula
+}
+
+impl PathRecorder {
+    pub fn start(&mut self) {
+        self.samples.clear();
+        self.started = Some(Instant::now());
+    }
+
+    pub fn stop(&mut self) {
+        self.started = None;
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.started.is_some()
+    }
+
+    pub fn sample_count(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.samples.last().map_or(0.0, |(t, _)| *t)
+    }
+
+    // Only changes are stored. When the view moves again after a pause, the paused
+    // view is stored again at the same moment, so playback holds it through the
+    // pause instead of drifting toward the next change.
+    pub fn record(&mut self, state: &FractalState) {
+        let Some(started) = self.started else {
+            return;
+        };
+        let now = started.elapsed().as_secs_f64();
+        if let Some((time, last)) = self.samples.last() {
+            if same_view(last, state) {
+                return;
+            }
+            if now - time > PAUSE {
+                self.samples.push((now, last.clone()));
+            }
+        }
+        self.samples.push((now, state.clone()));
+    }
+
+    pub fn to_timeline(&self) -> Timeline {
+        let mut timeline = Timeline::default();
+        for (time, state) in &self.samples {
+            timeline.push(*time, state.clone());
+        }
+        timeline
+    }
+
+    // Thins the capture to one keyframe per `interval` seconds for editing.
+    pub fn to_keyframes(&self, interval: f64) -> Timeline {
+        let mut timeline = Timeline::default();
+        let mut last_time = f64::NEG_INFINITY;
+        for (i, (time, state)) in self.samples.iter().enumerate() {
+            let is_last = i + 1 == self.samples.len();
+            if *time - last_time >= interval || is_last {
+                timeline.push(*time, state.clone());
+                last_time = *time;
+            }
+        }
+        timeline
+    }
+}
@@ -0,0 +1,44 @@
+use crate::antialias::AntialiasSettings;
+use crate::quality::{Precision, QualitySettings};
+use egui::{Pos2, Rect};
+
+pub struct RegionBoost {
+    pub enabled: bool,
+    // Screen-space rectangle relative to the image's top-left corner.
+    pub rect: Option<Rect>,
+    pub start: Option<Pos2>,
+    pub iteration_boost: f64,
+    pub samples: u32,
+}
+
+impl Default for RegionBoost {
+    fn default() -> Self {
+        Self { enabled: false, rect: None, start: None, iteration_boost: 4.0, samples: 16 }
+    }
+}
+
+impl RegionBoost {
+    // The live quality with the boost layered on top; never lowers what is already set.
+    pub fn boosted(&self, quality: QualitySettings) -> QualitySettings {
+        QualitySettings {
+            iteration_multiplier: quality.iteration_multiplier * self.iteration_boost,
+            antialias: AntialiasSettings {
+                samples: self.samples.max(quality.antialias.samples),
+                ..quality.antialias
+            },
+            precision: Precision::Double,
+            ..quality
+        }
+    }
+
+    // The rectangle in buffer pixels as (x, y, width, height), where `scale` maps
+    // screen pixels to buffer pixels. None when the clipped area is empty.
+    pub fn pixel_bounds(&self, scale: f32, width: u32, height: u32) -> Option<(u32, u32, u32, u32)> {
+        let rect = self.rect?;
+        let x0 = (rect.min.x * scale).floor().clamp(0.0, width as f32) as u32;
+        let y0 = (rect.min.y * scale).floor().clamp(0.0, height as f32) as u32;
+        let x1 = (rect.max.x * scale).ceil().clamp(0.0, width as f32) as u32;
+        let y1 = (rect.max.y * scale).ceil().clamp(0.0, height as f32) as u32;
+        (x1 > x0 && y1 > y0).then(|| (x0, y0, x1 - x0, y1 - y0))
+    }
+}
@@ -0,0 +1,39 @@
+use crate::orbit::{Orbit, RecentIterates};
+use num_complex::Complex64;
+
+// exp overflows f64 just past 709; an exponent beyond this has escaped.
+const EXPONENT_LIMIT: f64 = 700.0;
+
+// The power tower c^c^c^... as the iteration z -> c^z from z = c. Instead of a
+// bailout on |z|, an orbit escapes once z ln c is too large to exponentiate, and
+// bounded orbits are classified by the period of the cycle they settle on.
+pub fn orbit(c: Complex64, max_iter: u32) -> Orbit {
+    let mut orbit = Orbit::new();
+    if c.norm_sqr() == 0.0 {
+        // 0^z = 0 for the first iterate onwards.
+        orbit.iterations = max_iter;
+        orbit.period = 1;
+        return orbit;
+    }
+    let log_c = c.ln();
+    let mut z = c;
+    let mut recent = RecentIterates::new();
+    for i in 0..max_iter {
+        let w = z * log_c;
+        if w.re >= EXPONENT_LIMIT || w.re.is_nan() {
+            orbit.iterations = i;
+            orbit.escaped = true;
+            orbit.final_z = z;
+            return orbit;
+        }
+        if i > 0 {
+            orbit.track(z, i);
+        }
+        recent.push(z);
+        z = w.exp();
+    }
+    orbit.iterations = max_iter;
+    orbit.final_z = z;
+    orbit.period = recent.period(z);
+    orbit
+}
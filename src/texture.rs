@@ -0,0 +1,59 @@
+use crate::color::srgb_to_linear;
+use image::{ImageBuffer, Rgb};
+use std::sync::Arc;
+
+// Which orbit coordinates pick the texel.
+#[derive(Clone, Copy, PartialEq)]
+pub enum TextureSource {
+    // Angle and smooth escape fraction of the escaping iterate, so the picture
+    // wraps once around each escape band.
+    FinalZ,
+    // Where the orbit came closest to the trap, relative to the trap center, so the
+    // picture appears wherever an orbit passes through the trap.
+    Trap,
+}
+
+impl TextureSource {
+    pub const ALL: [TextureSource; 2] = [TextureSource::FinalZ, TextureSource::Trap];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            TextureSource::FinalZ => "Final z",
+            TextureSource::Trap => "Orbit trap",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct TextureMapping {
+    pub source: TextureSource,
+    // Side of the square around the trap center the image covers.
+    pub size: f64,
+}
+
+impl Default for TextureMapping {
+    fn default() -> Self {
+        Self { source: TextureSource::FinalZ, size: 1.0 }
+    }
+}
+
+#[derive(Clone, Default)]
+pub struct TextureImage {
+    pub path: String,
+    pub image: Option<Arc<ImageBuffer<Rgb<u8>, Vec<u8>>>>,
+}
+
+impl TextureImage {
+    pub fn load(&mut self) -> image::ImageResult<()> {
+        self.image = Some(Arc::new(image::open(self.path.trim())?.to_rgb8()));
+        Ok(())
+    }
+
+    // Linear color at (u, v) in [0, 1)², nearest texel, with v = 0 at the top row.
+    pub fn sample(&self, u: f64, v: f64) -> Option<[f32; 3]> {
+        let img = self.image.as_ref().filter(|img| img.width() > 0 && img.height() > 0)?;
+        let x = ((u * img.width() as f64) as u32).min(img.width() - 1);
+        let y = ((v * img.height() as f64) as u32).min(img.height() - 1);
+        Some(img.get_pixel(x, y).0.map(|c| srgb_to_linear(c as f32 / 255.0)))
+    }
+}
@@ -0,0 +1,83 @@
+use crate::orbit::Orbit;
+use crate::postprocess::ColorBuffer;
+use parking_lot::Mutex;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Rows per tile. Tiles are queued to the thread pool, so a slow band near the set
+// does not hold up threads that drew through fast ones.
+pub const TILE_ROWS: u32 = 16;
+
+// Shared flag a render checks between rows, so a stale frame stops as soon as the
+// view it was started for changes. A default token is never cancelled.
+#[derive(Clone, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+// A finished preview: the colors and, when the view is a single pass over the base
+// fractal, the orbits behind them with their per-pixel step, kept for recoloring.
+pub struct RenderedFrame {
+    pub buffer: ColorBuffer,
+    pub orbits: Option<(Vec<Orbit>, (f64, f64))>,
+}
+
+// A preview rendering on a worker thread. Tiles of the base pass are posted as they
+// finish so the UI can show a partial frame; cancelling makes the remaining tiles
+// skip their work and the frame is never posted.
+pub struct RenderJob {
+    pub width: u32,
+    pub height: u32,
+    pub token: CancelToken,
+    // Colored bands not yet shown, by first row.
+    tiles: Mutex<Vec<(u32, ColorBuffer)>>,
+    frame: Mutex<Option<RenderedFrame>>,
+}
+
+impl RenderJob {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            token: CancelToken::default(),
+            tiles: Mutex::new(Vec::new()),
+            frame: Mutex::new(None),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.token.cancel();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    pub fn post_tile(&self, y: u32, tile: ColorBuffer) {
+        if !self.is_cancelled() {
+            self.tiles.lock().push((y, tile));
+        }
+    }
+
+    pub fn post_frame(&self, frame: RenderedFrame) {
+        if !self.is_cancelled() {
+            *self.frame.lock() = Some(frame);
+        }
+    }
+
+    pub fn take_tiles(&self) -> Vec<(u32, ColorBuffer)> {
+        std::mem::take(&mut *self.tiles.lock())
+    }
+
+    pub fn take_frame(&self) -> Option<RenderedFrame> {
+        self.frame.lock().take()
+    }
+}
@@ -0,0 +1,54 @@
+use display_info::DisplayInfo;
+use image::{imageops, ImageBuffer, Rgb};
+
+#[derive(Clone)]
+pub struct Monitor {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub primary: bool,
+}
+
+// Geometry is taken as the OS reports it; scaled displays may need manual correction.
+pub fn detect_monitors() -> Vec<Monitor> {
+    DisplayInfo::all()
+        .map(|displays| {
+            displays
+                .into_iter()
+                .map(|d| Monitor {
+                    x: d.x,
+                    y: d.y,
+                    width: d.width,
+                    height: d.height,
+                    primary: d.is_primary,
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// Bounding box of the whole desktop as (left, top, width, height).
+pub fn desktop_bounds(monitors: &[Monitor]) -> Option<(i32, i32, u32, u32)> {
+    let left = monitors.iter().map(|m| m.x).min()?;
+    let top = monitors.iter().map(|m| m.y).min()?;
+    let right = monitors.iter().map(|m| m.x + m.width as i32).max()?;
+    let bottom = monitors.iter().map(|m| m.y + m.height as i32).max()?;
+    Some((left, top, (right - left) as u32, (bottom - top) as u32))
+}
+
+pub fn crop(
+    desktop: &ImageBuffer<Rgb<u8>, Vec<u8>>,
+    monitor: &Monitor,
+    left: i32,
+    top: i32,
+) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+    imageops::crop_imm(
+        desktop,
+        (monitor.x - left) as u32,
+        (monitor.y - top) as u32,
+        monitor.width,
+        monitor.height,
+    )
+    .to_image()
+}